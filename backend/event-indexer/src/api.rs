@@ -0,0 +1,113 @@
+//! HTTP/JSON API over the indexed events
+//!
+//! | Route | Response |
+//! |-------|----------|
+//! | `GET /health` | Sync state |
+//! | `GET /users/{address}/history?limit=N` | Completed zaps and zap-outs, newest first |
+//! | `GET /zaps/{zap_id}` | Every event of one zap, in order |
+//! | `GET /volumes?since_ledger=N` | Zap volume per token |
+//! | `GET /vaults/{address}/flows` | Deposits and withdrawals through the vault |
+
+use serde_json::{json, Value};
+
+use crate::error::Result;
+use crate::store::Store;
+
+pub const DEFAULT_HISTORY_LIMIT: u32 = 50;
+pub const MAX_HISTORY_LIMIT: u32 = 500;
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Response {
+    pub status: u16,
+    pub body: Value,
+}
+
+impl Response {
+    fn ok(body: Value) -> Self {
+        Self { status: 200, body }
+    }
+
+    fn error(status: u16, message: &str) -> Self {
+        Self { status, body: json!({ "error": message }) }
+    }
+}
+
+/// Routes one request. Kept free of any HTTP server type so it can be tested directly.
+pub fn handle(store: &Store, method: &str, url: &str) -> Response {
+    if method != "GET" {
+        return Response::error(405, "method not allowed");
+    }
+    let (path, query) = url.split_once('?').unwrap_or((url, ""));
+    let segments: Vec<&str> = path.trim_matches('/').split('/').collect();
+
+    let result = match segments.as_slice() {
+        ["health"] => store.sync_state().map(|state| json!({ "status": "ok", "sync": state })),
+        ["users", user, "history"] => {
+            let limit = match param(query, "limit") {
+                Some(limit) => match limit.parse::<u32>() {
+                    Ok(limit) => limit.min(MAX_HISTORY_LIMIT),
+                    Err(_) => return Response::error(400, "invalid limit"),
+                },
+                None => DEFAULT_HISTORY_LIMIT,
+            };
+            to_json(store.user_history(user, limit))
+        }
+        ["zaps", zap_id] => match zap_id.parse::<u64>() {
+            Ok(zap_id) => match store.zap(zap_id) {
+                Ok(events) if events.is_empty() => return Response::error(404, "zap not found"),
+                events => to_json(events),
+            },
+            Err(_) => return Response::error(400, "invalid zap id"),
+        },
+        ["volumes"] => {
+            let since_ledger = match param(query, "since_ledger").map(str::parse::<u32>) {
+                Some(Ok(ledger)) => ledger,
+                Some(Err(_)) => return Response::error(400, "invalid since_ledger"),
+                None => 0,
+            };
+            to_json(store.volumes(since_ledger))
+        }
+        ["vaults", vault, "flows"] => to_json(store.vault_flows(vault)),
+        _ => return Response::error(404, "not found"),
+    };
+
+    match result {
+        Ok(body) => Response::ok(body),
+        Err(err) => {
+            eprintln!("{method} {url} failed: {err}");
+            Response::error(500, "internal error")
+        }
+    }
+}
+
+fn to_json<T: serde::Serialize>(value: Result<T>) -> Result<Value> {
+    Ok(serde_json::to_value(value?)?)
+}
+
+fn param<'a>(query: &'a str, name: &str) -> Option<&'a str> {
+    query
+        .split('&')
+        .filter_map(|pair| pair.split_once('='))
+        .find(|(key, _)| *key == name)
+        .map(|(_, value)| value)
+}
+
+/// Serves the API until the process exits
+pub fn serve(store: &Store, addr: &str) -> Result<()> {
+    let server = tiny_http::Server::http(addr)
+        .map_err(|err| std::io::Error::new(std::io::ErrorKind::AddrNotAvailable, err))?;
+    eprintln!("listening on http://{addr}");
+
+    let content_type = tiny_http::Header::from_bytes("Content-Type", "application/json")
+        .expect("static header is valid");
+    for request in server.incoming_requests() {
+        let response = handle(store, request.method().as_str(), request.url());
+        let reply = tiny_http::Response::from_string(response.body.to_string())
+            .with_status_code(response.status)
+            .with_header(content_type.clone());
+        if let Err(err) = request.respond(reply) {
+            eprintln!("failed to respond: {err}");
+        }
+    }
+    Ok(())
+}
@@ -0,0 +1,282 @@
+//! Decodes the Zap contract's versioned events into typed records
+//!
+//! Zap events have the topics `(name, user, vault)` and a `#[contracttype]` struct as data, which
+//! arrives as an `ScMap` keyed by field name. See "Events" in docs/contract-integration.md.
+
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use stellar_xdr::curr::{
+    AccountId, Limits, PublicKey, ReadXdr, ScAddress, ScVal, Uint256,
+};
+
+use crate::error::{Error, Result};
+use crate::rpc::RpcEvent;
+
+/// The event schema version this indexer understands
+pub const EVENT_VERSION: u32 = 1;
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Swap {
+    pub token_in: String,
+    pub token_out: String,
+    #[serde(with = "amount")]
+    pub amount_in: i128,
+    #[serde(with = "amount")]
+    pub amount_out: i128,
+    pub price_impact_bps: u32,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Deposit {
+    pub asset: String,
+    #[serde(with = "amount")]
+    pub amount: i128,
+    pub receiver: String,
+    #[serde(with = "amount")]
+    pub shares: i128,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Withdraw {
+    #[serde(with = "amount")]
+    pub shares: i128,
+    #[serde(with = "amount")]
+    pub assets: i128,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ZapCompleted {
+    pub receiver: String,
+    pub from_token: String,
+    #[serde(with = "amount")]
+    pub amount_in: i128,
+    pub to_token: String,
+    #[serde(with = "amount")]
+    pub amount_swapped: i128,
+    #[serde(with = "amount")]
+    pub vault_shares: i128,
+    pub price_impact_bps: u32,
+    #[serde(with = "amount")]
+    pub protocol_fee: i128,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ZapOutCompleted {
+    pub vault_asset: String,
+    pub to_token: String,
+    #[serde(with = "amount")]
+    pub shares_burned: i128,
+    #[serde(with = "amount")]
+    pub assets_withdrawn: i128,
+    #[serde(with = "amount")]
+    pub amount_out: i128,
+    pub price_impact_bps: u32,
+    #[serde(with = "amount")]
+    pub protocol_fee: i128,
+}
+
+/// A decoded zap event, tagged with the contract's event name
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "event")]
+pub enum ZapEvent {
+    #[serde(rename = "soroswap_swap")]
+    Swap(Swap),
+    #[serde(rename = "defindex_deposit")]
+    Deposit(Deposit),
+    #[serde(rename = "defindex_withdraw")]
+    Withdraw(Withdraw),
+    #[serde(rename = "zap_completed")]
+    ZapCompleted(ZapCompleted),
+    #[serde(rename = "zap_out_completed")]
+    ZapOutCompleted(ZapOutCompleted),
+}
+
+impl ZapEvent {
+    pub fn name(&self) -> &'static str {
+        match self {
+            ZapEvent::Swap(_) => "soroswap_swap",
+            ZapEvent::Deposit(_) => "defindex_deposit",
+            ZapEvent::Withdraw(_) => "defindex_withdraw",
+            ZapEvent::ZapCompleted(_) => "zap_completed",
+            ZapEvent::ZapOutCompleted(_) => "zap_out_completed",
+        }
+    }
+}
+
+/// A zap event with where and when it was published
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct IndexedEvent {
+    pub id: String,
+    pub ledger: u32,
+    pub closed_at: String,
+    pub tx_hash: String,
+    pub user: String,
+    pub vault: String,
+    pub zap_id: u64,
+    pub version: u32,
+    #[serde(flatten)]
+    pub event: ZapEvent,
+}
+
+const ZAP_EVENTS: [&str; 5] = [
+    "soroswap_swap",
+    "defindex_deposit",
+    "defindex_withdraw",
+    "zap_completed",
+    "zap_out_completed",
+];
+
+/// Decodes one RPC event. Returns `None` for events the indexer does not track: other Zap events
+/// (DCA, limit zaps, admin) and events from failed contract calls.
+pub fn decode(raw: &RpcEvent) -> Result<Option<IndexedEvent>> {
+    if raw.kind != "contract" || !raw.in_successful_contract_call || raw.topic.len() != 3 {
+        return Ok(None);
+    }
+    let name = match ScVal::from_xdr_base64(&raw.topic[0], Limits::none())? {
+        ScVal::Symbol(symbol) => symbol.to_utf8_string_lossy(),
+        _ => return Ok(None),
+    };
+    if !ZAP_EVENTS.contains(&name.as_str()) {
+        return Ok(None);
+    }
+
+    let user = address(&ScVal::from_xdr_base64(&raw.topic[1], Limits::none())?, "user")?;
+    let vault = address(&ScVal::from_xdr_base64(&raw.topic[2], Limits::none())?, "vault")?;
+    let fields = Fields::parse(&name, ScVal::from_xdr_base64(&raw.value, Limits::none())?)?;
+
+    let version = fields.u32("version")?;
+    if version != EVENT_VERSION {
+        return Err(Error::UnsupportedVersion { event: name, version });
+    }
+
+    let event = match name.as_str() {
+        "soroswap_swap" => ZapEvent::Swap(Swap {
+            token_in: fields.address("token_in")?,
+            token_out: fields.address("token_out")?,
+            amount_in: fields.i128("amount_in")?,
+            amount_out: fields.i128("amount_out")?,
+            price_impact_bps: fields.u32("price_impact_bps")?,
+        }),
+        "defindex_deposit" => ZapEvent::Deposit(Deposit {
+            asset: fields.address("asset")?,
+            amount: fields.i128("amount")?,
+            receiver: fields.address("receiver")?,
+            shares: fields.i128("shares")?,
+        }),
+        "defindex_withdraw" => ZapEvent::Withdraw(Withdraw {
+            shares: fields.i128("shares")?,
+            assets: fields.i128("assets")?,
+        }),
+        "zap_completed" => ZapEvent::ZapCompleted(ZapCompleted {
+            receiver: fields.address("receiver")?,
+            from_token: fields.address("from_token")?,
+            amount_in: fields.i128("amount_in")?,
+            to_token: fields.address("to_token")?,
+            amount_swapped: fields.i128("amount_swapped")?,
+            vault_shares: fields.i128("vault_shares")?,
+            price_impact_bps: fields.u32("price_impact_bps")?,
+            protocol_fee: fields.i128("protocol_fee")?,
+        }),
+        _ => ZapEvent::ZapOutCompleted(ZapOutCompleted {
+            vault_asset: fields.address("vault_asset")?,
+            to_token: fields.address("to_token")?,
+            shares_burned: fields.i128("shares_burned")?,
+            assets_withdrawn: fields.i128("assets_withdrawn")?,
+            amount_out: fields.i128("amount_out")?,
+            price_impact_bps: fields.u32("price_impact_bps")?,
+            protocol_fee: fields.i128("protocol_fee")?,
+        }),
+    };
+
+    Ok(Some(IndexedEvent {
+        id: raw.id.clone(),
+        ledger: raw.ledger,
+        closed_at: raw.ledger_closed_at.clone(),
+        tx_hash: raw.tx_hash.clone(),
+        user,
+        vault,
+        zap_id: fields.u64("zap_id")?,
+        version,
+        event,
+    }))
+}
+
+/// Renders an `ScAddress` as its strkey (`G...` or `C...`)
+fn address(val: &ScVal, field: &str) -> Result<String> {
+    match val {
+        ScVal::Address(ScAddress::Account(AccountId(PublicKey::PublicKeyTypeEd25519(
+            Uint256(key),
+        )))) => Ok(stellar_strkey::ed25519::PublicKey(*key).to_string()),
+        ScVal::Address(ScAddress::Contract(hash)) => Ok(stellar_strkey::Contract(hash.0).to_string()),
+        _ => Err(Error::Decode(format!("{field} is not an address"))),
+    }
+}
+
+/// The fields of a `#[contracttype]` struct
+struct Fields<'a> {
+    event: &'a str,
+    values: BTreeMap<String, ScVal>,
+}
+
+impl<'a> Fields<'a> {
+    fn parse(event: &'a str, val: ScVal) -> Result<Self> {
+        let ScVal::Map(Some(map)) = val else {
+            return Err(Error::Decode(format!("{event} data is not a struct")));
+        };
+        let mut values = BTreeMap::new();
+        for entry in map.0.iter() {
+            if let ScVal::Symbol(key) = &entry.key {
+                values.insert(key.to_utf8_string_lossy(), entry.val.clone());
+            }
+        }
+        Ok(Self { event, values })
+    }
+
+    fn get(&self, field: &str) -> Result<&ScVal> {
+        self.values
+            .get(field)
+            .ok_or_else(|| Error::Decode(format!("{} is missing {field}", self.event)))
+    }
+
+    fn mistyped(&self, field: &str) -> Error {
+        Error::Decode(format!("{}.{field} has the wrong type", self.event))
+    }
+
+    fn address(&self, field: &str) -> Result<String> {
+        address(self.get(field)?, &format!("{}.{field}", self.event))
+    }
+
+    fn i128(&self, field: &str) -> Result<i128> {
+        match self.get(field)? {
+            ScVal::I128(parts) => Ok(((parts.hi as i128) << 64) | parts.lo as i128),
+            _ => Err(self.mistyped(field)),
+        }
+    }
+
+    fn u32(&self, field: &str) -> Result<u32> {
+        match self.get(field)? {
+            ScVal::U32(v) => Ok(*v),
+            _ => Err(self.mistyped(field)),
+        }
+    }
+
+    fn u64(&self, field: &str) -> Result<u64> {
+        match self.get(field)? {
+            ScVal::U64(v) => Ok(*v),
+            _ => Err(self.mistyped(field)),
+        }
+    }
+}
+
+/// Serializes `i128` amounts as decimal strings; JSON numbers lose precision past 2^53
+pub mod amount {
+    use serde::{de::Error, Deserialize, Deserializer, Serializer};
+
+    pub fn serialize<S: Serializer>(value: &i128, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(&value.to_string())
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<i128, D::Error> {
+        String::deserialize(deserializer)?.parse().map_err(D::Error::custom)
+    }
+}
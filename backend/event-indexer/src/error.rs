@@ -0,0 +1,62 @@
+use std::fmt;
+
+pub type Result<T> = std::result::Result<T, Error>;
+
+#[derive(Debug)]
+pub enum Error {
+    /// The RPC request failed or returned a JSON-RPC error
+    Rpc(String),
+    Json(serde_json::Error),
+    Xdr(stellar_xdr::curr::Error),
+    Db(rusqlite::Error),
+    Io(std::io::Error),
+    /// An event had the shape of a zap event but a field was missing or mistyped
+    Decode(String),
+    /// The contract published a schema this indexer does not know yet
+    UnsupportedVersion { event: String, version: u32 },
+    /// An aggregate over the stored events does not fit in an i128
+    Overflow(String),
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Error::Rpc(msg) => write!(f, "rpc error: {msg}"),
+            Error::Json(err) => write!(f, "json error: {err}"),
+            Error::Xdr(err) => write!(f, "xdr error: {err}"),
+            Error::Db(err) => write!(f, "database error: {err}"),
+            Error::Io(err) => write!(f, "io error: {err}"),
+            Error::Decode(msg) => write!(f, "decode error: {msg}"),
+            Error::UnsupportedVersion { event, version } => {
+                write!(f, "unsupported {event} event version {version}")
+            }
+            Error::Overflow(what) => write!(f, "{what} overflowed"),
+        }
+    }
+}
+
+impl std::error::Error for Error {}
+
+impl From<serde_json::Error> for Error {
+    fn from(err: serde_json::Error) -> Self {
+        Error::Json(err)
+    }
+}
+
+impl From<stellar_xdr::curr::Error> for Error {
+    fn from(err: stellar_xdr::curr::Error) -> Self {
+        Error::Xdr(err)
+    }
+}
+
+impl From<rusqlite::Error> for Error {
+    fn from(err: rusqlite::Error) -> Self {
+        Error::Db(err)
+    }
+}
+
+impl From<std::io::Error> for Error {
+    fn from(err: std::io::Error) -> Self {
+        Error::Io(err)
+    }
+}
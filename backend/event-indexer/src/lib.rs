@@ -0,0 +1,15 @@
+//! Indexes the YieldZap contract's events into SQLite and serves them over HTTP
+
+pub mod api;
+pub mod decode;
+mod error;
+pub mod rpc;
+pub mod store;
+pub mod sync;
+
+pub use decode::{decode, IndexedEvent, ZapEvent, EVENT_VERSION};
+pub use error::{Error, Result};
+pub use store::Store;
+
+#[cfg(test)]
+mod test;
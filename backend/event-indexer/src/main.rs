@@ -0,0 +1,101 @@
+use std::collections::HashMap;
+use std::process::exit;
+use std::thread;
+use std::time::Duration;
+
+use event_indexer::rpc::{self, RpcClient};
+use event_indexer::sync::{self, Syncer};
+use event_indexer::{api, Result, Store};
+
+const USAGE: &str = "usage:
+  event-indexer import --fixture FILE [--db PATH]
+  event-indexer sync   --rpc URL --contract C... [--start-ledger N] [--interval SECS] [--db PATH]
+  event-indexer serve  [--addr HOST:PORT] [--db PATH]
+  event-indexer run    --rpc URL --contract C... [--start-ledger N] [--interval SECS] [--addr HOST:PORT] [--db PATH]";
+
+fn main() {
+    let mut args = std::env::args().skip(1);
+    let command = args.next().unwrap_or_default();
+    let flags = match parse_flags(args) {
+        Some(flags) => flags,
+        None => usage(),
+    };
+    if let Err(err) = run(&command, &flags) {
+        eprintln!("error: {err}");
+        exit(1);
+    }
+}
+
+fn run(command: &str, flags: &HashMap<String, String>) -> Result<()> {
+    let db = flags.get("db").map(String::as_str).unwrap_or("yieldzap-events.db");
+    let addr = flags.get("addr").map(String::as_str).unwrap_or("127.0.0.1:8080");
+
+    match command {
+        "import" => {
+            let page = rpc::load_fixture(required(flags, "fixture"))?;
+            let stored = sync::ingest(&mut Store::open(db)?, &page)?;
+            eprintln!("imported {stored} of {} events", page.events.len());
+            Ok(())
+        }
+        "sync" => syncer(flags).run(&mut Store::open(db)?),
+        "serve" => api::serve(&Store::open(db)?, addr),
+        "run" => {
+            let syncer = syncer(flags);
+            let mut store = Store::open(db)?;
+            let api_store = Store::open(db)?;
+            thread::spawn(move || {
+                if let Err(err) = syncer.run(&mut store) {
+                    eprintln!("sync stopped: {err}");
+                    exit(1);
+                }
+            });
+            api::serve(&api_store, addr)
+        }
+        _ => usage(),
+    }
+}
+
+fn syncer(flags: &HashMap<String, String>) -> Syncer {
+    Syncer {
+        rpc: RpcClient::new(required(flags, "rpc")),
+        contract: required(flags, "contract").to_string(),
+        start_ledger: number(flags, "start-ledger", 0),
+        interval: Duration::from_secs(number(flags, "interval", 5)),
+    }
+}
+
+/// Parses `--key value` pairs
+fn parse_flags(mut args: impl Iterator<Item = String>) -> Option<HashMap<String, String>> {
+    let mut flags = HashMap::new();
+    while let Some(key) = args.next() {
+        let key = key.strip_prefix("--")?.to_string();
+        flags.insert(key, args.next()?);
+    }
+    Some(flags)
+}
+
+fn required<'a>(flags: &'a HashMap<String, String>, name: &str) -> &'a str {
+    match flags.get(name) {
+        Some(value) => value,
+        None => {
+            eprintln!("missing --{name}");
+            usage()
+        }
+    }
+}
+
+fn number<T: std::str::FromStr>(flags: &HashMap<String, String>, name: &str, default: T) -> T {
+    match flags.get(name).map(|value| value.parse()) {
+        Some(Ok(value)) => value,
+        Some(Err(_)) => {
+            eprintln!("--{name} must be a number");
+            usage()
+        }
+        None => default,
+    }
+}
+
+fn usage() -> ! {
+    eprintln!("{USAGE}");
+    exit(2)
+}
@@ -0,0 +1,247 @@
+//! SQLite storage for indexed events and the aggregates the API serves
+
+use rusqlite::{params, Connection, OptionalExtension, Row};
+use serde::Serialize;
+use std::collections::BTreeMap;
+use std::path::Path;
+use std::time::Duration;
+
+use crate::decode::{amount, IndexedEvent, ZapEvent};
+use crate::error::{Error, Result};
+
+/// How long a statement waits on a lock held by the other connection before failing
+const BUSY_TIMEOUT: Duration = Duration::from_secs(5);
+
+const SCHEMA: &str = "
+CREATE TABLE IF NOT EXISTS events (
+    id TEXT PRIMARY KEY,
+    ledger INTEGER NOT NULL,
+    closed_at TEXT NOT NULL,
+    tx_hash TEXT NOT NULL,
+    name TEXT NOT NULL,
+    user TEXT NOT NULL,
+    vault TEXT NOT NULL,
+    zap_id INTEGER NOT NULL,
+    version INTEGER NOT NULL,
+    data TEXT NOT NULL
+);
+CREATE INDEX IF NOT EXISTS events_by_user ON events (user, name, ledger);
+CREATE INDEX IF NOT EXISTS events_by_vault ON events (vault, name);
+CREATE INDEX IF NOT EXISTS events_by_zap ON events (zap_id);
+CREATE TABLE IF NOT EXISTS sync_state (
+    id INTEGER PRIMARY KEY CHECK (id = 0),
+    cursor TEXT NOT NULL,
+    latest_ledger INTEGER NOT NULL
+);
+";
+
+/// Where ingestion stopped, so a restarted indexer resumes without gaps
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct SyncState {
+    pub cursor: String,
+    pub latest_ledger: u32,
+}
+
+/// Zap volume for one token. Zaps count `amount_in` of `from_token`, zap-outs `amount_out` of
+/// `to_token`.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize)]
+pub struct TokenVolume {
+    pub token: String,
+    pub zaps: u64,
+    #[serde(with = "amount")]
+    pub zapped_in: i128,
+    pub zap_outs: u64,
+    #[serde(with = "amount")]
+    pub zapped_out: i128,
+}
+
+/// Asset and share flows through one vault via the Zap
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize)]
+pub struct VaultFlows {
+    pub vault: String,
+    pub deposits: u64,
+    #[serde(with = "amount")]
+    pub assets_deposited: i128,
+    #[serde(with = "amount")]
+    pub shares_minted: i128,
+    pub withdrawals: u64,
+    #[serde(with = "amount")]
+    pub assets_withdrawn: i128,
+    #[serde(with = "amount")]
+    pub shares_burned: i128,
+    #[serde(with = "amount")]
+    pub net_assets: i128,
+}
+
+pub struct Store {
+    conn: Connection,
+}
+
+impl Store {
+    /// Opens a database file. `run` holds one connection for the syncer and one for the API, so
+    /// the file uses WAL to let reads proceed during writes, and waits out any remaining locks.
+    pub fn open(path: impl AsRef<Path>) -> Result<Self> {
+        let conn = Connection::open(path)?;
+        conn.busy_timeout(BUSY_TIMEOUT)?;
+        conn.pragma_update(None, "journal_mode", "WAL")?;
+        Self::init(conn)
+    }
+
+    pub fn in_memory() -> Result<Self> {
+        Self::init(Connection::open_in_memory()?)
+    }
+
+    fn init(conn: Connection) -> Result<Self> {
+        conn.execute_batch(SCHEMA)?;
+        Ok(Self { conn })
+    }
+
+    /// Stores a page of events and the cursor after it in one transaction. Events already stored
+    /// are skipped, so replaying a page is harmless. Returns how many events were new.
+    pub fn insert_page(&mut self, events: &[IndexedEvent], state: &SyncState) -> Result<usize> {
+        let tx = self.conn.transaction()?;
+        let mut inserted = 0;
+        {
+            let mut stmt = tx.prepare_cached(
+                "INSERT OR IGNORE INTO events
+                 (id, ledger, closed_at, tx_hash, name, user, vault, zap_id, version, data)
+                 VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10)",
+            )?;
+            for e in events {
+                inserted += stmt.execute(params![
+                    e.id,
+                    e.ledger,
+                    e.closed_at,
+                    e.tx_hash,
+                    e.event.name(),
+                    e.user,
+                    e.vault,
+                    e.zap_id as i64,
+                    e.version,
+                    serde_json::to_string(&e.event)?,
+                ])?;
+            }
+        }
+        tx.execute(
+            "INSERT INTO sync_state (id, cursor, latest_ledger) VALUES (0, ?1, ?2)
+             ON CONFLICT (id) DO UPDATE SET cursor = ?1, latest_ledger = ?2",
+            params![state.cursor, state.latest_ledger],
+        )?;
+        tx.commit()?;
+        Ok(inserted)
+    }
+
+    pub fn sync_state(&self) -> Result<Option<SyncState>> {
+        Ok(self
+            .conn
+            .query_row("SELECT cursor, latest_ledger FROM sync_state WHERE id = 0", [], |row| {
+                Ok(SyncState { cursor: row.get(0)?, latest_ledger: row.get(1)? })
+            })
+            .optional()?)
+    }
+
+    /// A user's completed zaps and zap-outs, newest first
+    pub fn user_history(&self, user: &str, limit: u32) -> Result<Vec<IndexedEvent>> {
+        self.query(
+            "SELECT * FROM events
+             WHERE user = ?1 AND name IN ('zap_completed', 'zap_out_completed')
+             ORDER BY ledger DESC, id DESC LIMIT ?2",
+            params![user, limit],
+        )
+    }
+
+    /// Every event of one zap, in publication order
+    pub fn zap(&self, zap_id: u64) -> Result<Vec<IndexedEvent>> {
+        self.query("SELECT * FROM events WHERE zap_id = ?1 ORDER BY id", params![zap_id as i64])
+    }
+
+    /// Volume per token since `since_ledger` (inclusive), ordered by token
+    pub fn volumes(&self, since_ledger: u32) -> Result<Vec<TokenVolume>> {
+        let events = self.query(
+            "SELECT * FROM events
+             WHERE name IN ('zap_completed', 'zap_out_completed') AND ledger >= ?1",
+            params![since_ledger],
+        )?;
+
+        let mut volumes: BTreeMap<String, TokenVolume> = BTreeMap::new();
+        for e in events {
+            match e.event {
+                ZapEvent::ZapCompleted(zap) => {
+                    let volume = volumes.entry(zap.from_token.clone()).or_default();
+                    volume.zaps += 1;
+                    add(&mut volume.zapped_in, zap.amount_in, "zapped_in")?;
+                }
+                ZapEvent::ZapOutCompleted(zap_out) => {
+                    let volume = volumes.entry(zap_out.to_token.clone()).or_default();
+                    volume.zap_outs += 1;
+                    add(&mut volume.zapped_out, zap_out.amount_out, "zapped_out")?;
+                }
+                _ => {}
+            }
+        }
+        Ok(volumes
+            .into_iter()
+            .map(|(token, volume)| TokenVolume { token, ..volume })
+            .collect())
+    }
+
+    pub fn vault_flows(&self, vault: &str) -> Result<VaultFlows> {
+        let events = self.query(
+            "SELECT * FROM events WHERE vault = ?1 AND name IN ('defindex_deposit', 'defindex_withdraw')",
+            params![vault],
+        )?;
+
+        let mut flows = VaultFlows { vault: vault.to_string(), ..Default::default() };
+        for e in events {
+            match e.event {
+                ZapEvent::Deposit(deposit) => {
+                    flows.deposits += 1;
+                    add(&mut flows.assets_deposited, deposit.amount, "assets_deposited")?;
+                    add(&mut flows.shares_minted, deposit.shares, "shares_minted")?;
+                }
+                ZapEvent::Withdraw(withdraw) => {
+                    flows.withdrawals += 1;
+                    add(&mut flows.assets_withdrawn, withdraw.assets, "assets_withdrawn")?;
+                    add(&mut flows.shares_burned, withdraw.shares, "shares_burned")?;
+                }
+                _ => {}
+            }
+        }
+        flows.net_assets = flows
+            .assets_deposited
+            .checked_sub(flows.assets_withdrawn)
+            .ok_or_else(|| Error::Overflow("net_assets".into()))?;
+        Ok(flows)
+    }
+
+    fn query(&self, sql: &str, params: impl rusqlite::Params) -> Result<Vec<IndexedEvent>> {
+        let mut stmt = self.conn.prepare_cached(sql)?;
+        let rows = stmt.query_map(params, |row| Ok(Self::row(row)))?;
+        let mut events = Vec::new();
+        for row in rows {
+            events.push(row??);
+        }
+        Ok(events)
+    }
+
+    fn row(row: &Row) -> Result<IndexedEvent> {
+        let data: String = row.get("data")?;
+        Ok(IndexedEvent {
+            id: row.get("id")?,
+            ledger: row.get("ledger")?,
+            closed_at: row.get("closed_at")?,
+            tx_hash: row.get("tx_hash")?,
+            user: row.get("user")?,
+            vault: row.get("vault")?,
+            zap_id: row.get::<_, i64>("zap_id")? as u64,
+            version: row.get("version")?,
+            event: serde_json::from_str(&data)?,
+        })
+    }
+}
+
+/// Adds `amount` to a running total, naming the total if it overflows
+fn add(total: &mut i128, amount: i128, what: &str) -> Result<()> {
+    *total = total.checked_add(amount).ok_or_else(|| Error::Overflow(what.into()))?;
+    Ok(())
+}
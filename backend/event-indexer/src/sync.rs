@@ -0,0 +1,74 @@
+//! Pulls Zap events from the RPC into the store
+
+use std::thread;
+use std::time::Duration;
+
+use crate::decode::decode;
+use crate::error::Result;
+use crate::rpc::{GetEventsResult, RpcClient, Start};
+use crate::store::{Store, SyncState};
+
+/// Events requested per `getEvents` page
+pub const PAGE_LIMIT: u32 = 200;
+
+/// Decodes and stores one `getEvents` page. Returns how many new zap events were stored.
+pub fn ingest(store: &mut Store, page: &GetEventsResult) -> Result<usize> {
+    let mut events = Vec::new();
+    for raw in &page.events {
+        if let Some(event) = decode(raw)? {
+            events.push(event);
+        }
+    }
+
+    let previous = store.sync_state()?;
+    let cursor = match page.next_cursor() {
+        Some(cursor) => cursor,
+        // An empty page without a cursor leaves the resume point where it was
+        None => match previous {
+            Some(state) => state.cursor,
+            None => return Ok(0),
+        },
+    };
+    store.insert_page(&events, &SyncState { cursor, latest_ledger: page.latest_ledger })
+}
+
+pub struct Syncer {
+    pub rpc: RpcClient,
+    pub contract: String,
+    /// Used until the store has a cursor to resume from
+    pub start_ledger: u32,
+    pub interval: Duration,
+}
+
+impl Syncer {
+    /// Fetches pages until the RPC has nothing new. Returns how many events were stored.
+    pub fn catch_up(&self, store: &mut Store) -> Result<usize> {
+        let mut total = 0;
+        loop {
+            let start = match store.sync_state()? {
+                Some(state) => Start::Cursor(state.cursor),
+                None => Start::Ledger(self.start_ledger),
+            };
+            let page = self.rpc.get_events(&self.contract, &start, PAGE_LIMIT)?;
+            let last_page = page.events.len() < PAGE_LIMIT as usize;
+            total += ingest(store, &page)?;
+            if last_page {
+                return Ok(total);
+            }
+        }
+    }
+
+    /// Polls forever. RPC errors are logged and retried on the next tick; a decode error stops the
+    /// loop, since skipping past it would leave a gap in the index.
+    pub fn run(&self, store: &mut Store) -> Result<()> {
+        loop {
+            match self.catch_up(store) {
+                Ok(0) => {}
+                Ok(n) => eprintln!("indexed {n} events"),
+                Err(crate::Error::Rpc(err)) => eprintln!("rpc error, retrying: {err}"),
+                Err(err) => return Err(err),
+            }
+            thread::sleep(self.interval);
+        }
+    }
+}
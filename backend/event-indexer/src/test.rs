@@ -0,0 +1,292 @@
+use serde_json::json;
+use stellar_xdr::curr::{Limits, ScMap, ScMapEntry, ScSymbol, ScVal, WriteXdr};
+
+use crate::api::handle;
+use crate::decode::{Deposit, Swap, ZapCompleted};
+use crate::rpc::{load_fixture, parse_response, GetEventsResult, RpcEvent};
+use crate::store::SyncState;
+use crate::sync::ingest;
+use crate::{decode, Error, Store, ZapEvent};
+
+// Addresses used by fixtures/zap_events.json
+const ALICE: &str = "GAAQCAIBAEAQCAIBAEAQCAIBAEAQCAIBAEAQCAIBAEAQCAIBAEAQDZ7H";
+const BOB: &str = "GABAEAQCAIBAEAQCAIBAEAQCAIBAEAQCAIBAEAQCAIBAEAQCAIBAEJXA";
+const VAULT: &str = "CADQOBYHA4DQOBYHA4DQOBYHA4DQOBYHA4DQOBYHA4DQOBYHA4DQP5KR";
+const XLM: &str = "CACAIBAEAQCAIBAEAQCAIBAEAQCAIBAEAQCAIBAEAQCAIBAEAQCAINCW";
+const USDC: &str = "CABQGAYDAMBQGAYDAMBQGAYDAMBQGAYDAMBQGAYDAMBQGAYDAMBQGCK3";
+
+fn fixture() -> GetEventsResult {
+    load_fixture(concat!(env!("CARGO_MANIFEST_DIR"), "/fixtures/zap_events.json")).unwrap()
+}
+
+fn indexed_store() -> Store {
+    let mut store = Store::in_memory().unwrap();
+    ingest(&mut store, &fixture()).unwrap();
+    store
+}
+
+fn get(store: &Store, url: &str) -> serde_json::Value {
+    let response = handle(store, "GET", url);
+    assert_eq!(response.status, 200, "{url}: {}", response.body);
+    response.body
+}
+
+fn b64(val: ScVal) -> String {
+    val.to_xdr_base64(Limits::none()).unwrap()
+}
+
+fn symbol(name: &str) -> ScVal {
+    ScVal::Symbol(ScSymbol(name.try_into().unwrap()))
+}
+
+#[test]
+fn test_decode_fixture() {
+    let page = fixture();
+    assert_eq!(page.events.len(), 9);
+
+    let events: Vec<_> = page.events.iter().filter_map(|e| decode(e).unwrap()).collect();
+    // The untracked `dca_created` event is skipped
+    assert_eq!(events.len(), 8);
+
+    let swap = &events[0];
+    assert_eq!((swap.user.as_str(), swap.vault.as_str(), swap.zap_id), (ALICE, VAULT, 0));
+    assert_eq!(swap.ledger, 1000);
+    assert_eq!(
+        swap.event,
+        ZapEvent::Swap(Swap {
+            token_in: XLM.into(),
+            token_out: USDC.into(),
+            amount_in: 10_000_000_000,
+            amount_out: 1_200_000_000,
+            price_impact_bps: 15,
+        })
+    );
+    assert_eq!(
+        events[1].event,
+        ZapEvent::Deposit(Deposit {
+            asset: USDC.into(),
+            amount: 1_200_000_000,
+            receiver: ALICE.into(),
+            shares: 1_180_000_000,
+        })
+    );
+    let names: Vec<_> = events.iter().map(|e| (e.event.name(), e.zap_id)).collect();
+    assert_eq!(
+        names,
+        [
+            ("soroswap_swap", 0),
+            ("defindex_deposit", 0),
+            ("zap_completed", 0),
+            ("defindex_deposit", 1),
+            ("zap_completed", 1),
+            ("defindex_withdraw", 2),
+            ("soroswap_swap", 2),
+            ("zap_out_completed", 2),
+        ]
+    );
+}
+
+#[test]
+fn test_decode_rejects_unknown_version() {
+    let mut raw = fixture().events[0].clone();
+    let value = ScVal::Map(Some(ScMap(
+        vec![ScMapEntry { key: symbol("version"), val: ScVal::U32(2) }].try_into().unwrap(),
+    )));
+    raw.value = b64(value);
+
+    match decode(&raw) {
+        Err(Error::UnsupportedVersion { event, version }) => {
+            assert_eq!((event.as_str(), version), ("soroswap_swap", 2));
+        }
+        other => panic!("expected UnsupportedVersion, got {other:?}"),
+    }
+}
+
+#[test]
+fn test_decode_skips_failed_calls() {
+    let raw = RpcEvent { in_successful_contract_call: false, ..fixture().events[0].clone() };
+    assert_eq!(decode(&raw).unwrap(), None);
+}
+
+#[test]
+fn test_ingest_is_idempotent_and_tracks_cursor() {
+    let mut store = Store::in_memory().unwrap();
+    let page = fixture();
+
+    assert_eq!(ingest(&mut store, &page).unwrap(), 8);
+    assert_eq!(ingest(&mut store, &page).unwrap(), 0);
+
+    let state = store.sync_state().unwrap().unwrap();
+    assert_eq!(state.cursor, "0000004337916973056-0000000003");
+    assert_eq!(state.latest_ledger, 1012);
+
+    // An empty page keeps the cursor and advances the latest ledger
+    let empty = parse_response(
+        &json!({ "jsonrpc": "2.0", "id": 1, "result": { "events": [], "latestLedger": 1020 } })
+            .to_string(),
+    )
+    .unwrap();
+    assert_eq!(ingest(&mut store, &empty).unwrap(), 0);
+    let state = store.sync_state().unwrap().unwrap();
+    assert_eq!(state.cursor, "0000004337916973056-0000000003");
+    assert_eq!(state.latest_ledger, 1020);
+}
+
+#[test]
+fn test_file_store_shares_the_database_in_wal_mode() {
+    let path = std::env::temp_dir().join(format!("event-indexer-{}.db", std::process::id()));
+    let mut writer = Store::open(&path).unwrap();
+    let reader = Store::open(&path).unwrap();
+    assert!(path.with_extension("db-wal").exists());
+
+    assert_eq!(ingest(&mut writer, &fixture()).unwrap(), 8);
+    assert_eq!(reader.sync_state().unwrap().unwrap().latest_ledger, 1012);
+
+    drop((writer, reader));
+    for suffix in ["db", "db-wal", "db-shm"] {
+        let _ = std::fs::remove_file(path.with_extension(suffix));
+    }
+}
+
+#[test]
+fn test_rpc_error_response() {
+    let body = json!({
+        "jsonrpc": "2.0",
+        "id": 1,
+        "error": { "code": -32600, "message": "startLedger must be positive" }
+    });
+    match parse_response(&body.to_string()) {
+        Err(Error::Rpc(msg)) => assert!(msg.contains("startLedger must be positive")),
+        other => panic!("expected rpc error, got {other:?}"),
+    }
+}
+
+#[test]
+fn test_api_user_history() {
+    let store = indexed_store();
+
+    let history = get(&store, &format!("/users/{ALICE}/history"));
+    let history = history.as_array().unwrap();
+    assert_eq!(history.len(), 2);
+    // Newest first: the zap-out, then the zap
+    assert_eq!(history[0]["event"], "zap_out_completed");
+    assert_eq!(history[0]["amount_out"], "4950000000");
+    assert_eq!(history[1]["event"], "zap_completed");
+    assert_eq!(history[1]["zap_id"], 0);
+    assert_eq!(history[1]["receiver"], ALICE);
+
+    let limited = get(&store, &format!("/users/{ALICE}/history?limit=1"));
+    assert_eq!(limited.as_array().unwrap().len(), 1);
+
+    let bob = get(&store, &format!("/users/{BOB}/history"));
+    let zap: crate::IndexedEvent = serde_json::from_value(bob[0].clone()).unwrap();
+    assert_eq!(
+        zap.event,
+        ZapEvent::ZapCompleted(ZapCompleted {
+            receiver: BOB.into(),
+            from_token: USDC.into(),
+            amount_in: 500_000_000,
+            to_token: USDC.into(),
+            amount_swapped: 500_000_000,
+            vault_shares: 490_000_000,
+            price_impact_bps: 0,
+            protocol_fee: 0,
+        })
+    );
+
+    assert_eq!(handle(&store, "GET", "/users/x/history?limit=many").status, 400);
+}
+
+#[test]
+fn test_api_zap_events() {
+    let store = indexed_store();
+
+    let events = get(&store, "/zaps/2");
+    let names: Vec<_> = events.as_array().unwrap().iter().map(|e| e["event"].clone()).collect();
+    assert_eq!(names, [json!("defindex_withdraw"), json!("soroswap_swap"), json!("zap_out_completed")]);
+
+    assert_eq!(handle(&store, "GET", "/zaps/9").status, 404);
+    assert_eq!(handle(&store, "GET", "/zaps/latest").status, 400);
+}
+
+#[test]
+fn test_api_volumes() {
+    let store = indexed_store();
+
+    let volumes = get(&store, "/volumes");
+    assert_eq!(
+        volumes,
+        json!([
+            { "token": USDC, "zaps": 1, "zapped_in": "500000000", "zap_outs": 0, "zapped_out": "0" },
+            { "token": XLM, "zaps": 1, "zapped_in": "10000000000", "zap_outs": 1, "zapped_out": "4950000000" },
+        ])
+    );
+
+    // Only the zap-out happened at or after ledger 1005
+    let recent = get(&store, "/volumes?since_ledger=1005");
+    assert_eq!(
+        recent,
+        json!([{ "token": XLM, "zaps": 0, "zapped_in": "0", "zap_outs": 1, "zapped_out": "4950000000" }])
+    );
+}
+
+#[test]
+fn test_api_vault_flows() {
+    let store = indexed_store();
+
+    let flows = get(&store, &format!("/vaults/{VAULT}/flows"));
+    assert_eq!(
+        flows,
+        json!({
+            "vault": VAULT,
+            "deposits": 2,
+            "assets_deposited": "1700000000",
+            "shares_minted": "1670000000",
+            "withdrawals": 1,
+            "assets_withdrawn": "600000000",
+            "shares_burned": "590000000",
+            "net_assets": "1100000000",
+        })
+    );
+}
+
+#[test]
+fn test_aggregates_report_overflow() {
+    let mut store = Store::in_memory().unwrap();
+    let events: Vec<_> = fixture().events.iter().filter_map(|raw| decode(raw).unwrap()).collect();
+    let state = SyncState { cursor: "overflow".into(), latest_ledger: 2000 };
+
+    // Two events near i128::MAX of each kind the aggregates sum
+    let mut huge = Vec::new();
+    for (i, mut e) in events.into_iter().enumerate() {
+        match &mut e.event {
+            ZapEvent::ZapCompleted(zap) => {
+                zap.from_token = XLM.into();
+                zap.amount_in = i128::MAX - 1;
+            }
+            ZapEvent::Deposit(deposit) => deposit.amount = i128::MAX - 1,
+            _ => continue,
+        }
+        e.id = format!("overflow-{i}");
+        huge.push(e);
+    }
+    store.insert_page(&huge, &state).unwrap();
+
+    assert!(matches!(store.volumes(0), Err(Error::Overflow(what)) if what == "zapped_in"));
+    assert!(
+        matches!(store.vault_flows(VAULT), Err(Error::Overflow(what)) if what == "assets_deposited")
+    );
+    assert_eq!(handle(&store, "GET", "/volumes").status, 500);
+    assert_eq!(handle(&store, "GET", &format!("/vaults/{VAULT}/flows")).status, 500);
+}
+
+#[test]
+fn test_api_health_and_unknown_routes() {
+    let store = indexed_store();
+
+    let health = get(&store, "/health");
+    assert_eq!(health["sync"]["latest_ledger"], 1012);
+
+    assert_eq!(handle(&store, "GET", "/nope").status, 404);
+    assert_eq!(handle(&store, "POST", "/health").status, 405);
+}
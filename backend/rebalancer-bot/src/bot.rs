@@ -0,0 +1,213 @@
+//! One pass of the bot: observe vaults, decide per user, simulate and submit
+
+use serde::Serialize;
+use std::collections::BTreeSet;
+
+use crate::decimal;
+use crate::error::{Error, Result};
+use crate::performance::{Sample, Tracker};
+use crate::policy::{Action, Holding, Policy, UserState, VaultStats};
+use crate::scval;
+use crate::zap::{Prepared, ZapClient};
+
+pub struct Settings {
+    /// Users who delegated to the keeper with `set_operator`
+    pub users: Vec<String>,
+    /// Vaults to consider besides the ones users already hold
+    pub vaults: Vec<String>,
+    /// Amount passed to `preview_vault_deposit` to sample share prices
+    pub probe_amount: i128,
+    /// Tolerance below the simulated output for migrations and rebalance swaps
+    pub slippage_bps: u32,
+    /// Seconds of history an APY is measured over
+    pub window: u64,
+    /// Simulate actions without submitting them
+    pub dry_run: bool,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(tag = "status", rename_all = "snake_case")]
+pub enum Status {
+    /// Dry run: the action simulated successfully and was not submitted
+    Simulated,
+    Submitted { hash: String },
+    Failed { error: String },
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct Outcome {
+    pub user: String,
+    pub action: Action,
+    /// Minimum output the migration was built with
+    #[serde(with = "decimal::option", skip_serializing_if = "Option::is_none")]
+    pub min_amount_out: Option<i128>,
+    #[serde(flatten)]
+    pub status: Status,
+}
+
+pub struct Bot {
+    zap: ZapClient,
+    policy: Box<dyn Policy>,
+    tracker: Tracker,
+    settings: Settings,
+}
+
+impl Bot {
+    pub fn new(zap: ZapClient, policy: Box<dyn Policy>, tracker: Tracker, settings: Settings) -> Self {
+        Self { zap, policy, tracker, settings }
+    }
+
+    pub fn tracker(&self) -> &Tracker {
+        &self.tracker
+    }
+
+    /// Runs one pass at ledger time `now`. Errors reading a user or vault are logged and skip
+    /// that user or vault; errors executing an action are reported in its outcome.
+    pub fn tick(&mut self, now: u64) -> Vec<Outcome> {
+        let mut users = Vec::new();
+        let mut vaults: BTreeSet<String> = self.settings.vaults.iter().cloned().collect();
+        for user in &self.settings.users {
+            match self.load_user(user, now) {
+                Ok(Some((holdings, delegation))) => {
+                    vaults.extend(holdings.iter().map(|h| h.vault.clone()));
+                    users.push((user.clone(), holdings, delegation));
+                }
+                Ok(None) => {}
+                Err(err) => eprintln!("skipping user {user}: {err}"),
+            }
+        }
+
+        let mut stats = Vec::new();
+        for vault in vaults {
+            match self.observe(&vault, now) {
+                Ok(vault_stats) => stats.push(vault_stats),
+                Err(err) => eprintln!("skipping vault {vault}: {err}"),
+            }
+        }
+
+        let mut outcomes = Vec::new();
+        for (user, holdings, delegation) in &users {
+            let state = UserState { user, holdings, delegation, vaults: &stats };
+            for action in self.policy.decide(&state) {
+                let (min_amount_out, status) = match self.execute(user, delegation, &action, &stats) {
+                    Ok((min_amount_out, status)) => (min_amount_out, status),
+                    Err(err) => (None, Status::Failed { error: err.to_string() }),
+                };
+                outcomes.push(Outcome { user: user.clone(), action, min_amount_out, status });
+            }
+        }
+        outcomes
+    }
+
+    /// The user's holdings and live delegation, or None when the keeper may not act for them
+    fn load_user(
+        &self,
+        user: &str,
+        now: u64,
+    ) -> Result<Option<(Vec<Holding>, crate::zap::Delegation)>> {
+        let Some(delegation) = self.zap.delegation(user)? else {
+            return Ok(None);
+        };
+        if delegation.expires_at <= now {
+            return Ok(None);
+        }
+
+        let mut holdings = Vec::new();
+        for vault in self.zap.position_vaults(user)? {
+            let shares = self.zap.share_balance(&vault, user)?;
+            holdings.push(Holding { vault, shares });
+        }
+        Ok(Some((holdings, delegation)))
+    }
+
+    fn observe(&mut self, vault: &str, now: u64) -> Result<VaultStats> {
+        let info = self.zap.vault_info(vault)?;
+        let shares = self.zap.preview_deposit(vault, self.settings.probe_amount)?;
+        if let Some(sample) = Sample::from_preview(now, self.settings.probe_amount, shares) {
+            self.tracker.record(vault, sample, self.settings.window);
+        }
+        Ok(VaultStats {
+            vault: vault.to_string(),
+            asset: info.asset,
+            tvl: info.total_assets,
+            fee_bps: info.fee_bps,
+            apy_bps: self.tracker.apy_bps(vault, self.settings.window),
+        })
+    }
+
+    fn execute(
+        &self,
+        user: &str,
+        delegation: &crate::zap::Delegation,
+        action: &Action,
+        stats: &[VaultStats],
+    ) -> Result<(Option<i128>, Status)> {
+        // The Zap rejects rebalances looser than the user's policy
+        let slippage_bps = self.settings.slippage_bps.min(delegation.max_slippage_bps);
+        let (min_amount_out, prepared) = match action {
+            Action::Migrate { from_vault, to_vault, shares } => {
+                let asset = |vault: &str| {
+                    stats
+                        .iter()
+                        .find(|s| s.vault == vault)
+                        .map(|s| s.asset.clone())
+                        .ok_or_else(|| Error::Decode(format!("no stats for {vault}")))
+                };
+                let (from_asset, to_asset) = (asset(from_vault)?, asset(to_vault)?);
+                let migrate = |min_amount_out: i128| -> Result<Prepared> {
+                    self.zap.simulate(
+                        "operator_migrate",
+                        vec![
+                            scval::address(&self.zap.keeper())?,
+                            scval::address(user)?,
+                            scval::address(from_vault)?,
+                            scval::i128(*shares),
+                            scval::address(&from_asset)?,
+                            scval::address(to_vault)?,
+                            scval::address(&to_asset)?,
+                            scval::i128(min_amount_out),
+                            scval::option(None),
+                        ],
+                    )
+                };
+
+                // Quote without a floor, then rebuild with the floor the slippage allows
+                let quote = migrate(0)?;
+                let amount = scval::to_i128(scval::field(&quote.simulation.result, "amount_swapped")?)?;
+                let min_amount_out = min_amount_out(amount, slippage_bps)?;
+                (Some(min_amount_out), migrate(min_amount_out)?)
+            }
+            Action::Rebalance { targets } => {
+                let targets = targets
+                    .iter()
+                    .map(|(vault, weight)| scval::vec(vec![scval::address(vault)?, scval::u32(*weight)]))
+                    .collect::<Result<Vec<_>>>()?;
+                let prepared = self.zap.simulate(
+                    "operator_rebalance",
+                    vec![
+                        scval::address(&self.zap.keeper())?,
+                        scval::address(user)?,
+                        scval::vec(targets)?,
+                        scval::u32(slippage_bps),
+                    ],
+                )?;
+                (None, prepared)
+            }
+        };
+
+        if self.settings.dry_run {
+            return Ok((min_amount_out, Status::Simulated));
+        }
+        let hash = self.zap.submit(prepared)?;
+        Ok((min_amount_out, Status::Submitted { hash }))
+    }
+}
+
+/// `amount` less `slippage_bps`, rounded down
+pub(crate) fn min_amount_out(amount: i128, slippage_bps: u32) -> Result<i128> {
+    10_000i128
+        .checked_sub(slippage_bps as i128)
+        .and_then(|kept_bps| amount.checked_mul(kept_bps))
+        .map(|kept| kept / 10_000)
+        .ok_or_else(|| Error::Overflow(format!("{amount} less {slippage_bps} bps")))
+}
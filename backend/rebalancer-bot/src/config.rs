@@ -0,0 +1,108 @@
+//! The bot's JSON configuration file. The keeper secret is read from `KEEPER_SECRET` instead, so
+//! the file can be shared.
+
+use serde::Deserialize;
+use std::path::{Path, PathBuf};
+
+use crate::bot::Settings;
+use crate::error::{Error, Result};
+use crate::policy::{ApyPolicy, Policy, Screen, SpreadPolicy};
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct Config {
+    pub rpc_url: String,
+    pub network_passphrase: String,
+    pub zap_contract: String,
+    pub users: Vec<String>,
+    #[serde(default)]
+    pub vaults: Vec<String>,
+    #[serde(default = "default_interval")]
+    pub interval_secs: u64,
+    #[serde(default = "default_probe_amount")]
+    pub probe_amount: i128,
+    #[serde(default = "default_slippage")]
+    pub slippage_bps: u32,
+    #[serde(default = "default_window")]
+    pub window_secs: u64,
+    /// Where share-price history is kept between runs
+    pub state_path: Option<PathBuf>,
+    pub policy: PolicyConfig,
+}
+
+fn default_interval() -> u64 {
+    3_600
+}
+
+fn default_probe_amount() -> i128 {
+    10_000_000
+}
+
+fn default_slippage() -> u32 {
+    100
+}
+
+fn default_window() -> u64 {
+    7 * 24 * 3_600
+}
+
+fn default_max_fee() -> u32 {
+    10_000
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum PolicyConfig {
+    Apy {
+        min_gain_bps: i128,
+        #[serde(default)]
+        min_tvl: i128,
+        #[serde(default = "default_max_fee")]
+        max_fee_bps: u32,
+    },
+    Spread {
+        count: usize,
+        min_gain_bps: i128,
+        #[serde(default)]
+        min_tvl: i128,
+        #[serde(default = "default_max_fee")]
+        max_fee_bps: u32,
+    },
+}
+
+impl PolicyConfig {
+    pub fn build(&self) -> Box<dyn Policy> {
+        match *self {
+            PolicyConfig::Apy { min_gain_bps, min_tvl, max_fee_bps } => Box::new(ApyPolicy {
+                screen: Screen { min_tvl, max_fee_bps },
+                min_gain_bps,
+            }),
+            PolicyConfig::Spread { count, min_gain_bps, min_tvl, max_fee_bps } => {
+                Box::new(SpreadPolicy { screen: Screen { min_tvl, max_fee_bps }, count, min_gain_bps })
+            }
+        }
+    }
+}
+
+impl Config {
+    pub fn load(path: impl AsRef<Path>) -> Result<Self> {
+        let config: Config = serde_json::from_str(&std::fs::read_to_string(path)?)?;
+        if config.slippage_bps > 10_000 {
+            return Err(Error::Config("slippage_bps must be at most 10000".into()));
+        }
+        if let PolicyConfig::Spread { count: 0, .. } = config.policy {
+            return Err(Error::Config("spread policy needs a count of at least 1".into()));
+        }
+        Ok(config)
+    }
+
+    pub fn settings(&self, dry_run: bool) -> Settings {
+        Settings {
+            users: self.users.clone(),
+            vaults: self.vaults.clone(),
+            probe_amount: self.probe_amount,
+            slippage_bps: self.slippage_bps,
+            window: self.window_secs,
+            dry_run,
+        }
+    }
+}
@@ -0,0 +1,18 @@
+//! Serializes `i128` amounts as decimal strings; JSON numbers lose precision past 2^53
+
+use serde::Serializer;
+
+pub fn serialize<S: Serializer>(value: &i128, serializer: S) -> Result<S::Ok, S::Error> {
+    serializer.serialize_str(&value.to_string())
+}
+
+pub mod option {
+    use serde::Serializer;
+
+    pub fn serialize<S: Serializer>(value: &Option<i128>, serializer: S) -> Result<S::Ok, S::Error> {
+        match value {
+            Some(value) => super::serialize(value, serializer),
+            None => serializer.serialize_none(),
+        }
+    }
+}
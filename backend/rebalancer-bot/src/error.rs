@@ -0,0 +1,57 @@
+use std::fmt;
+
+pub type Result<T> = std::result::Result<T, Error>;
+
+#[derive(Debug)]
+pub enum Error {
+    /// The RPC request failed or returned a JSON-RPC error
+    Rpc(String),
+    Json(serde_json::Error),
+    Xdr(stellar_xdr::curr::Error),
+    Io(std::io::Error),
+    /// A contract call failed in simulation; nothing was submitted
+    Simulation(String),
+    /// A submitted transaction was rejected or failed on chain
+    Transaction(String),
+    /// A contract returned a value of an unexpected shape
+    Decode(String),
+    Config(String),
+    /// An amount computation overflowed i128
+    Overflow(String),
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Error::Rpc(msg) => write!(f, "rpc error: {msg}"),
+            Error::Json(err) => write!(f, "json error: {err}"),
+            Error::Xdr(err) => write!(f, "xdr error: {err}"),
+            Error::Io(err) => write!(f, "io error: {err}"),
+            Error::Simulation(msg) => write!(f, "simulation failed: {msg}"),
+            Error::Transaction(msg) => write!(f, "transaction failed: {msg}"),
+            Error::Decode(msg) => write!(f, "decode error: {msg}"),
+            Error::Config(msg) => write!(f, "config error: {msg}"),
+            Error::Overflow(msg) => write!(f, "overflow: {msg}"),
+        }
+    }
+}
+
+impl std::error::Error for Error {}
+
+impl From<serde_json::Error> for Error {
+    fn from(err: serde_json::Error) -> Self {
+        Error::Json(err)
+    }
+}
+
+impl From<stellar_xdr::curr::Error> for Error {
+    fn from(err: stellar_xdr::curr::Error) -> Self {
+        Error::Xdr(err)
+    }
+}
+
+impl From<std::io::Error> for Error {
+    fn from(err: std::io::Error) -> Self {
+        Error::Io(err)
+    }
+}
@@ -0,0 +1,19 @@
+//! Keeper bot that migrates and rebalances users' delegated Zap positions toward better vaults
+
+pub mod bot;
+pub mod config;
+mod decimal;
+mod error;
+pub mod performance;
+pub mod policy;
+pub mod rpc;
+pub mod scval;
+pub mod tx;
+pub mod zap;
+
+pub use bot::{Bot, Outcome, Settings, Status};
+pub use error::{Error, Result};
+pub use policy::{Action, Policy};
+
+#[cfg(test)]
+mod test;
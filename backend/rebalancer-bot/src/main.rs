@@ -0,0 +1,73 @@
+use std::process::exit;
+use std::thread;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use rebalancer_bot::config::Config;
+use rebalancer_bot::performance::Tracker;
+use rebalancer_bot::rpc::RpcClient;
+use rebalancer_bot::tx::Keypair;
+use rebalancer_bot::zap::ZapClient;
+use rebalancer_bot::{Bot, Error, Result};
+
+const USAGE: &str = "usage: KEEPER_SECRET=S... rebalancer-bot --config FILE [--dry-run] [--once]";
+
+fn main() {
+    let mut config_path = None;
+    let (mut dry_run, mut once) = (false, false);
+    let mut args = std::env::args().skip(1);
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--config" => config_path = args.next(),
+            "--dry-run" => dry_run = true,
+            "--once" => once = true,
+            _ => {
+                eprintln!("{USAGE}");
+                exit(2);
+            }
+        }
+    }
+    let Some(config_path) = config_path else {
+        eprintln!("{USAGE}");
+        exit(2);
+    };
+
+    if let Err(err) = run(&config_path, dry_run, once) {
+        eprintln!("error: {err}");
+        exit(1);
+    }
+}
+
+fn run(config_path: &str, dry_run: bool, once: bool) -> Result<()> {
+    let config = Config::load(config_path)?;
+    let secret = std::env::var("KEEPER_SECRET")
+        .map_err(|_| Error::Config("KEEPER_SECRET is not set".into()))?;
+
+    let zap = ZapClient::new(
+        RpcClient::new(&config.rpc_url),
+        Keypair::from_secret(&secret)?,
+        config.zap_contract.clone(),
+        config.network_passphrase.clone(),
+    );
+    eprintln!("keeper {}{}", zap.keeper(), if dry_run { " (dry run)" } else { "" });
+
+    let tracker = match &config.state_path {
+        Some(path) => Tracker::load(path)?,
+        None => Tracker::default(),
+    };
+    let mut bot = Bot::new(zap, config.policy.build(), tracker, config.settings(dry_run));
+
+    loop {
+        let now = SystemTime::now().duration_since(UNIX_EPOCH).map(|d| d.as_secs()).unwrap_or(0);
+        // One JSON line per action, for log shippers and alerting
+        for outcome in bot.tick(now) {
+            println!("{}", serde_json::to_string(&outcome)?);
+        }
+        if let Some(path) = &config.state_path {
+            bot.tracker().save(path)?;
+        }
+        if once {
+            return Ok(());
+        }
+        thread::sleep(Duration::from_secs(config.interval_secs));
+    }
+}
@@ -0,0 +1,78 @@
+//! Share-price history the bot observes, and the APY derived from it
+
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::path::Path;
+
+use crate::error::Result;
+
+/// Fixed-point scale of `Sample::share_price`
+pub const PRICE_SCALE: i128 = 10_000_000;
+pub const SECONDS_PER_YEAR: i128 = 31_536_000;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Sample {
+    pub timestamp: u64,
+    /// Vault assets per share, scaled by `PRICE_SCALE`
+    pub share_price: i128,
+}
+
+impl Sample {
+    /// Derives the share price from a `preview_vault_deposit` of `amount`
+    pub fn from_preview(timestamp: u64, amount: i128, shares: i128) -> Option<Self> {
+        if amount <= 0 || shares <= 0 {
+            return None;
+        }
+        Some(Self { timestamp, share_price: amount.checked_mul(PRICE_SCALE)? / shares })
+    }
+}
+
+/// Share-price samples per vault. Persisted between runs so APYs survive restarts.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Tracker {
+    samples: BTreeMap<String, Vec<Sample>>,
+}
+
+impl Tracker {
+    pub fn load(path: impl AsRef<Path>) -> Result<Self> {
+        match std::fs::read_to_string(path) {
+            Ok(json) => Ok(serde_json::from_str(&json)?),
+            Err(err) if err.kind() == std::io::ErrorKind::NotFound => Ok(Self::default()),
+            Err(err) => Err(err.into()),
+        }
+    }
+
+    pub fn save(&self, path: impl AsRef<Path>) -> Result<()> {
+        Ok(std::fs::write(path, serde_json::to_string(self)?)?)
+    }
+
+    pub fn samples(&self, vault: &str) -> &[Sample] {
+        self.samples.get(vault).map(Vec::as_slice).unwrap_or_default()
+    }
+
+    /// Adds a sample, dropping those no longer needed to measure `window`
+    pub fn record(&mut self, vault: &str, sample: Sample, window: u64) {
+        let samples = self.samples.entry(vault.to_string()).or_default();
+        samples.push(sample);
+
+        let cutoff = sample.timestamp.saturating_sub(window);
+        let base = samples.iter().rposition(|s| s.timestamp <= cutoff).unwrap_or(0);
+        samples.drain(..base);
+    }
+
+    /// Annualized share-price growth over at least `window` seconds, in bps. None until the
+    /// samples span the window.
+    pub fn apy_bps(&self, vault: &str, window: u64) -> Option<i128> {
+        let samples = self.samples(vault);
+        let last = samples.last()?;
+        let cutoff = last.timestamp.checked_sub(window)?;
+        let base = samples.iter().rev().find(|s| s.timestamp <= cutoff)?;
+
+        let elapsed = (last.timestamp - base.timestamp) as i128;
+        if elapsed == 0 || base.share_price <= 0 {
+            return None;
+        }
+        let growth_bps = (last.share_price - base.share_price).checked_mul(10_000)? / base.share_price;
+        Some(growth_bps.checked_mul(SECONDS_PER_YEAR)? / elapsed)
+    }
+}
@@ -0,0 +1,168 @@
+//! Deciding what to do with a user's delegated positions
+//!
+//! A [`Policy`] sees one user's holdings, what the keeper may touch for them and the latest stats
+//! of every tracked vault, and returns the actions to take. The bot simulates each action and,
+//! outside dry-run mode, submits it.
+
+use serde::Serialize;
+
+use crate::decimal;
+use crate::zap::Delegation;
+
+/// What the bot knows about a vault this tick
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct VaultStats {
+    pub vault: String,
+    pub asset: String,
+    pub tvl: i128,
+    pub fee_bps: u32,
+    /// None until the bot has observed the vault for a full window
+    pub apy_bps: Option<i128>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Holding {
+    pub vault: String,
+    pub shares: i128,
+}
+
+pub struct UserState<'a> {
+    pub user: &'a str,
+    pub holdings: &'a [Holding],
+    pub delegation: &'a Delegation,
+    pub vaults: &'a [VaultStats],
+}
+
+impl UserState<'_> {
+    pub fn stats(&self, vault: &str) -> Option<&VaultStats> {
+        self.vaults.iter().find(|stats| stats.vault == vault)
+    }
+
+    /// Whether the delegation lets the keeper move funds into or out of the vault
+    pub fn delegated(&self, stats: &VaultStats) -> bool {
+        self.delegation.vaults.contains(&stats.vault) && self.delegation.tokens.contains(&stats.asset)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum Action {
+    /// `operator_migrate` all of a position into another vault
+    Migrate {
+        from_vault: String,
+        to_vault: String,
+        #[serde(with = "decimal")]
+        shares: i128,
+    },
+    /// `operator_rebalance` toward (vault, weight in bps) targets
+    Rebalance { targets: Vec<(String, u32)> },
+}
+
+pub trait Policy {
+    fn decide(&self, state: &UserState) -> Vec<Action>;
+}
+
+/// Vaults a policy is willing to move funds into
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Screen {
+    pub min_tvl: i128,
+    pub max_fee_bps: u32,
+}
+
+impl Screen {
+    /// Delegated vaults passing the screen with a known APY, best first
+    fn ranked<'a>(&self, state: &'a UserState) -> Vec<&'a VaultStats> {
+        let mut ranked: Vec<_> = state
+            .vaults
+            .iter()
+            .filter(|stats| {
+                stats.apy_bps.is_some()
+                    && stats.tvl >= self.min_tvl
+                    && stats.fee_bps <= self.max_fee_bps
+                    && state.delegated(stats)
+            })
+            .collect();
+        // Ties go to the larger vault, then the address, so decisions are deterministic
+        ranked.sort_by(|a, b| {
+            b.apy_bps.cmp(&a.apy_bps).then(b.tvl.cmp(&a.tvl)).then(a.vault.cmp(&b.vault))
+        });
+        ranked
+    }
+}
+
+/// Migrates each position into the best vault once it beats the current one by `min_gain_bps`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ApyPolicy {
+    pub screen: Screen,
+    pub min_gain_bps: i128,
+}
+
+impl Policy for ApyPolicy {
+    fn decide(&self, state: &UserState) -> Vec<Action> {
+        let ranked = self.screen.ranked(state);
+        let mut actions = Vec::new();
+        for holding in state.holdings.iter().filter(|h| h.shares > 0) {
+            // Positions in vaults the bot cannot measure or may not touch stay put
+            let Some(current) = state.stats(&holding.vault).filter(|s| state.delegated(s)) else {
+                continue;
+            };
+            let Some(current_apy) = current.apy_bps else {
+                continue;
+            };
+            let Some(best) = ranked.iter().find(|stats| stats.vault != holding.vault) else {
+                continue;
+            };
+            if best.apy_bps.unwrap_or_default() - current_apy >= self.min_gain_bps {
+                actions.push(Action::Migrate {
+                    from_vault: holding.vault.clone(),
+                    to_vault: best.vault.clone(),
+                    shares: holding.shares,
+                });
+            }
+        }
+        actions
+    }
+}
+
+/// Spreads a user's funds evenly over the `count` best vaults, rebalancing out of any held vault
+/// that trails the worst of them by `min_gain_bps`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SpreadPolicy {
+    pub screen: Screen,
+    pub count: usize,
+    pub min_gain_bps: i128,
+}
+
+impl Policy for SpreadPolicy {
+    fn decide(&self, state: &UserState) -> Vec<Action> {
+        let top: Vec<_> = self.screen.ranked(state).into_iter().take(self.count).collect();
+        let Some(worst_top_apy) = top.last().and_then(|stats| stats.apy_bps) else {
+            return Vec::new();
+        };
+
+        let mut exits = Vec::new();
+        for holding in state.holdings.iter().filter(|h| h.shares > 0) {
+            if top.iter().any(|stats| stats.vault == holding.vault) {
+                continue;
+            }
+            let Some(current) = state.stats(&holding.vault).filter(|s| state.delegated(s)) else {
+                continue;
+            };
+            match current.apy_bps {
+                Some(apy) if worst_top_apy - apy >= self.min_gain_bps => exits.push(current),
+                _ => {}
+            }
+        }
+        if exits.is_empty() {
+            return Vec::new();
+        }
+
+        // Equal weights, the rounding remainder to the best vault; exited vaults weigh zero
+        let weight = 10_000 / top.len() as u32;
+        let mut targets: Vec<(String, u32)> =
+            top.iter().map(|stats| (stats.vault.clone(), weight)).collect();
+        targets[0].1 += 10_000 - weight * top.len() as u32;
+        targets.extend(exits.iter().map(|stats| (stats.vault.clone(), 0)));
+        vec![Action::Rebalance { targets }]
+    }
+}
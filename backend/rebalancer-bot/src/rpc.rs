@@ -0,0 +1,150 @@
+//! Soroban RPC client for the calls the bot needs
+
+use serde::Deserialize;
+use serde_json::{json, Value};
+use stellar_xdr::curr::{
+    LedgerEntryData, LedgerKey, LedgerKeyAccount, Limits, ReadXdr, ScVal, SorobanAuthorizationEntry,
+    SorobanTransactionData, TransactionEnvelope, WriteXdr,
+};
+
+use crate::error::{Error, Result};
+use crate::scval;
+
+/// The part of a `simulateTransaction` response needed to assemble the transaction
+#[derive(Debug, Clone)]
+pub struct Simulation {
+    pub result: ScVal,
+    pub auth: Vec<SorobanAuthorizationEntry>,
+    pub transaction_data: SorobanTransactionData,
+    pub min_resource_fee: u32,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct RawSimulation {
+    error: Option<String>,
+    transaction_data: Option<String>,
+    min_resource_fee: Option<String>,
+    #[serde(default)]
+    results: Vec<RawSimulationResult>,
+}
+
+#[derive(Deserialize)]
+struct RawSimulationResult {
+    #[serde(default)]
+    auth: Vec<String>,
+    xdr: String,
+}
+
+/// Outcome of a submitted transaction
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum TxStatus {
+    Pending,
+    Success,
+    Failed,
+}
+
+pub struct RpcClient {
+    url: String,
+    agent: ureq::Agent,
+}
+
+impl RpcClient {
+    pub fn new(url: impl Into<String>) -> Self {
+        Self { url: url.into(), agent: ureq::Agent::new() }
+    }
+
+    fn call(&self, method: &str, params: Value) -> Result<Value> {
+        let request = json!({ "jsonrpc": "2.0", "id": 1, "method": method, "params": params });
+        let mut response: Value = self
+            .agent
+            .post(&self.url)
+            .send_json(request)
+            .map_err(|err| Error::Rpc(format!("{method}: {err}")))?
+            .into_json()?;
+
+        if let Some(err) = response.get("error") {
+            return Err(Error::Rpc(format!("{method}: {err}")));
+        }
+        match response.get_mut("result") {
+            Some(result) => Ok(result.take()),
+            None => Err(Error::Rpc(format!("{method}: response has no result"))),
+        }
+    }
+
+    /// Current sequence number of `account`
+    pub fn account_sequence(&self, account: &str) -> Result<i64> {
+        let stellar_xdr::curr::ScAddress::Account(account_id) = scval::sc_address(account)? else {
+            return Err(Error::Config(format!("{account} is not an account")));
+        };
+        let key = LedgerKey::Account(LedgerKeyAccount { account_id });
+        let result = self.call(
+            "getLedgerEntries",
+            json!({ "keys": [key.to_xdr_base64(Limits::none())?] }),
+        )?;
+
+        let entry = result["entries"]
+            .get(0)
+            .and_then(|entry| entry["xdr"].as_str())
+            .ok_or_else(|| Error::Rpc(format!("account {account} not found")))?;
+        match LedgerEntryData::from_xdr_base64(entry, Limits::none())? {
+            LedgerEntryData::Account(account) => Ok(account.seq_num.0),
+            _ => Err(Error::Decode("ledger entry is not an account".into())),
+        }
+    }
+
+    pub fn simulate(&self, envelope: &TransactionEnvelope) -> Result<Simulation> {
+        let result = self.call(
+            "simulateTransaction",
+            json!({ "transaction": envelope.to_xdr_base64(Limits::none())? }),
+        )?;
+        let raw: RawSimulation = serde_json::from_value(result)?;
+        if let Some(err) = raw.error {
+            return Err(Error::Simulation(err));
+        }
+
+        let (Some(data), Some(fee), Some(result)) =
+            (raw.transaction_data, raw.min_resource_fee, raw.results.first())
+        else {
+            return Err(Error::Simulation("response is missing its result".into()));
+        };
+        Ok(Simulation {
+            result: ScVal::from_xdr_base64(&result.xdr, Limits::none())?,
+            auth: result
+                .auth
+                .iter()
+                .map(|entry| SorobanAuthorizationEntry::from_xdr_base64(entry, Limits::none()))
+                .collect::<std::result::Result<_, _>>()?,
+            transaction_data: SorobanTransactionData::from_xdr_base64(&data, Limits::none())?,
+            min_resource_fee: fee
+                .parse()
+                .map_err(|_| Error::Decode(format!("invalid minResourceFee {fee}")))?,
+        })
+    }
+
+    /// Submits a signed transaction. Returns once the RPC accepted it for inclusion.
+    pub fn send(&self, envelope: &TransactionEnvelope) -> Result<()> {
+        let result = self.call(
+            "sendTransaction",
+            json!({ "transaction": envelope.to_xdr_base64(Limits::none())? }),
+        )?;
+        match result["status"].as_str() {
+            Some("PENDING") | Some("DUPLICATE") => Ok(()),
+            status => Err(Error::Transaction(format!(
+                "rejected with status {}: {}",
+                status.unwrap_or("unknown"),
+                result["errorResultXdr"].as_str().unwrap_or("no result"),
+            ))),
+        }
+    }
+
+    pub fn transaction_status(&self, hash: &str) -> Result<TxStatus> {
+        let result = self.call("getTransaction", json!({ "hash": hash }))?;
+        match result["status"].as_str() {
+            Some("SUCCESS") => Ok(TxStatus::Success),
+            Some("FAILED") => Ok(TxStatus::Failed),
+            Some("NOT_FOUND") => Ok(TxStatus::Pending),
+            status => Err(Error::Rpc(format!("unknown transaction status {status:?}"))),
+        }
+    }
+}
@@ -0,0 +1,101 @@
+//! Conversions between Rust values and the `ScVal`s the Zap contract takes and returns
+
+use std::str::FromStr;
+use stellar_xdr::curr::{
+    AccountId, Hash, Int128Parts, PublicKey, ScAddress, ScMap, ScSymbol, ScVal, ScVec, Uint256,
+};
+
+use crate::error::{Error, Result};
+
+pub fn symbol(name: &str) -> ScVal {
+    ScVal::Symbol(ScSymbol(name.try_into().expect("symbol is valid")))
+}
+
+/// Parses a `G...` account or `C...` contract strkey
+pub fn sc_address(strkey: &str) -> Result<ScAddress> {
+    match stellar_strkey::Strkey::from_str(strkey) {
+        Ok(stellar_strkey::Strkey::PublicKeyEd25519(key)) => Ok(ScAddress::Account(AccountId(
+            PublicKey::PublicKeyTypeEd25519(Uint256(key.0)),
+        ))),
+        Ok(stellar_strkey::Strkey::Contract(contract)) => Ok(ScAddress::Contract(Hash(contract.0))),
+        _ => Err(Error::Config(format!("{strkey} is not an account or contract address"))),
+    }
+}
+
+pub fn address(strkey: &str) -> Result<ScVal> {
+    Ok(ScVal::Address(sc_address(strkey)?))
+}
+
+pub fn i128(value: i128) -> ScVal {
+    ScVal::I128(Int128Parts { hi: (value >> 64) as i64, lo: value as u64 })
+}
+
+pub fn u32(value: u32) -> ScVal {
+    ScVal::U32(value)
+}
+
+pub fn option(value: Option<ScVal>) -> ScVal {
+    value.unwrap_or(ScVal::Void)
+}
+
+pub fn vec(items: Vec<ScVal>) -> Result<ScVal> {
+    Ok(ScVal::Vec(Some(ScVec(items.try_into()?))))
+}
+
+pub fn to_address(val: &ScVal) -> Result<String> {
+    match val {
+        ScVal::Address(ScAddress::Account(AccountId(PublicKey::PublicKeyTypeEd25519(Uint256(
+            key,
+        ))))) => Ok(stellar_strkey::ed25519::PublicKey(*key).to_string()),
+        ScVal::Address(ScAddress::Contract(hash)) => Ok(stellar_strkey::Contract(hash.0).to_string()),
+        other => Err(Error::Decode(format!("expected an address, got {other:?}"))),
+    }
+}
+
+pub fn to_i128(val: &ScVal) -> Result<i128> {
+    match val {
+        ScVal::I128(parts) => Ok(((parts.hi as i128) << 64) | parts.lo as i128),
+        other => Err(Error::Decode(format!("expected an i128, got {other:?}"))),
+    }
+}
+
+pub fn to_u32(val: &ScVal) -> Result<u32> {
+    match val {
+        ScVal::U32(v) => Ok(*v),
+        other => Err(Error::Decode(format!("expected a u32, got {other:?}"))),
+    }
+}
+
+pub fn to_u64(val: &ScVal) -> Result<u64> {
+    match val {
+        ScVal::U64(v) => Ok(*v),
+        other => Err(Error::Decode(format!("expected a u64, got {other:?}"))),
+    }
+}
+
+pub fn to_vec(val: &ScVal) -> Result<Vec<ScVal>> {
+    match val {
+        ScVal::Vec(Some(items)) => Ok(items.0.to_vec()),
+        other => Err(Error::Decode(format!("expected a vec, got {other:?}"))),
+    }
+}
+
+/// `None` for `Option::None`, which the host encodes as `Void`
+pub fn to_option(val: &ScVal) -> Option<&ScVal> {
+    match val {
+        ScVal::Void => None,
+        other => Some(other),
+    }
+}
+
+/// A field of a `#[contracttype]` struct, which arrives as a map keyed by field name
+pub fn field<'a>(val: &'a ScVal, name: &str) -> Result<&'a ScVal> {
+    let ScVal::Map(Some(ScMap(entries))) = val else {
+        return Err(Error::Decode(format!("expected a struct with {name}, got {val:?}")));
+    };
+    entries
+        .iter()
+        .find(|entry| entry.key == symbol(name))
+        .map(|entry| &entry.val)
+        .ok_or_else(|| Error::Decode(format!("struct has no field {name}")))
+}
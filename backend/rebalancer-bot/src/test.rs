@@ -0,0 +1,484 @@
+use ed25519_dalek::{Signature as DalekSignature, Verifier, VerifyingKey};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use stellar_xdr::curr::{OperationBody, ScVal, TransactionEnvelope, TransactionExt};
+
+use crate::bot::min_amount_out;
+use crate::error::Error;
+use crate::performance::{Sample, Tracker, PRICE_SCALE};
+use crate::policy::{ApyPolicy, Holding, Screen, SpreadPolicy, UserState, VaultStats};
+use crate::rpc::RpcClient;
+use crate::tx::{self, Keypair, BASE_FEE};
+use crate::zap::{Delegation, ZapClient};
+use crate::{scval, Action, Bot, Outcome, Policy, Settings, Status};
+
+const PASSPHRASE: &str = "Standalone Network ; February 2017";
+const WINDOW: u64 = 1_000;
+
+fn contract(byte: u8) -> String {
+    stellar_strkey::Contract([byte; 32]).to_string()
+}
+
+fn account(byte: u8) -> String {
+    stellar_strkey::ed25519::PublicKey([byte; 32]).to_string()
+}
+
+fn keeper() -> Keypair {
+    Keypair::from_secret(&stellar_strkey::ed25519::PrivateKey([7; 32]).to_string()).unwrap()
+}
+
+/// A local stand-in for Soroban RPC that answers simulations from an in-memory Zap
+mod stand_in {
+    use serde_json::{json, Value};
+    use std::collections::HashMap;
+    use std::sync::{Arc, Mutex};
+    use std::thread;
+    use stellar_xdr::curr::{
+        AccountEntry, AccountEntryExt, ExtensionPoint, HostFunction, LedgerEntryData,
+        LedgerFootprint, Limits, OperationBody, ReadXdr, ScVal, SequenceNumber, SorobanResources,
+        SorobanTransactionData, String32, Thresholds, TransactionEnvelope, VecM, WriteXdr,
+    };
+
+    use crate::scval;
+
+    pub const SEQUENCE: i64 = 41;
+    pub const RESOURCE_FEE: u32 = 5_000;
+
+    pub struct VaultState {
+        pub asset: String,
+        pub total_assets: i128,
+        pub fee_bps: u32,
+        /// Assets per share, scaled by `PRICE_SCALE`
+        pub share_price: i128,
+    }
+
+    #[derive(Default)]
+    pub struct Chain {
+        pub vaults: HashMap<String, VaultState>,
+        pub positions: HashMap<String, Vec<String>>,
+        pub balances: HashMap<(String, String), i128>,
+        /// user -> (vaults, tokens, expires_at, max_slippage_bps)
+        pub delegations: HashMap<String, (Vec<String>, Vec<String>, u64, u32)>,
+        /// `amount_swapped` reported by `operator_migrate`
+        pub migrate_out: i128,
+        /// Makes state-changing calls fail in simulation with this error
+        pub fail: Option<String>,
+        /// Every simulated (function, args)
+        pub calls: Vec<(String, Vec<ScVal>)>,
+        pub sent: Vec<TransactionEnvelope>,
+    }
+
+    fn struct_val(fields: Vec<(&str, ScVal)>) -> ScVal {
+        let entries: Vec<_> = fields
+            .into_iter()
+            .map(|(key, val)| stellar_xdr::curr::ScMapEntry { key: scval::symbol(key), val })
+            .collect();
+        ScVal::Map(Some(stellar_xdr::curr::ScMap(entries.try_into().unwrap())))
+    }
+
+    fn addresses(items: &[String]) -> ScVal {
+        scval::vec(items.iter().map(|a| scval::address(a).unwrap()).collect()).unwrap()
+    }
+
+    fn simulate(chain: &mut Chain, envelope: &str) -> Value {
+        let TransactionEnvelope::Tx(envelope) =
+            TransactionEnvelope::from_xdr_base64(envelope, Limits::none()).unwrap()
+        else {
+            panic!("unexpected envelope type");
+        };
+        let OperationBody::InvokeHostFunction(op) = &envelope.tx.operations[0].body else {
+            panic!("not a contract call");
+        };
+        let HostFunction::InvokeContract(call) = &op.host_function else {
+            panic!("not a contract call");
+        };
+        let function = call.function_name.to_utf8_string_lossy();
+        let args = call.args.to_vec();
+        chain.calls.push((function.clone(), args.clone()));
+        let arg = |i: usize| scval::to_address(&args[i]).unwrap();
+
+        let result = match function.as_str() {
+            "get_positions" => scval::vec(
+                chain
+                    .positions
+                    .get(&arg(0))
+                    .cloned()
+                    .unwrap_or_default()
+                    .iter()
+                    .map(|vault| struct_val(vec![("vault", scval::address(vault).unwrap())]))
+                    .collect(),
+            )
+            .unwrap(),
+            "get_operator" => match chain.delegations.get(&arg(0)) {
+                Some((vaults, tokens, expires_at, max_slippage_bps)) => struct_val(vec![
+                    ("expires_at", ScVal::U64(*expires_at)),
+                    ("max_slippage_bps", scval::u32(*max_slippage_bps)),
+                    ("tokens", addresses(tokens)),
+                    ("vaults", addresses(vaults)),
+                ]),
+                None => ScVal::Void,
+            },
+            "get_vault_info" => {
+                let vault = &chain.vaults[&arg(0)];
+                scval::vec(vec![
+                    scval::address(&vault.asset).unwrap(),
+                    scval::i128(vault.total_assets),
+                    scval::i128(vault.total_assets * crate::performance::PRICE_SCALE / vault.share_price),
+                    ScVal::Void,
+                    ScVal::Void,
+                    scval::u32(7),
+                    scval::u32(vault.fee_bps),
+                    ScVal::Void,
+                ])
+                .unwrap()
+            }
+            "preview_vault_deposit" => {
+                let amount = scval::to_i128(&args[1]).unwrap();
+                scval::i128(amount * crate::performance::PRICE_SCALE / chain.vaults[&arg(0)].share_price)
+            }
+            "balance" => {
+                let vault = scval::to_address(&ScVal::Address(call.contract_address.clone())).unwrap();
+                scval::i128(chain.balances.get(&(vault, arg(0))).copied().unwrap_or_default())
+            }
+            "operator_migrate" | "operator_rebalance" => {
+                if let Some(err) = &chain.fail {
+                    return json!({ "error": err, "latestLedger": 100 });
+                }
+                if function == "operator_migrate" {
+                    struct_val(vec![("amount_swapped", scval::i128(chain.migrate_out))])
+                } else {
+                    struct_val(vec![("total_value", scval::i128(0))])
+                }
+            }
+            other => panic!("unexpected call {other}"),
+        };
+
+        let data = SorobanTransactionData {
+            ext: ExtensionPoint::V0,
+            resources: SorobanResources {
+                footprint: LedgerFootprint { read_only: VecM::default(), read_write: VecM::default() },
+                instructions: 1_000,
+                read_bytes: 100,
+                write_bytes: 100,
+            },
+            resource_fee: RESOURCE_FEE as i64,
+        };
+        json!({
+            "transactionData": data.to_xdr_base64(Limits::none()).unwrap(),
+            "minResourceFee": RESOURCE_FEE.to_string(),
+            "results": [{ "auth": [], "xdr": result.to_xdr_base64(Limits::none()).unwrap() }],
+            "latestLedger": 100,
+        })
+    }
+
+    fn account_entry(request: &Value) -> Value {
+        let key = stellar_xdr::curr::LedgerKey::from_xdr_base64(
+            request["params"]["keys"][0].as_str().unwrap(),
+            Limits::none(),
+        )
+        .unwrap();
+        let stellar_xdr::curr::LedgerKey::Account(key) = key else {
+            panic!("expected an account key");
+        };
+        let entry = LedgerEntryData::Account(AccountEntry {
+            account_id: key.account_id,
+            balance: 100_000_000,
+            seq_num: SequenceNumber(SEQUENCE),
+            num_sub_entries: 0,
+            inflation_dest: None,
+            flags: 0,
+            home_domain: String32::default(),
+            thresholds: Thresholds([1, 0, 0, 0]),
+            signers: VecM::default(),
+            ext: AccountEntryExt::V0,
+        });
+        json!({ "entries": [{ "xdr": entry.to_xdr_base64(Limits::none()).unwrap() }], "latestLedger": 100 })
+    }
+
+    /// Serves the chain on a free local port and returns its URL
+    pub fn start(chain: Arc<Mutex<Chain>>) -> String {
+        let server = tiny_http::Server::http("127.0.0.1:0").unwrap();
+        let url = format!("http://{}", server.server_addr().to_ip().unwrap());
+        thread::spawn(move || {
+            for mut request in server.incoming_requests() {
+                let mut body = String::new();
+                request.as_reader().read_to_string(&mut body).unwrap();
+                let rpc: Value = serde_json::from_str(&body).unwrap();
+
+                let mut chain = chain.lock().unwrap();
+                let result = match rpc["method"].as_str().unwrap() {
+                    "getLedgerEntries" => account_entry(&rpc),
+                    "simulateTransaction" => {
+                        simulate(&mut chain, rpc["params"]["transaction"].as_str().unwrap())
+                    }
+                    "sendTransaction" => {
+                        let envelope = TransactionEnvelope::from_xdr_base64(
+                            rpc["params"]["transaction"].as_str().unwrap(),
+                            Limits::none(),
+                        )
+                        .unwrap();
+                        chain.sent.push(envelope);
+                        json!({ "status": "PENDING", "latestLedger": 100 })
+                    }
+                    "getTransaction" => json!({ "status": "SUCCESS", "latestLedger": 101 }),
+                    other => panic!("unexpected method {other}"),
+                };
+                let response = json!({ "jsonrpc": "2.0", "id": rpc["id"], "result": result });
+                request.respond(tiny_http::Response::from_string(response.to_string())).unwrap();
+            }
+        });
+        url
+    }
+}
+
+struct Setup {
+    chain: Arc<Mutex<stand_in::Chain>>,
+    user: String,
+    vault_a: String,
+    vault_b: String,
+}
+
+/// A user holding vault A shares, delegated to the keeper for vaults A and B. B is listed as a
+/// candidate; both vaults start at a share price of 1.
+fn setup() -> Setup {
+    let (user, usdc, vault_a, vault_b) = (account(1), contract(3), contract(10), contract(11));
+    let mut chain = stand_in::Chain { migrate_out: 9_950_000_000, ..Default::default() };
+    for vault in [&vault_a, &vault_b] {
+        chain.vaults.insert(
+            vault.clone(),
+            stand_in::VaultState {
+                asset: usdc.clone(),
+                total_assets: 1_000_000_000_000,
+                fee_bps: 50,
+                share_price: PRICE_SCALE,
+            },
+        );
+    }
+    chain.positions.insert(user.clone(), vec![vault_a.clone()]);
+    chain.balances.insert((vault_a.clone(), user.clone()), 10_000_000_000);
+    chain.delegations.insert(
+        user.clone(),
+        (vec![vault_a.clone(), vault_b.clone()], vec![usdc], 10_000_000, 100),
+    );
+    Setup { chain: Arc::new(Mutex::new(chain)), user, vault_a, vault_b }
+}
+
+fn bot(setup: &Setup, policy: Box<dyn Policy>, dry_run: bool) -> Bot {
+    let mut zap = ZapClient::new(
+        RpcClient::new(stand_in::start(setup.chain.clone())),
+        keeper(),
+        contract(9),
+        PASSPHRASE.to_string(),
+    );
+    zap.confirm_interval = Duration::ZERO;
+    let settings = Settings {
+        users: vec![setup.user.clone()],
+        vaults: vec![setup.vault_b.clone()],
+        probe_amount: 10_000_000,
+        slippage_bps: 100,
+        window: WINDOW,
+        dry_run,
+    };
+    Bot::new(zap, policy, Tracker::default(), settings)
+}
+
+fn apy_policy() -> Box<dyn Policy> {
+    Box::new(ApyPolicy { screen: Screen { min_tvl: 0, max_fee_bps: 100 }, min_gain_bps: 200 })
+}
+
+/// Observes both vaults for a window in which B's share price grows 1% and A's barely moves
+fn observe_window(setup: &Setup, bot: &mut Bot) {
+    assert_eq!(bot.tick(1_000), vec![]);
+    let mut chain = setup.chain.lock().unwrap();
+    chain.vaults.get_mut(&setup.vault_a).unwrap().share_price = 10_001_000;
+    chain.vaults.get_mut(&setup.vault_b).unwrap().share_price = 10_100_000;
+}
+
+fn migrate_a_to_b(setup: &Setup) -> Action {
+    Action::Migrate {
+        from_vault: setup.vault_a.clone(),
+        to_vault: setup.vault_b.clone(),
+        shares: 10_000_000_000,
+    }
+}
+
+#[test]
+fn test_tracker_apy_needs_full_window() {
+    let mut tracker = Tracker::default();
+    let vault = contract(10);
+
+    tracker.record(&vault, Sample { timestamp: 0, share_price: PRICE_SCALE }, WINDOW);
+    tracker.record(&vault, Sample { timestamp: 500, share_price: 10_005_000 }, WINDOW);
+    assert_eq!(tracker.apy_bps(&vault, WINDOW), None);
+
+    // 10 bps over 1000 seconds, annualized
+    tracker.record(&vault, Sample { timestamp: 1_000, share_price: 10_010_000 }, WINDOW);
+    assert_eq!(tracker.apy_bps(&vault, WINDOW), Some(10 * 31_536));
+
+    // Samples older than the window's base are dropped
+    tracker.record(&vault, Sample { timestamp: 1_600, share_price: 10_016_000 }, WINDOW);
+    assert_eq!(tracker.samples(&vault).len(), 3);
+    assert_eq!(tracker.samples(&vault)[0].timestamp, 500);
+
+    assert_eq!(Sample::from_preview(0, 10_000_000, 9_900_990).unwrap().share_price, 10_100_000);
+    assert_eq!(Sample::from_preview(0, 10_000_000, 0), None);
+}
+
+#[test]
+fn test_policies_decide_from_stats() {
+    let (usdc, a, b, c) = (contract(3), contract(10), contract(11), contract(12));
+    let stats = |vault: &str, apy: Option<i128>, fee_bps: u32| VaultStats {
+        vault: vault.to_string(),
+        asset: usdc.clone(),
+        tvl: 1_000,
+        fee_bps,
+        apy_bps: apy,
+    };
+    let vaults = vec![stats(&a, Some(300), 10), stats(&b, Some(450), 10), stats(&c, Some(900), 500)];
+    let holdings = vec![Holding { vault: a.clone(), shares: 100 }];
+    let delegation = Delegation {
+        vaults: vec![a.clone(), b.clone(), c.clone()],
+        tokens: vec![usdc.clone()],
+        max_slippage_bps: 100,
+        expires_at: u64::MAX,
+    };
+    let state = UserState { user: "user", holdings: &holdings, delegation: &delegation, vaults: &vaults };
+
+    // C pays the most but fails the fee screen; B beats A by 150 bps
+    let apy = |min_gain_bps| ApyPolicy { screen: Screen { min_tvl: 0, max_fee_bps: 100 }, min_gain_bps };
+    assert_eq!(
+        apy(150).decide(&state),
+        vec![Action::Migrate { from_vault: a.clone(), to_vault: b.clone(), shares: 100 }]
+    );
+    assert_eq!(apy(151).decide(&state), vec![]);
+
+    // Without the fee screen the spread covers B and C and exits A
+    let spread = SpreadPolicy { screen: Screen { min_tvl: 0, max_fee_bps: 1_000 }, count: 2, min_gain_bps: 100 };
+    assert_eq!(
+        spread.decide(&state),
+        vec![Action::Rebalance { targets: vec![(c.clone(), 5_000), (b.clone(), 5_000), (a.clone(), 0)] }]
+    );
+
+    // Vaults the keeper was not delegated are never targeted
+    let narrow = Delegation { vaults: vec![a.clone()], ..delegation.clone() };
+    let state = UserState { delegation: &narrow, ..state };
+    assert_eq!(apy(0).decide(&state), vec![]);
+    assert_eq!(spread.decide(&state), vec![]);
+}
+
+#[test]
+fn test_dry_run_simulates_without_submitting() {
+    let setup = setup();
+    let mut bot = bot(&setup, apy_policy(), true);
+    observe_window(&setup, &mut bot);
+
+    let outcomes = bot.tick(2_000);
+    assert_eq!(
+        outcomes,
+        vec![Outcome {
+            user: setup.user.clone(),
+            action: migrate_a_to_b(&setup),
+            min_amount_out: Some(9_850_500_000),
+            status: Status::Simulated,
+        }]
+    );
+
+    let chain = setup.chain.lock().unwrap();
+    assert!(chain.sent.is_empty());
+    // The quote is rebuilt with the slippage floor before it would be submitted
+    let (function, args) = chain.calls.last().unwrap();
+    assert_eq!(function, "operator_migrate");
+    assert_eq!(scval::to_address(&args[0]).unwrap(), keeper().address());
+    assert_eq!(scval::to_i128(&args[7]).unwrap(), 9_850_500_000);
+    assert_eq!(args[8], ScVal::Void);
+}
+
+#[test]
+fn test_submits_signed_transaction() {
+    let setup = setup();
+    let mut bot = bot(&setup, apy_policy(), false);
+    observe_window(&setup, &mut bot);
+
+    let outcomes = bot.tick(2_000);
+    assert_eq!(outcomes.len(), 1);
+    let Status::Submitted { hash } = &outcomes[0].status else {
+        panic!("expected a submission, got {:?}", outcomes[0].status);
+    };
+
+    let chain = setup.chain.lock().unwrap();
+    assert_eq!(chain.sent.len(), 1);
+    let TransactionEnvelope::Tx(envelope) = &chain.sent[0] else {
+        panic!("unexpected envelope type");
+    };
+    let tx = &envelope.tx;
+    assert_eq!(tx.seq_num.0, stand_in::SEQUENCE + 1);
+    assert_eq!(tx.fee, BASE_FEE + stand_in::RESOURCE_FEE);
+    assert!(matches!(tx.ext, TransactionExt::V1(_)));
+    assert!(matches!(tx.operations[0].body, OperationBody::InvokeHostFunction(_)));
+
+    // Signed by the keeper over the network-bound hash the outcome reports
+    let tx_hash = tx::hash(tx, PASSPHRASE).unwrap();
+    let hex: String = tx_hash.iter().map(|b| format!("{b:02x}")).collect();
+    assert_eq!(&hex, hash);
+    let key = VerifyingKey::from_bytes(&keeper().public_key()).unwrap();
+    let signature = DalekSignature::from_slice(envelope.signatures[0].signature.0.as_slice()).unwrap();
+    assert!(key.verify(&tx_hash, &signature).is_ok());
+}
+
+#[test]
+fn test_spread_policy_simulates_rebalance() {
+    let setup = setup();
+    let policy = SpreadPolicy { screen: Screen { min_tvl: 0, max_fee_bps: 100 }, count: 1, min_gain_bps: 200 };
+    let mut bot = bot(&setup, Box::new(policy), true);
+    observe_window(&setup, &mut bot);
+    // The user's policy is tighter than the bot's 1% setting
+    setup.chain.lock().unwrap().delegations.get_mut(&setup.user).unwrap().3 = 50;
+
+    let outcomes = bot.tick(2_000);
+    let targets = vec![(setup.vault_b.clone(), 10_000), (setup.vault_a.clone(), 0)];
+    assert_eq!(outcomes[0].action, Action::Rebalance { targets });
+    assert_eq!(outcomes[0].status, Status::Simulated);
+
+    let chain = setup.chain.lock().unwrap();
+    let (function, args) = chain.calls.last().unwrap();
+    assert_eq!(function, "operator_rebalance");
+    let targets = scval::to_vec(&args[2]).unwrap();
+    let first = scval::to_vec(&targets[0]).unwrap();
+    assert_eq!(scval::to_address(&first[0]).unwrap(), setup.vault_b);
+    assert_eq!(scval::to_u32(&first[1]).unwrap(), 10_000);
+    assert_eq!(scval::to_u32(&args[3]).unwrap(), 50);
+}
+
+#[test]
+fn test_failed_simulation_is_reported() {
+    let setup = setup();
+    let mut bot = bot(&setup, apy_policy(), false);
+    observe_window(&setup, &mut bot);
+    setup.chain.lock().unwrap().fail = Some("HostError: Error(Contract, #16)".into());
+
+    let outcomes = bot.tick(2_000);
+    assert_eq!(outcomes.len(), 1);
+    assert_eq!(outcomes[0].action, migrate_a_to_b(&setup));
+    match &outcomes[0].status {
+        Status::Failed { error } => assert!(error.contains("Error(Contract, #16)")),
+        other => panic!("expected a failure, got {other:?}"),
+    }
+    assert!(setup.chain.lock().unwrap().sent.is_empty());
+}
+
+#[test]
+fn test_expired_delegation_is_skipped() {
+    let setup = setup();
+    let mut bot = bot(&setup, apy_policy(), true);
+    observe_window(&setup, &mut bot);
+    setup.chain.lock().unwrap().delegations.get_mut(&setup.user).unwrap().2 = 1_500;
+
+    assert_eq!(bot.tick(2_000), vec![]);
+}
+
+#[test]
+fn test_min_amount_out_is_checked() {
+    assert_eq!(min_amount_out(1_000_000, 50).unwrap(), 995_000);
+    assert_eq!(min_amount_out(1_000_000, 10_000).unwrap(), 0);
+    assert!(matches!(min_amount_out(i128::MAX, 50), Err(Error::Overflow(_))));
+}
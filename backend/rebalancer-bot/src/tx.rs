@@ -0,0 +1,115 @@
+//! Building, assembling and signing Zap contract invocations
+
+use ed25519_dalek::{Signer, SigningKey};
+use sha2::{Digest, Sha256};
+use stellar_xdr::curr::{
+    DecoratedSignature, Hash, HostFunction, InvokeContractArgs, InvokeHostFunctionOp, Limits, Memo,
+    MuxedAccount, Operation, OperationBody, Preconditions, ScSymbol, ScVal, SequenceNumber,
+    Signature, SignatureHint, Transaction, TransactionEnvelope, TransactionExt,
+    TransactionSignaturePayload, TransactionSignaturePayloadTaggedTransaction,
+    TransactionV1Envelope, Uint256, VecM, WriteXdr,
+};
+
+use crate::error::{Error, Result};
+use crate::rpc::Simulation;
+use crate::scval;
+
+/// Inclusion fee offered on top of the simulated resource fee, in stroops
+pub const BASE_FEE: u32 = 100;
+
+/// The keeper account that signs and pays for the bot's transactions
+pub struct Keypair {
+    key: SigningKey,
+}
+
+impl Keypair {
+    /// Parses an `S...` secret seed
+    pub fn from_secret(secret: &str) -> Result<Self> {
+        let seed = stellar_strkey::ed25519::PrivateKey::from_string(secret)
+            .map_err(|_| Error::Config("keeper secret is not a valid S... seed".into()))?;
+        Ok(Self { key: SigningKey::from_bytes(&seed.0) })
+    }
+
+    pub fn public_key(&self) -> [u8; 32] {
+        self.key.verifying_key().to_bytes()
+    }
+
+    /// The `G...` address of the keeper
+    pub fn address(&self) -> String {
+        stellar_strkey::ed25519::PublicKey(self.public_key()).to_string()
+    }
+}
+
+/// An unsigned transaction invoking `function` on `contract` from `source`
+pub fn invoke(
+    source: &Keypair,
+    sequence: i64,
+    contract: &str,
+    function: &str,
+    args: Vec<ScVal>,
+) -> Result<Transaction> {
+    let op = Operation {
+        source_account: None,
+        body: OperationBody::InvokeHostFunction(InvokeHostFunctionOp {
+            host_function: HostFunction::InvokeContract(InvokeContractArgs {
+                contract_address: scval::sc_address(contract)?,
+                function_name: ScSymbol(function.try_into()?),
+                args: args.try_into()?,
+            }),
+            auth: VecM::default(),
+        }),
+    };
+    Ok(Transaction {
+        source_account: MuxedAccount::Ed25519(Uint256(source.public_key())),
+        fee: BASE_FEE,
+        seq_num: SequenceNumber(sequence),
+        cond: Preconditions::None,
+        memo: Memo::None,
+        operations: vec![op].try_into()?,
+        ext: TransactionExt::V0,
+    })
+}
+
+/// Applies a simulation to its transaction: footprint, resource fee and authorizations
+pub fn assemble(mut tx: Transaction, simulation: &Simulation) -> Result<Transaction> {
+    let mut operations = tx.operations.to_vec();
+    let Some(OperationBody::InvokeHostFunction(op)) = operations.first_mut().map(|op| &mut op.body)
+    else {
+        return Err(Error::Config("transaction does not invoke a contract".into()));
+    };
+    op.auth = simulation.auth.clone().try_into()?;
+    tx.operations = operations.try_into()?;
+    tx.fee = tx
+        .fee
+        .checked_add(simulation.min_resource_fee)
+        .ok_or_else(|| Error::Simulation("resource fee overflows".into()))?;
+    tx.ext = TransactionExt::V1(simulation.transaction_data.clone());
+    Ok(tx)
+}
+
+pub fn unsigned(tx: Transaction) -> TransactionEnvelope {
+    TransactionEnvelope::Tx(TransactionV1Envelope { tx, signatures: VecM::default() })
+}
+
+/// Hash that identifies the transaction on `passphrase`'s network and that is signed
+pub fn hash(tx: &Transaction, passphrase: &str) -> Result<[u8; 32]> {
+    let payload = TransactionSignaturePayload {
+        network_id: Hash(Sha256::digest(passphrase.as_bytes()).into()),
+        tagged_transaction: TransactionSignaturePayloadTaggedTransaction::Tx(tx.clone()),
+    };
+    Ok(Sha256::digest(payload.to_xdr(Limits::none())?).into())
+}
+
+pub fn sign(tx: Transaction, keypair: &Keypair, passphrase: &str) -> Result<TransactionEnvelope> {
+    let signature = keypair.key.sign(&hash(&tx, passphrase)?);
+    let public_key = keypair.public_key();
+    let hint = SignatureHint(public_key[28..].try_into().expect("hint is 4 bytes"));
+    let decorated = DecoratedSignature {
+        hint,
+        signature: Signature(signature.to_bytes().to_vec().try_into()?),
+    };
+    Ok(TransactionEnvelope::Tx(TransactionV1Envelope {
+        tx,
+        signatures: vec![decorated].try_into()?,
+    }))
+}
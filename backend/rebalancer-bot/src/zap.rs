@@ -0,0 +1,153 @@
+//! Typed access to the Zap contract through simulation and signed submission
+
+use std::thread;
+use std::time::Duration;
+use stellar_xdr::curr::{ScVal, Transaction};
+
+use crate::error::{Error, Result};
+use crate::rpc::{RpcClient, Simulation, TxStatus};
+use crate::scval;
+use crate::tx::{self, Keypair};
+
+/// The parts of a user's `OperatorPolicy` for the keeper that the bot checks
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Delegation {
+    pub vaults: Vec<String>,
+    pub tokens: Vec<String>,
+    /// Largest slippage the keeper may accept; the Zap floors its swaps at the TWAP less this
+    pub max_slippage_bps: u32,
+    pub expires_at: u64,
+}
+
+/// `get_vault_info` fields, in `VaultInfo` order: asset, total_assets, total_shares, name,
+/// symbol, decimals, fee, strategy
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct VaultInfo {
+    pub asset: String,
+    pub total_assets: i128,
+    pub total_shares: i128,
+    pub fee_bps: u32,
+}
+
+/// A simulated call, ready to be signed and submitted
+#[derive(Debug, Clone)]
+pub struct Prepared {
+    tx: Transaction,
+    pub simulation: Simulation,
+}
+
+pub struct ZapClient {
+    rpc: RpcClient,
+    keeper: Keypair,
+    contract: String,
+    passphrase: String,
+    /// How often and how many times `submit` polls for the transaction result
+    pub confirm_interval: Duration,
+    pub confirm_attempts: u32,
+}
+
+impl ZapClient {
+    pub fn new(rpc: RpcClient, keeper: Keypair, contract: String, passphrase: String) -> Self {
+        Self {
+            rpc,
+            keeper,
+            contract,
+            passphrase,
+            confirm_interval: Duration::from_secs(2),
+            confirm_attempts: 30,
+        }
+    }
+
+    pub fn keeper(&self) -> String {
+        self.keeper.address()
+    }
+
+    /// Simulates `function` on `contract` from the keeper account. Simulation ignores the
+    /// sequence number, so it is only fetched on submission.
+    fn simulate_on(&self, contract: &str, function: &str, args: Vec<ScVal>) -> Result<Prepared> {
+        let tx = tx::invoke(&self.keeper, 0, contract, function, args)?;
+        let simulation = self.rpc.simulate(&tx::unsigned(tx.clone()))?;
+        Ok(Prepared { tx, simulation })
+    }
+
+    /// Simulates a Zap call that `submit` can then send
+    pub fn simulate(&self, function: &str, args: Vec<ScVal>) -> Result<Prepared> {
+        self.simulate_on(&self.contract, function, args)
+    }
+
+    fn view(&self, contract: &str, function: &str, args: Vec<ScVal>) -> Result<ScVal> {
+        Ok(self.simulate_on(contract, function, args)?.simulation.result)
+    }
+
+    /// Signs and submits a simulated call, then waits for it to succeed. Returns the hash.
+    pub fn submit(&self, prepared: Prepared) -> Result<String> {
+        let sequence = self.rpc.account_sequence(&self.keeper.address())?;
+        let mut tx = tx::assemble(prepared.tx, &prepared.simulation)?;
+        tx.seq_num.0 = sequence + 1;
+
+        let hash: String = tx::hash(&tx, &self.passphrase)?.iter().map(|b| format!("{b:02x}")).collect();
+        self.rpc.send(&tx::sign(tx, &self.keeper, &self.passphrase)?)?;
+
+        for _ in 0..self.confirm_attempts {
+            match self.rpc.transaction_status(&hash)? {
+                TxStatus::Success => return Ok(hash),
+                TxStatus::Failed => return Err(Error::Transaction(format!("{hash} failed"))),
+                TxStatus::Pending => thread::sleep(self.confirm_interval),
+            }
+        }
+        Err(Error::Transaction(format!("{hash} not confirmed")))
+    }
+
+    /// Vaults the user has a position in, from `get_positions`
+    pub fn position_vaults(&self, user: &str) -> Result<Vec<String>> {
+        let positions = self.view(&self.contract, "get_positions", vec![scval::address(user)?])?;
+        scval::to_vec(&positions)?
+            .iter()
+            .map(|position| scval::to_address(scval::field(position, "vault")?))
+            .collect()
+    }
+
+    /// The user's delegation to the keeper, from `get_operator`
+    pub fn delegation(&self, user: &str) -> Result<Option<Delegation>> {
+        let args = vec![scval::address(user)?, scval::address(&self.keeper())?];
+        let policy = self.view(&self.contract, "get_operator", args)?;
+        let Some(policy) = scval::to_option(&policy) else {
+            return Ok(None);
+        };
+
+        let addresses = |name| -> Result<Vec<String>> {
+            scval::to_vec(scval::field(policy, name)?)?.iter().map(scval::to_address).collect()
+        };
+        Ok(Some(Delegation {
+            vaults: addresses("vaults")?,
+            tokens: addresses("tokens")?,
+            max_slippage_bps: scval::to_u32(scval::field(policy, "max_slippage_bps")?)?,
+            expires_at: scval::to_u64(scval::field(policy, "expires_at")?)?,
+        }))
+    }
+
+    pub fn vault_info(&self, vault: &str) -> Result<VaultInfo> {
+        let info = self.view(&self.contract, "get_vault_info", vec![scval::address(vault)?])?;
+        let info = scval::to_vec(&info)?;
+        if info.len() < 7 {
+            return Err(Error::Decode(format!("get_vault_info returned {} fields", info.len())));
+        }
+        Ok(VaultInfo {
+            asset: scval::to_address(&info[0])?,
+            total_assets: scval::to_i128(&info[1])?,
+            total_shares: scval::to_i128(&info[2])?,
+            fee_bps: scval::to_u32(&info[6])?,
+        })
+    }
+
+    /// Shares `amount` of the vault asset would mint, from `preview_vault_deposit`
+    pub fn preview_deposit(&self, vault: &str, amount: i128) -> Result<i128> {
+        let args = vec![scval::address(vault)?, scval::i128(amount)];
+        scval::to_i128(&self.view(&self.contract, "preview_vault_deposit", args)?)
+    }
+
+    /// The user's share balance, read from the vault's token interface
+    pub fn share_balance(&self, vault: &str, user: &str) -> Result<i128> {
+        scval::to_i128(&self.view(vault, "balance", vec![scval::address(user)?])?)
+    }
+}
@@ -0,0 +1,22 @@
+//! `i128` amounts as decimal strings, as the contract's JSON clients carry them. Plain JSON numbers
+//! are accepted on input for hand-written files.
+
+use serde::{de::Error, Deserialize, Deserializer, Serializer};
+
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum Raw {
+    Text(String),
+    Number(i64),
+}
+
+pub fn serialize<S: Serializer>(value: &i128, serializer: S) -> Result<S::Ok, S::Error> {
+    serializer.serialize_str(&value.to_string())
+}
+
+pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<i128, D::Error> {
+    match Raw::deserialize(deserializer)? {
+        Raw::Text(text) => text.parse().map_err(D::Error::custom),
+        Raw::Number(number) => Ok(number as i128),
+    }
+}
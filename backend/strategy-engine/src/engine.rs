@@ -0,0 +1,229 @@
+//! Ranking vaults and splitting funds across them
+
+use serde::{Deserialize, Serialize};
+
+use crate::amount;
+use crate::series::{Metrics, VaultSeries};
+
+pub const BPS: u32 = 10_000;
+
+/// How much risk the user accepts
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct RiskParams {
+    /// Percent of volatility subtracted from the APY when scoring: at 50, a vault with 1000 bps
+    /// of volatility scores 500 bps below its APY
+    pub risk_aversion_pct: u32,
+    pub max_volatility_bps: Option<i64>,
+    pub max_drawdown_bps: Option<i64>,
+    /// Vaults observed for less than this are not ranked
+    pub min_history_secs: u64,
+    #[serde(with = "amount")]
+    pub min_tvl: i128,
+    pub max_fee_bps: u32,
+    /// Largest share of the allocation one vault may take. Raised to an even split when too
+    /// few vaults qualify to reach 100% under it.
+    pub max_weight_bps: u32,
+}
+
+impl Default for RiskParams {
+    fn default() -> Self {
+        Self {
+            risk_aversion_pct: 50,
+            max_volatility_bps: None,
+            max_drawdown_bps: None,
+            min_history_secs: 7 * 24 * 3_600,
+            min_tvl: 0,
+            max_fee_bps: BPS,
+            max_weight_bps: BPS,
+        }
+    }
+}
+
+/// Which vaults the user is willing to hold
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Constraints {
+    /// Vault assets allowed; empty allows all
+    pub assets: Vec<String>,
+    /// Vaults allowed; empty allows all
+    pub vaults: Vec<String>,
+    pub excluded_vaults: Vec<String>,
+    /// Most vaults in the allocation; None for no limit
+    pub max_vaults: Option<usize>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Input {
+    pub vaults: Vec<VaultSeries>,
+    #[serde(default)]
+    pub risk: RiskParams,
+    #[serde(default)]
+    pub constraints: Constraints,
+}
+
+/// Why a vault was not ranked
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Reason {
+    NotAllowed,
+    InsufficientHistory,
+    BelowMinTvl,
+    FeeTooHigh,
+    VolatilityTooHigh,
+    DrawdownTooHigh,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct Recommendation {
+    pub vault: String,
+    pub asset: String,
+    /// 1 for the best vault; None when the vault was screened out
+    pub rank: Option<u32>,
+    /// Risk-adjusted APY the ranking uses
+    pub score_bps: Option<i64>,
+    pub weight_bps: u32,
+    pub metrics: Option<Metrics>,
+    pub reasons: Vec<Reason>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct Allocation {
+    pub vault: String,
+    pub weight_bps: u32,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct Report {
+    /// Ranked vaults first, best first, then screened-out vaults in input order
+    pub recommendations: Vec<Recommendation>,
+    /// Target weights summing to 10_000 bps; empty when no vault has a positive score
+    pub allocation: Vec<Allocation>,
+}
+
+fn screen(series: &VaultSeries, metrics: Option<&Metrics>, input: &Input) -> Vec<Reason> {
+    let (risk, constraints) = (&input.risk, &input.constraints);
+    let mut reasons = Vec::new();
+    if (!constraints.assets.is_empty() && !constraints.assets.contains(&series.asset))
+        || (!constraints.vaults.is_empty() && !constraints.vaults.contains(&series.vault))
+        || constraints.excluded_vaults.contains(&series.vault)
+    {
+        reasons.push(Reason::NotAllowed);
+    }
+    let Some(metrics) = metrics.filter(|m| m.history_secs >= risk.min_history_secs) else {
+        reasons.push(Reason::InsufficientHistory);
+        return reasons;
+    };
+    if metrics.tvl < risk.min_tvl {
+        reasons.push(Reason::BelowMinTvl);
+    }
+    if metrics.fee_bps > risk.max_fee_bps {
+        reasons.push(Reason::FeeTooHigh);
+    }
+    if risk.max_volatility_bps.is_some_and(|max| metrics.volatility_bps > max) {
+        reasons.push(Reason::VolatilityTooHigh);
+    }
+    if risk.max_drawdown_bps.is_some_and(|max| metrics.max_drawdown_bps > max) {
+        reasons.push(Reason::DrawdownTooHigh);
+    }
+    reasons
+}
+
+/// Ranks the vaults and allocates across the best of them
+pub fn recommend(input: &Input) -> Report {
+    let mut ranked = Vec::new();
+    let mut screened = Vec::new();
+    for series in &input.vaults {
+        let metrics = series.metrics();
+        let reasons = screen(series, metrics.as_ref(), input);
+        let score_bps = metrics.as_ref().filter(|_| reasons.is_empty()).map(|m| {
+            m.apy_bps - m.volatility_bps * input.risk.risk_aversion_pct as i64 / 100
+        });
+        let recommendation = Recommendation {
+            vault: series.vault.clone(),
+            asset: series.asset.clone(),
+            rank: None,
+            score_bps,
+            weight_bps: 0,
+            metrics,
+            reasons,
+        };
+        if score_bps.is_some() {
+            ranked.push(recommendation);
+        } else {
+            screened.push(recommendation);
+        }
+    }
+
+    // Ties go to the larger vault, then the address, so the output is deterministic
+    ranked.sort_by(|a, b| {
+        let tvl = |r: &Recommendation| r.metrics.as_ref().map_or(0, |m| m.tvl);
+        b.score_bps.cmp(&a.score_bps).then(tvl(b).cmp(&tvl(a))).then(a.vault.cmp(&b.vault))
+    });
+    for (i, recommendation) in ranked.iter_mut().enumerate() {
+        recommendation.rank = Some(i as u32 + 1);
+    }
+
+    let picks: Vec<(usize, i64)> = ranked
+        .iter()
+        .enumerate()
+        .filter_map(|(i, r)| r.score_bps.filter(|score| *score > 0).map(|score| (i, score)))
+        .take(input.constraints.max_vaults.unwrap_or(usize::MAX))
+        .collect();
+    let scores: Vec<i64> = picks.iter().map(|(_, score)| *score).collect();
+    let weights = allocate(&scores, input.risk.max_weight_bps);
+
+    let mut allocation = Vec::new();
+    for ((i, _), weight_bps) in picks.iter().zip(weights) {
+        ranked[*i].weight_bps = weight_bps;
+        allocation.push(Allocation { vault: ranked[*i].vault.clone(), weight_bps });
+    }
+
+    ranked.extend(screened);
+    Report { recommendations: ranked, allocation }
+}
+
+/// Splits 10_000 bps in proportion to positive `scores`, with no weight above `max_weight_bps`.
+/// Capped weights' excess is spread over the others in proportion to their scores; rounding
+/// leftovers go to the largest remainders, best score first on ties.
+pub fn allocate(scores: &[i64], max_weight_bps: u32) -> Vec<u32> {
+    let n = scores.len();
+    if n == 0 {
+        return Vec::new();
+    }
+    let cap = max_weight_bps.max(BPS.div_ceil(n as u32)) as f64;
+
+    let mut weights = vec![0.0; n];
+    let mut capped = vec![false; n];
+    loop {
+        let remaining = BPS as f64 - capped.iter().filter(|c| **c).count() as f64 * cap;
+        let free_score: f64 = (0..n).filter(|i| !capped[*i]).map(|i| scores[i] as f64).sum();
+        let mut changed = false;
+        for i in 0..n {
+            if capped[i] {
+                continue;
+            }
+            weights[i] = remaining * scores[i] as f64 / free_score;
+            if weights[i] > cap {
+                weights[i] = cap;
+                capped[i] = true;
+                changed = true;
+            }
+        }
+        if !changed {
+            break;
+        }
+    }
+
+    let mut result: Vec<u32> = weights.iter().map(|w| w.floor() as u32).collect();
+    let leftover = BPS - result.iter().sum::<u32>();
+    let mut order: Vec<usize> = (0..n).collect();
+    order.sort_by(|&a, &b| {
+        let fraction = |i: usize| weights[i] - weights[i].floor();
+        fraction(b).total_cmp(&fraction(a)).then(a.cmp(&b))
+    });
+    for &i in order.iter().take(leftover as usize) {
+        result[i] += 1;
+    }
+    result
+}
@@ -0,0 +1,16 @@
+//! Ranks YieldZap vaults by risk-adjusted return and proposes target allocations
+//!
+//! The input is each vault's share-price and TVL history, as sampled from `get_vault_info` or
+//! the Zap's `get_vault_samples`. The targets can be fed to the Zap's `rebalance`.
+
+mod amount;
+pub mod engine;
+pub mod series;
+
+pub use engine::{
+    allocate, recommend, Allocation, Constraints, Input, Reason, Recommendation, Report, RiskParams,
+};
+pub use series::{Metrics, Observation, VaultSeries};
+
+#[cfg(test)]
+mod test;
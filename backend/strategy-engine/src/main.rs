@@ -0,0 +1,34 @@
+use std::io::Read;
+use std::process::exit;
+
+use strategy_engine::{recommend, Input};
+
+const USAGE: &str = "usage: strategy-engine [INPUT.json]   (reads stdin without a file)";
+
+fn main() {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    let json = match args.as_slice() {
+        [] => {
+            let mut json = String::new();
+            std::io::stdin().read_to_string(&mut json).map(|_| json)
+        }
+        [path] if !path.starts_with('-') => std::fs::read_to_string(path),
+        _ => {
+            eprintln!("{USAGE}");
+            exit(2);
+        }
+    };
+
+    let input: Input = match json.map_err(|err| err.to_string()).and_then(|json| {
+        serde_json::from_str(&json).map_err(|err| format!("invalid input: {err}"))
+    }) {
+        Ok(input) => input,
+        Err(err) => {
+            eprintln!("error: {err}");
+            exit(1);
+        }
+    };
+
+    let report = recommend(&input);
+    println!("{}", serde_json::to_string_pretty(&report).expect("report serializes"));
+}
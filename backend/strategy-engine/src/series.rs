@@ -0,0 +1,104 @@
+//! Vault time series and the performance metrics derived from them
+
+use serde::{Deserialize, Serialize};
+
+use crate::amount;
+
+pub const SECONDS_PER_YEAR: f64 = 31_536_000.0;
+
+/// One observation of a vault, as read from `get_vault_info` or the Zap's `get_vault_samples`
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Observation {
+    pub timestamp: u64,
+    #[serde(with = "amount")]
+    pub total_assets: i128,
+    #[serde(with = "amount")]
+    pub total_shares: i128,
+    /// Vault fee in bps at the time of the observation
+    #[serde(default)]
+    pub fee_bps: u32,
+}
+
+impl Observation {
+    /// Assets per share; None for an empty vault
+    pub fn share_price(&self) -> Option<f64> {
+        (self.total_assets > 0 && self.total_shares > 0)
+            .then(|| self.total_assets as f64 / self.total_shares as f64)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct VaultSeries {
+    pub vault: String,
+    pub asset: String,
+    /// Observations in any order; they are sorted by timestamp before use
+    pub observations: Vec<Observation>,
+}
+
+/// Performance of a vault over its series. Rates are annualized, in bps.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct Metrics {
+    /// Linear annualized share-price growth. The vault takes its fees out of the share price,
+    /// so this rate already reflects them and `fee_bps` is not deducted again.
+    pub apy_bps: i64,
+    /// Annualized standard deviation of share-price returns
+    pub volatility_bps: i64,
+    /// Largest peak-to-trough share-price decline
+    pub max_drawdown_bps: i64,
+    #[serde(with = "amount")]
+    pub tvl: i128,
+    pub fee_bps: u32,
+    /// Seconds between the first and last observation
+    pub history_secs: u64,
+}
+
+impl VaultSeries {
+    /// None when fewer than two observations have a share price or no time passed between them
+    pub fn metrics(&self) -> Option<Metrics> {
+        let mut observations: Vec<_> = self.observations.iter().collect();
+        observations.sort_by_key(|o| o.timestamp);
+        let last = *observations.last()?;
+
+        let prices: Vec<(u64, f64)> = observations
+            .iter()
+            .filter_map(|o| o.share_price().map(|price| (o.timestamp, price)))
+            .collect();
+        let (&(first_time, first_price), &(last_time, last_price)) = (prices.first()?, prices.last()?);
+        let history_secs = last_time - first_time;
+        if history_secs == 0 {
+            return None;
+        }
+
+        let apy = (last_price / first_price - 1.0) * SECONDS_PER_YEAR / history_secs as f64;
+
+        // Returns scaled to one second, so uneven sampling intervals are comparable
+        let returns: Vec<f64> = prices
+            .windows(2)
+            .filter(|pair| pair[1].0 > pair[0].0)
+            .map(|pair| (pair[1].1 / pair[0].1 - 1.0) / ((pair[1].0 - pair[0].0) as f64).sqrt())
+            .collect();
+        let mean = returns.iter().sum::<f64>() / returns.len() as f64;
+        let variance = returns.iter().map(|r| (r - mean).powi(2)).sum::<f64>() / returns.len() as f64;
+        let volatility = variance.sqrt() * SECONDS_PER_YEAR.sqrt();
+
+        let mut peak = first_price;
+        let mut max_drawdown: f64 = 0.0;
+        for &(_, price) in &prices {
+            peak = peak.max(price);
+            max_drawdown = max_drawdown.max(1.0 - price / peak);
+        }
+
+        Some(Metrics {
+            apy_bps: bps(apy),
+            volatility_bps: bps(volatility),
+            max_drawdown_bps: bps(max_drawdown),
+            tvl: last.total_assets,
+            fee_bps: last.fee_bps,
+            history_secs,
+        })
+    }
+}
+
+fn bps(rate: f64) -> i64 {
+    (rate * 10_000.0).round() as i64
+}
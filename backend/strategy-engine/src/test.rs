@@ -0,0 +1,197 @@
+use serde_json::json;
+
+use crate::{
+    allocate, recommend, Allocation, Constraints, Input, Observation, Reason, RiskParams, VaultSeries,
+};
+
+const DAY: u64 = 86_400;
+const SHARES: i128 = 10_000_000_000;
+
+/// A vault observed daily at the given share prices (scaled by 1e7)
+fn series(vault: &str, asset: &str, prices: &[i128], tvl_scale: i128, fee_bps: u32) -> VaultSeries {
+    VaultSeries {
+        vault: vault.to_string(),
+        asset: asset.to_string(),
+        observations: prices
+            .iter()
+            .enumerate()
+            .map(|(day, price)| Observation {
+                timestamp: 1_700_000_000 + day as u64 * DAY,
+                total_assets: SHARES * price / 10_000_000 * tvl_scale,
+                total_shares: SHARES * tvl_scale,
+                fee_bps,
+            })
+            .collect(),
+    }
+}
+
+/// Ten days of linear growth to `1 + gain`
+fn steady(vault: &str, gain: i128) -> VaultSeries {
+    let prices: Vec<i128> = (0..=10).map(|day| 10_000_000 + gain * day / 10).collect();
+    series(vault, "USDC", &prices, 1, 50)
+}
+
+/// Ten days of 5% swings around a 4% rise
+fn choppy(vault: &str) -> VaultSeries {
+    let prices: Vec<i128> = (0..=10)
+        .map(|day| 10_000_000 + 40_000 * day + if day % 2 == 1 { 500_000 } else { 0 })
+        .collect();
+    series(vault, "USDC", &prices, 1, 50)
+}
+
+fn input(vaults: Vec<VaultSeries>, risk: RiskParams) -> Input {
+    Input { vaults, risk, constraints: Constraints::default() }
+}
+
+#[test]
+fn test_metrics_from_series() {
+    // 2% in 10 days is 73% a year
+    let metrics = steady("A", 200_000).metrics().unwrap();
+    assert_eq!(metrics.apy_bps, 7_300);
+    assert!(metrics.volatility_bps <= 5, "{}", metrics.volatility_bps);
+    assert_eq!(metrics.max_drawdown_bps, 0);
+    assert_eq!(metrics.tvl, 10_200_000_000);
+    assert_eq!(metrics.fee_bps, 50);
+    assert_eq!(metrics.history_secs, 10 * DAY);
+
+    // Peak 1.10 to trough 0.99 is a 10% drawdown; observation order does not matter
+    let mut dip = series("B", "USDC", &[10_000_000, 11_000_000, 9_900_000, 10_500_000], 1, 0);
+    dip.observations.reverse();
+    let metrics = dip.metrics().unwrap();
+    assert_eq!(metrics.max_drawdown_bps, 1_000);
+    assert!(metrics.volatility_bps > 10_000);
+
+    assert_eq!(series("C", "USDC", &[10_000_000], 1, 0).metrics(), None);
+}
+
+#[test]
+fn test_risk_aversion_changes_ranking() {
+    let risk = |risk_aversion_pct| RiskParams { risk_aversion_pct, ..RiskParams::default() };
+    let ranking = |report: crate::Report| -> Vec<String> {
+        report.recommendations.into_iter().map(|r| r.vault).collect()
+    };
+
+    // The choppy vault pays more but swings hard
+    let vaults = vec![steady("steady", 200_000), choppy("choppy")];
+    assert_eq!(ranking(recommend(&input(vaults.clone(), risk(0)))), ["choppy", "steady"]);
+    assert_eq!(ranking(recommend(&input(vaults.clone(), risk(100)))), ["steady", "choppy"]);
+
+    let report = recommend(&input(vaults, risk(100)));
+    let best = &report.recommendations[0];
+    let metrics = best.metrics.as_ref().unwrap();
+    assert_eq!(best.score_bps, Some(metrics.apy_bps - metrics.volatility_bps));
+    assert_eq!(report.recommendations[0].rank, Some(1));
+}
+
+#[test]
+fn test_screens_explain_exclusions() {
+    let vaults = vec![
+        steady("ok", 100_000),
+        series("fees", "USDC", &[10_000_000; 11], 1, 2_000),
+        series("young", "USDC", &[10_000_000, 10_010_000], 1, 0),
+        VaultSeries { asset: "EURC".into(), ..steady("euro", 300_000) },
+        series("tiny", "USDC", &[10_000_000; 11], 0, 0),
+    ];
+    let risk = RiskParams { min_tvl: 1, max_fee_bps: 1_000, ..RiskParams::default() };
+    let constraints = Constraints { assets: vec!["USDC".into()], ..Constraints::default() };
+    let report = recommend(&Input { vaults, risk, constraints });
+
+    let reasons: Vec<(&str, Option<u32>, &[Reason])> = report
+        .recommendations
+        .iter()
+        .map(|r| (r.vault.as_str(), r.rank, r.reasons.as_slice()))
+        .collect();
+    assert_eq!(
+        reasons,
+        [
+            ("ok", Some(1), &[][..]),
+            ("fees", None, &[Reason::FeeTooHigh][..]),
+            ("young", None, &[Reason::InsufficientHistory][..]),
+            ("euro", None, &[Reason::NotAllowed][..]),
+            // An empty vault has no share price to measure
+            ("tiny", None, &[Reason::InsufficientHistory][..]),
+        ]
+    );
+    assert_eq!(report.allocation, [Allocation { vault: "ok".into(), weight_bps: 10_000 }]);
+}
+
+#[test]
+fn test_allocate_caps_and_rounds() {
+    assert_eq!(allocate(&[3_000, 1_000], 10_000), [7_500, 2_500]);
+    // The capped vault's excess moves to the others
+    assert_eq!(allocate(&[3_000, 1_000, 1_000], 5_000), [5_000, 2_500, 2_500]);
+    // Rounding leftovers go to the best vault first
+    assert_eq!(allocate(&[1, 1, 1], 10_000), [3_334, 3_333, 3_333]);
+    // A cap two vaults cannot fill is raised to an even split
+    assert_eq!(allocate(&[5, 1], 2_000), [5_000, 5_000]);
+    assert_eq!(allocate(&[], 10_000), Vec::<u32>::new());
+
+    for scores in [&[7, 3, 2, 1][..], &[1_234, 999, 17, 5], &[50; 7]] {
+        let weights = allocate(scores, 3_000);
+        assert_eq!(weights.iter().sum::<u32>(), 10_000);
+        assert!(weights.iter().all(|w| *w <= 3_000), "{weights:?}");
+    }
+}
+
+#[test]
+fn test_allocation_skips_losing_vaults_and_respects_max_vaults() {
+    let falling: Vec<i128> = (0..=10).map(|day| 10_000_000 - 10_000 * day).collect();
+    let vaults = vec![
+        steady("a", 300_000),
+        steady("b", 200_000),
+        steady("c", 100_000),
+        series("falling", "USDC", &falling, 1, 0),
+    ];
+    let risk = RiskParams { risk_aversion_pct: 0, ..RiskParams::default() };
+    let constraints = Constraints { max_vaults: Some(2), ..Constraints::default() };
+    let report = recommend(&Input { vaults, risk, constraints });
+
+    // Weights follow the scores (10950 and 7300 bps)
+    assert_eq!(
+        report.allocation,
+        [
+            Allocation { vault: "a".into(), weight_bps: 6_000 },
+            Allocation { vault: "b".into(), weight_bps: 4_000 },
+        ]
+    );
+    let falling = report.recommendations.last().unwrap();
+    assert_eq!((falling.rank, falling.weight_bps), (Some(4), 0));
+    assert!(falling.score_bps.unwrap() < 0);
+
+    // Nothing earns: stay out
+    let slipping: Vec<i128> = (0..=10).map(|day| 10_000_000 - day).collect();
+    let vaults = vec![series("slipping", "USDC", &slipping, 1, 0)];
+    assert!(recommend(&input(vaults, RiskParams::default())).allocation.is_empty());
+}
+
+#[test]
+fn test_json_input_and_output() {
+    let input: Input = serde_json::from_value(json!({
+        "vaults": [{
+            "vault": "CVAULT",
+            "asset": "USDC",
+            "observations": [
+                { "timestamp": 0, "total_assets": "1000000000", "total_shares": 1000000000 },
+                {
+                    "timestamp": 604800,
+                    "total_assets": "1010000000",
+                    "total_shares": "1000000000",
+                    "fee_bps": 20
+                }
+            ]
+        }],
+        "risk": { "max_weight_bps": 8000 }
+    }))
+    .unwrap();
+    assert_eq!(input.risk.risk_aversion_pct, 50);
+    assert_eq!(input.constraints, Constraints::default());
+
+    let report = serde_json::to_value(recommend(&input)).unwrap();
+    assert_eq!(report["recommendations"][0]["metrics"]["tvl"], "1010000000");
+    assert_eq!(report["recommendations"][0]["metrics"]["apy_bps"], 5_214);
+    assert_eq!(report["allocation"], json!([{ "vault": "CVAULT", "weight_bps": 10_000 }]));
+
+    // The sample shipped for the CLI stays valid
+    let sample: Input = serde_json::from_str(include_str!("../sample-input.json")).unwrap();
+    assert!(!recommend(&sample).allocation.is_empty());
+}
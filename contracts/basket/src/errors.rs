@@ -0,0 +1,14 @@
+use soroban_sdk::contracterror;
+
+/// Typed errors surfaced by the basket contract
+#[contracterror]
+#[derive(Copy, Clone, Debug, Eq, PartialEq, PartialOrd, Ord)]
+#[repr(u32)]
+pub enum BasketError {
+    InvalidConfig = 1,
+    InvalidAmount = 2,
+    InsufficientBalance = 3,
+    InsufficientAllowance = 4,
+    InsufficientOutput = 5,
+    ArithmeticOverflow = 6,
+}
@@ -0,0 +1,26 @@
+use soroban_sdk::{contracttype, Address};
+
+// Zap Contract Interface - Documentation Only
+//
+// The actual contract calls are made using env.invoke_contract()
+// These types mirror the Zap's return values so they can be decoded
+
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct ZapResult {
+    pub amount_swapped: i128,
+    pub vault_shares: i128,
+    pub vault_address: Address,
+    pub price_impact_bps: u32,
+    pub protocol_fee: i128,
+}
+
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct ZapOutResult {
+    pub shares_burned: i128,
+    pub assets_withdrawn: i128,
+    pub amount_out: i128,
+    pub price_impact_bps: u32,
+    pub protocol_fee: i128,
+}
@@ -0,0 +1,416 @@
+#![no_std]
+
+mod errors;
+mod interfaces;
+mod storage;
+
+#[cfg(test)]
+mod test;
+
+use soroban_sdk::{
+    auth::{ContractContext, InvokerContractAuthEntry, SubContractInvocation},
+    contract, contractimpl, contracttype, panic_with_error,
+    token::{Client as TokenClient, TokenInterface},
+    vec, Address, Env, IntoVal, String, Symbol, Vec,
+};
+use soroban_token_sdk::{metadata::TokenMetadata, TokenUtils};
+
+pub use errors::BasketError;
+use interfaces::{ZapOutResult, ZapResult};
+use storage::AllowanceValue;
+use zap_math::{MathError, Rounding, BPS_DENOMINATOR};
+
+const DECIMALS: u32 = 7;
+/// Tokens of the first deposit locked in the basket's own balance, so the supply never
+/// returns to a few units an attacker could inflate by donating vault shares
+pub const MINIMUM_SUPPLY: i128 = 1_000;
+
+/// An underlying vault and its target share of every deposit
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct BasketVault {
+    pub vault: Address,
+    pub asset: Address,       // Asset the vault accepts and pays out
+    pub weight_bps: u32,      // Target weights must sum to 10_000
+}
+
+/// `a * b / denominator` rounded down, through the Zap's 256-bit math
+fn mul_div(env: &Env, a: i128, b: i128, denominator: i128) -> i128 {
+    zap_math::mul_div(a, b, denominator, Rounding::Down).unwrap_or_else(|err| match err {
+        MathError::InvalidAmount => panic_with_error!(env, BasketError::InvalidAmount),
+        MathError::Overflow => panic_with_error!(env, BasketError::ArithmeticOverflow),
+    })
+}
+
+fn add(env: &Env, a: i128, b: i128) -> i128 {
+    a.checked_add(b)
+        .unwrap_or_else(|| panic_with_error!(env, BasketError::ArithmeticOverflow))
+}
+
+fn sub(env: &Env, a: i128, b: i128) -> i128 {
+    a.checked_sub(b)
+        .unwrap_or_else(|| panic_with_error!(env, BasketError::ArithmeticOverflow))
+}
+
+/// SEP-41 token for a pro-rata claim on shares of several DeFindex vaults
+///
+/// Deposits and redemptions go through the Zap contract, so the basket gets the
+/// Zap's routing, fees, price guards and impact caps without reimplementing them.
+#[contract]
+pub struct Basket;
+
+#[contractimpl]
+impl Basket {
+    pub fn __constructor(
+        env: Env,
+        zap: Address,
+        name: String,
+        symbol: String,
+        vaults: Vec<BasketVault>,
+    ) {
+        let total_weight = vaults
+            .iter()
+            .try_fold(0u32, |total, leg| total.checked_add(leg.weight_bps))
+            .unwrap_or_else(|| panic_with_error!(&env, BasketError::InvalidConfig));
+        if vaults.is_empty() || total_weight as i128 != BPS_DENOMINATOR {
+            panic_with_error!(&env, BasketError::InvalidConfig);
+        }
+
+        storage::set_zap(&env, &zap);
+        storage::set_vaults(&env, &vaults);
+        TokenUtils::new(&env).metadata().set_metadata(&TokenMetadata {
+            decimal: DECIMALS,
+            name,
+            symbol,
+        });
+    }
+
+    /// Zap `amount_in` of `from_token` into every vault by target weight and mint basket tokens
+    ///
+    /// The first deposit mints `amount_in` tokens, of which `MINIMUM_SUPPLY` stay
+    /// locked with the basket. Later deposits mint the smallest proportional
+    /// increase across the vaults' holdings, so existing holders are never diluted.
+    ///
+    /// # Arguments
+    /// * `user` - Address paying `from_token` and receiving the basket tokens
+    /// * `from_token` - Token to zap from
+    /// * `amount_in` - Amount of `from_token` to deposit
+    /// * `min_legs_out` - Minimum swap output of each leg in its vault asset, in `get_vaults` order
+    /// * `min_tokens_out` - Minimum basket tokens to mint
+    pub fn deposit(
+        env: Env,
+        user: Address,
+        from_token: Address,
+        amount_in: i128,
+        min_legs_out: Vec<i128>,
+        min_tokens_out: i128,
+    ) -> i128 {
+        user.require_auth();
+        storage::extend_instance(&env);
+
+        let vaults = storage::get_vaults(&env);
+        if amount_in <= 0 || min_legs_out.len() != vaults.len() {
+            panic_with_error!(&env, BasketError::InvalidAmount);
+        }
+
+        let basket = env.current_contract_address();
+        TokenClient::new(&env, &from_token).transfer(&user, &basket, &amount_in);
+
+        let supply = storage::get_total_supply(&env);
+        let mut remaining = amount_in;
+        let mut minted: Option<i128> = None;
+        for (i, leg) in vaults.iter().enumerate() {
+            // The last leg takes the rounding remainder
+            let amount = if i as u32 == vaults.len() - 1 {
+                remaining
+            } else {
+                mul_div(&env, amount_in, leg.weight_bps as i128, BPS_DENOMINATOR)
+            };
+            remaining = sub(&env, remaining, amount);
+            if amount == 0 {
+                continue;
+            }
+
+            let held = TokenClient::new(&env, &leg.vault).balance(&basket);
+            let min_amount_out = min_legs_out.get_unchecked(i as u32);
+            let shares = Self::zap_in(&env, &from_token, amount, min_amount_out, &leg);
+            if held > 0 {
+                let increase = mul_div(&env, supply, shares, held);
+                minted = Some(minted.map_or(increase, |m| m.min(increase)));
+            }
+        }
+
+        let (minted, locked) = if supply == 0 {
+            (sub(&env, amount_in, MINIMUM_SUPPLY), MINIMUM_SUPPLY)
+        } else {
+            (minted.unwrap_or(0), 0)
+        };
+        if minted <= 0 || minted < min_tokens_out {
+            panic_with_error!(&env, BasketError::InsufficientOutput);
+        }
+
+        storage::set_total_supply(&env, add(&env, add(&env, supply, minted), locked));
+        if locked > 0 {
+            storage::set_balance(&env, &basket, locked);
+            TokenUtils::new(&env).events().mint(basket.clone(), basket.clone(), locked);
+        }
+        storage::set_balance(&env, &user, add(&env, storage::get_balance(&env, &user), minted));
+        TokenUtils::new(&env).events().mint(basket, user, minted);
+        minted
+    }
+
+    /// Burn basket tokens, zap out of each vault pro rata and pay `user` in `to_token`
+    ///
+    /// # Arguments
+    /// * `user` - Holder of the basket tokens, receiving the output
+    /// * `amount` - Basket tokens to burn
+    /// * `to_token` - Token to receive
+    /// * `min_legs_out` - Minimum `to_token` from each leg, in `get_vaults` order
+    /// * `min_amount_out` - Minimum total amount of `to_token`
+    pub fn redeem(
+        env: Env,
+        user: Address,
+        amount: i128,
+        to_token: Address,
+        min_legs_out: Vec<i128>,
+        min_amount_out: i128,
+    ) -> i128 {
+        user.require_auth();
+        storage::extend_instance(&env);
+
+        let vaults = storage::get_vaults(&env);
+        if amount <= 0 || min_legs_out.len() != vaults.len() {
+            panic_with_error!(&env, BasketError::InvalidAmount);
+        }
+
+        let supply = storage::get_total_supply(&env);
+        Self::spend_balance(&env, &user, amount);
+        storage::set_total_supply(&env, sub(&env, supply, amount));
+
+        let basket = env.current_contract_address();
+        let mut amount_out = 0;
+        for (leg, min_leg_out) in vaults.iter().zip(min_legs_out.iter()) {
+            let held = TokenClient::new(&env, &leg.vault).balance(&basket);
+            let shares = mul_div(&env, held, amount, supply);
+            if shares > 0 {
+                let leg_out = Self::zap_out(&env, shares, min_leg_out, &leg, &to_token);
+                amount_out = add(&env, amount_out, leg_out);
+            }
+        }
+
+        if amount_out < min_amount_out {
+            panic_with_error!(&env, BasketError::InsufficientOutput);
+        }
+
+        TokenClient::new(&env, &to_token).transfer(&basket, &user, &amount_out);
+        TokenUtils::new(&env).events().burn(user, amount);
+        amount_out
+    }
+
+    pub fn get_vaults(env: Env) -> Vec<BasketVault> {
+        storage::get_vaults(&env)
+    }
+
+    /// Vault shares held by the basket, in `get_vaults` order
+    pub fn get_holdings(env: Env) -> Vec<i128> {
+        let basket = env.current_contract_address();
+        let mut holdings = Vec::new(&env);
+        for leg in storage::get_vaults(&env).iter() {
+            holdings.push_back(TokenClient::new(&env, &leg.vault).balance(&basket));
+        }
+        holdings
+    }
+
+    pub fn total_supply(env: Env) -> i128 {
+        storage::get_total_supply(&env)
+    }
+}
+
+#[contractimpl]
+impl TokenInterface for Basket {
+    fn allowance(env: Env, from: Address, spender: Address) -> i128 {
+        storage::get_allowance(&env, &from, &spender).amount
+    }
+
+    fn approve(env: Env, from: Address, spender: Address, amount: i128, expiration_ledger: u32) {
+        from.require_auth();
+        storage::extend_instance(&env);
+
+        if amount < 0 || (amount > 0 && expiration_ledger < env.ledger().sequence()) {
+            panic_with_error!(&env, BasketError::InvalidAmount);
+        }
+
+        let allowance = AllowanceValue {
+            amount,
+            expiration_ledger,
+        };
+        storage::set_allowance(&env, &from, &spender, &allowance);
+        TokenUtils::new(&env)
+            .events()
+            .approve(from, spender, amount, expiration_ledger);
+    }
+
+    fn balance(env: Env, id: Address) -> i128 {
+        storage::get_balance(&env, &id)
+    }
+
+    fn transfer(env: Env, from: Address, to: Address, amount: i128) {
+        from.require_auth();
+        storage::extend_instance(&env);
+
+        Self::move_balance(&env, &from, &to, amount);
+    }
+
+    fn transfer_from(env: Env, spender: Address, from: Address, to: Address, amount: i128) {
+        spender.require_auth();
+        storage::extend_instance(&env);
+
+        Self::spend_allowance(&env, &from, &spender, amount);
+        Self::move_balance(&env, &from, &to, amount);
+    }
+
+    fn burn(env: Env, from: Address, amount: i128) {
+        from.require_auth();
+        storage::extend_instance(&env);
+
+        Self::burn_balance(&env, &from, amount);
+    }
+
+    fn burn_from(env: Env, spender: Address, from: Address, amount: i128) {
+        spender.require_auth();
+        storage::extend_instance(&env);
+
+        Self::spend_allowance(&env, &from, &spender, amount);
+        Self::burn_balance(&env, &from, amount);
+    }
+
+    fn decimals(_env: Env) -> u32 {
+        DECIMALS
+    }
+
+    fn name(env: Env) -> String {
+        TokenUtils::new(&env).metadata().get_metadata().name
+    }
+
+    fn symbol(env: Env) -> String {
+        TokenUtils::new(&env).metadata().get_metadata().symbol
+    }
+}
+
+impl Basket {
+    /// Zap `amount` of `from_token` held by the basket into `leg`, returning the shares
+    fn zap_in(
+        env: &Env,
+        from_token: &Address,
+        amount: i128,
+        min_amount_out: i128,
+        leg: &BasketVault,
+    ) -> i128 {
+        let zap = storage::get_zap(env);
+        let basket = env.current_contract_address();
+
+        // The Zap pulls the input from the basket below its own invocation
+        Self::authorize_transfer(env, from_token, &zap, amount);
+        let result: ZapResult = env.invoke_contract(
+            &zap,
+            &Symbol::new(env, "zap_and_deposit"),
+            vec![
+                env,
+                basket.into_val(env),
+                from_token.into_val(env),
+                amount.into_val(env),
+                leg.asset.into_val(env),
+                leg.vault.into_val(env),
+                min_amount_out.into_val(env),
+                Vec::<Address>::new(env).into_val(env),
+                Vec::<u32>::new(env).into_val(env),
+                None::<u32>.into_val(env),
+                Some(basket.clone()).into_val(env),
+            ],
+        );
+        result.vault_shares
+    }
+
+    /// Zap `shares` of `leg` out into `to_token`, which the Zap sends to the basket
+    fn zap_out(
+        env: &Env,
+        shares: i128,
+        min_amount_out: i128,
+        leg: &BasketVault,
+        to_token: &Address,
+    ) -> i128 {
+        let zap = storage::get_zap(env);
+        let basket = env.current_contract_address();
+
+        Self::authorize_transfer(env, &leg.vault, &zap, shares);
+        let result: ZapOutResult = env.invoke_contract(
+            &zap,
+            &Symbol::new(env, "zap_out"),
+            vec![
+                env,
+                basket.into_val(env),
+                leg.vault.into_val(env),
+                shares.into_val(env),
+                leg.asset.into_val(env),
+                to_token.into_val(env),
+                min_amount_out.into_val(env),
+                Vec::<Address>::new(env).into_val(env),
+                Vec::<u32>::new(env).into_val(env),
+                None::<u32>.into_val(env),
+            ],
+        );
+        result.amount_out
+    }
+
+    /// Authorize the next call's transfer of `amount` of `token` from the basket to `to`
+    fn authorize_transfer(env: &Env, token: &Address, to: &Address, amount: i128) {
+        env.authorize_as_current_contract(vec![
+            env,
+            InvokerContractAuthEntry::Contract(SubContractInvocation {
+                context: ContractContext {
+                    contract: token.clone(),
+                    fn_name: Symbol::new(env, "transfer"),
+                    args: (env.current_contract_address(), to.clone(), amount).into_val(env),
+                },
+                sub_invocations: Vec::new(env),
+            }),
+        ]);
+    }
+
+    fn spend_balance(env: &Env, id: &Address, amount: i128) {
+        if amount < 0 {
+            panic_with_error!(env, BasketError::InvalidAmount);
+        }
+        let balance = storage::get_balance(env, id);
+        if balance < amount {
+            panic_with_error!(env, BasketError::InsufficientBalance);
+        }
+        storage::set_balance(env, id, sub(env, balance, amount));
+    }
+
+    fn spend_allowance(env: &Env, from: &Address, spender: &Address, amount: i128) {
+        let mut allowance = storage::get_allowance(env, from, spender);
+        if allowance.amount < amount {
+            panic_with_error!(env, BasketError::InsufficientAllowance);
+        }
+        if amount > 0 {
+            allowance.amount = sub(env, allowance.amount, amount);
+            storage::set_allowance(env, from, spender, &allowance);
+        }
+    }
+
+    fn move_balance(env: &Env, from: &Address, to: &Address, amount: i128) {
+        Self::spend_balance(env, from, amount);
+        storage::set_balance(env, to, add(env, storage::get_balance(env, to), amount));
+        TokenUtils::new(env)
+            .events()
+            .transfer(from.clone(), to.clone(), amount);
+    }
+
+    /// Burn tokens without redeeming, leaving their share of the vaults to other holders
+    fn burn_balance(env: &Env, from: &Address, amount: i128) {
+        Self::spend_balance(env, from, amount);
+        storage::set_total_supply(env, sub(env, storage::get_total_supply(env), amount));
+        TokenUtils::new(env).events().burn(from.clone(), amount);
+    }
+}
@@ -0,0 +1,105 @@
+use soroban_sdk::{contracttype, Address, Env, Vec};
+
+use crate::BasketVault;
+
+// Ledgers are ~5s, so one day is ~17280 ledgers
+pub const DAY_IN_LEDGERS: u32 = 17280;
+pub const INSTANCE_BUMP_AMOUNT: u32 = 7 * DAY_IN_LEDGERS;
+pub const INSTANCE_LIFETIME_THRESHOLD: u32 = INSTANCE_BUMP_AMOUNT - DAY_IN_LEDGERS;
+pub const PERSISTENT_BUMP_AMOUNT: u32 = 30 * DAY_IN_LEDGERS;
+pub const PERSISTENT_LIFETIME_THRESHOLD: u32 = PERSISTENT_BUMP_AMOUNT - DAY_IN_LEDGERS;
+
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum DataKey {
+    Zap,
+    Vaults,
+    TotalSupply,
+    Balance(Address),            // Basket token balance, persistent
+    Allowance(Address, Address), // (from, spender), temporary
+}
+
+/// A SEP-41 allowance and the ledger it lapses after
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct AllowanceValue {
+    pub amount: i128,
+    pub expiration_ledger: u32,
+}
+
+pub fn extend_instance(env: &Env) {
+    env.storage()
+        .instance()
+        .extend_ttl(INSTANCE_LIFETIME_THRESHOLD, INSTANCE_BUMP_AMOUNT);
+}
+
+pub fn get_zap(env: &Env) -> Address {
+    env.storage().instance().get(&DataKey::Zap).unwrap()
+}
+
+pub fn set_zap(env: &Env, zap: &Address) {
+    env.storage().instance().set(&DataKey::Zap, zap);
+}
+
+pub fn get_vaults(env: &Env) -> Vec<BasketVault> {
+    env.storage().instance().get(&DataKey::Vaults).unwrap()
+}
+
+pub fn set_vaults(env: &Env, vaults: &Vec<BasketVault>) {
+    env.storage().instance().set(&DataKey::Vaults, vaults);
+}
+
+pub fn get_total_supply(env: &Env) -> i128 {
+    env.storage().instance().get(&DataKey::TotalSupply).unwrap_or(0)
+}
+
+pub fn set_total_supply(env: &Env, supply: i128) {
+    env.storage().instance().set(&DataKey::TotalSupply, &supply);
+}
+
+pub fn get_balance(env: &Env, id: &Address) -> i128 {
+    let key = DataKey::Balance(id.clone());
+    match env.storage().persistent().get(&key) {
+        Some(balance) => {
+            env.storage()
+                .persistent()
+                .extend_ttl(&key, PERSISTENT_LIFETIME_THRESHOLD, PERSISTENT_BUMP_AMOUNT);
+            balance
+        }
+        None => 0,
+    }
+}
+
+pub fn set_balance(env: &Env, id: &Address, balance: i128) {
+    let key = DataKey::Balance(id.clone());
+    if balance == 0 {
+        env.storage().persistent().remove(&key);
+    } else {
+        env.storage().persistent().set(&key, &balance);
+        env.storage()
+            .persistent()
+            .extend_ttl(&key, PERSISTENT_LIFETIME_THRESHOLD, PERSISTENT_BUMP_AMOUNT);
+    }
+}
+
+/// The allowance of `spender` over `from`'s tokens, zero once expired
+pub fn get_allowance(env: &Env, from: &Address, spender: &Address) -> AllowanceValue {
+    env.storage()
+        .temporary()
+        .get(&DataKey::Allowance(from.clone(), spender.clone()))
+        .filter(|allowance: &AllowanceValue| allowance.expiration_ledger >= env.ledger().sequence())
+        .unwrap_or(AllowanceValue {
+            amount: 0,
+            expiration_ledger: 0,
+        })
+}
+
+pub fn set_allowance(env: &Env, from: &Address, spender: &Address, allowance: &AllowanceValue) {
+    let key = DataKey::Allowance(from.clone(), spender.clone());
+    env.storage().temporary().set(&key, allowance);
+    let current = env.ledger().sequence();
+    if allowance.amount > 0 && allowance.expiration_ledger >= current {
+        let live_for = allowance.expiration_ledger - current;
+        env.storage().temporary().extend_ttl(&key, live_for, live_for);
+    }
+}
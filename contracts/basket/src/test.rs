@@ -0,0 +1,236 @@
+#![cfg(test)]
+
+use super::*;
+use soroban_sdk::{testutils::Address as _, token::StellarAssetClient, Address, Env};
+
+mod mock_vault {
+    use soroban_sdk::{contract, contractimpl, symbol_short, token, Address, Env};
+
+    fn balance_of(env: &Env, id: &Address) -> i128 {
+        env.storage().instance().get(id).unwrap_or(0)
+    }
+
+    fn set_balance(env: &Env, id: &Address, amount: i128) {
+        env.storage().instance().set(id, &amount);
+    }
+
+    // Vault whose shares are a transferable token, paying out 2 assets per share
+    #[contract]
+    pub struct ShareVault;
+
+    #[contractimpl]
+    impl ShareVault {
+        pub fn __constructor(env: Env, asset: Address) {
+            env.storage().instance().set(&symbol_short!("asset"), &asset);
+        }
+
+        pub fn deposit(env: Env, _asset: Address, amount: i128, receiver: Address) -> i128 {
+            let shares = amount / 2;
+            set_balance(&env, &receiver, balance_of(&env, &receiver) + shares);
+            shares
+        }
+
+        pub fn withdraw(env: Env, shares: i128, receiver: Address) -> i128 {
+            let balance = balance_of(&env, &receiver);
+            assert!(balance >= shares, "insufficient shares");
+            set_balance(&env, &receiver, balance - shares);
+
+            let asset: Address = env.storage().instance().get(&symbol_short!("asset")).unwrap();
+            let assets = shares * 2;
+            token::Client::new(&env, &asset).transfer(&env.current_contract_address(), &receiver, &assets);
+            assets
+        }
+
+        pub fn balance(env: Env, id: Address) -> i128 {
+            balance_of(&env, &id)
+        }
+
+        pub fn transfer(env: Env, from: Address, to: Address, amount: i128) {
+            from.require_auth();
+            let balance = balance_of(&env, &from);
+            assert!(balance >= amount, "insufficient shares");
+            set_balance(&env, &from, balance - amount);
+            set_balance(&env, &to, balance_of(&env, &to) + amount);
+        }
+    }
+}
+
+/// A 60/40 basket over two vaults of the same asset, zapping through a real Zap contract
+fn setup(env: &Env) -> (BasketClient<'_>, Address, Address, Address) {
+    let zap = env.register(zap::Zap, ());
+    let zap_client = zap::ZapClient::new(env, &zap);
+    zap_client.initialize(&Address::generate(env));
+    zap_client.set_addresses(&zap::ContractAddresses {
+        soroswap_aggregator: Address::generate(env),
+        soroswap_router: Address::generate(env),
+        defindex_factory: Address::generate(env),
+        usdc: Address::generate(env),
+        xlm: Address::generate(env),
+        aqua: Address::generate(env),
+    });
+
+    let asset = env
+        .register_stellar_asset_contract_v2(Address::generate(env))
+        .address();
+    let first = env.register(mock_vault::ShareVault, (asset.clone(),));
+    let second = env.register(mock_vault::ShareVault, (asset.clone(),));
+    // The mock vaults hold deposits elsewhere; fund their withdrawals
+    StellarAssetClient::new(env, &asset).mint(&first, &10_000);
+    StellarAssetClient::new(env, &asset).mint(&second, &10_000);
+
+    let vaults = vec![
+        env,
+        BasketVault {
+            vault: first.clone(),
+            asset: asset.clone(),
+            weight_bps: 6_000,
+        },
+        BasketVault {
+            vault: second.clone(),
+            asset: asset.clone(),
+            weight_bps: 4_000,
+        },
+    ];
+    let basket = env.register(
+        Basket,
+        (
+            zap,
+            String::from_str(env, "YieldZap Blue Chip"),
+            String::from_str(env, "YZBC"),
+            vaults,
+        ),
+    );
+    (BasketClient::new(env, &basket), asset, first, second)
+}
+
+#[test]
+#[should_panic(expected = "Error(Contract, #1)")]
+fn test_weights_that_overflow_are_rejected() {
+    let env = Env::default();
+    let asset = Address::generate(&env);
+    let leg = |weight_bps| BasketVault {
+        vault: Address::generate(&env),
+        asset: asset.clone(),
+        weight_bps,
+    };
+
+    // Sums to 10_000 only by wrapping around
+    let vaults = vec![&env, leg(u32::MAX), leg(10_001)];
+    env.register(
+        Basket,
+        (
+            Address::generate(&env),
+            String::from_str(&env, "Overflow"),
+            String::from_str(&env, "OVF"),
+            vaults,
+        ),
+    );
+}
+
+#[test]
+fn test_deposit_allocates_by_weight_and_redeems_pro_rata() {
+    let env = Env::default();
+    env.mock_all_auths();
+
+    let (client, asset, _, _) = setup(&env);
+    let asset_client = TokenClient::new(&env, &asset);
+    let alice = Address::generate(&env);
+    let bob = Address::generate(&env);
+    StellarAssetClient::new(&env, &asset).mint(&alice, &10_000);
+    StellarAssetClient::new(&env, &asset).mint(&bob, &5_000);
+    let no_minimums = vec![&env, 0, 0];
+
+    // The first deposit locks the minimum supply with the basket
+    assert_eq!(
+        client.try_deposit(&alice, &asset, &MINIMUM_SUPPLY, &no_minimums, &0),
+        Err(Ok(BasketError::InsufficientOutput.into()))
+    );
+    assert_eq!(
+        client.try_deposit(&alice, &asset, &10_000, &vec![&env, 0], &0),
+        Err(Ok(BasketError::InvalidAmount.into()))
+    );
+    assert_eq!(client.deposit(&alice, &asset, &10_000, &no_minimums, &9_000), 9_000);
+    assert_eq!(client.balance(&client.address), MINIMUM_SUPPLY);
+    assert_eq!(client.total_supply(), 10_000);
+    assert_eq!(client.get_holdings(), vec![&env, 3_000, 2_000]);
+
+    // Each leg's minimum is enforced by the Zap
+    assert!(client
+        .try_deposit(&bob, &asset, &5_000, &vec![&env, 3_001, 0], &0)
+        .is_err());
+
+    // Later deposits mint in proportion to the holdings they add
+    assert_eq!(
+        client.try_deposit(&bob, &asset, &5_000, &no_minimums, &5_001),
+        Err(Ok(BasketError::InsufficientOutput.into()))
+    );
+    assert_eq!(client.deposit(&bob, &asset, &5_000, &no_minimums, &5_000), 5_000);
+    assert_eq!(client.total_supply(), 15_000);
+    assert_eq!(client.get_holdings(), vec![&env, 4_500, 3_000]);
+
+    client.transfer(&bob, &alice, &1_000);
+    assert_eq!(client.balance(&alice), 10_000);
+
+    assert!(client
+        .try_redeem(&alice, &6_000, &asset, &vec![&env, 0, 2_401], &0)
+        .is_err());
+    let amount_out = client.redeem(&alice, &6_000, &asset, &vec![&env, 3_600, 2_400], &6_000);
+    assert_eq!(amount_out, 6_000);
+    assert_eq!(asset_client.balance(&alice), 6_000);
+    assert_eq!(client.get_holdings(), vec![&env, 2_700, 1_800]);
+    assert_eq!(client.total_supply(), 9_000);
+
+    assert_eq!(
+        client.try_redeem(&bob, &4_001, &asset, &no_minimums, &0),
+        Err(Ok(BasketError::InsufficientBalance.into()))
+    );
+}
+
+#[test]
+fn test_amounts_whose_products_exceed_i128() {
+    let env = Env::default();
+    env.mock_all_auths();
+
+    let (client, asset, first, second) = setup(&env);
+    let alice = Address::generate(&env);
+    let bob = Address::generate(&env);
+    let amount = 10i128.pow(20);
+    StellarAssetClient::new(&env, &asset).mint(&alice, &amount);
+    StellarAssetClient::new(&env, &asset).mint(&bob, &amount);
+    StellarAssetClient::new(&env, &asset).mint(&first, &amount);
+    StellarAssetClient::new(&env, &asset).mint(&second, &amount);
+    let no_minimums = vec![&env, 0, 0];
+
+    // supply * shares is around 6 * 10^39, past i128::MAX
+    client.deposit(&alice, &asset, &amount, &no_minimums, &0);
+    assert_eq!(client.deposit(&bob, &asset, &amount, &no_minimums, &0), amount);
+    assert_eq!(client.redeem(&bob, &amount, &asset, &no_minimums, &0), amount);
+}
+
+#[test]
+fn test_token_allowances() {
+    let env = Env::default();
+    env.mock_all_auths();
+
+    let (client, asset, _, _) = setup(&env);
+    let alice = Address::generate(&env);
+    let spender = Address::generate(&env);
+    StellarAssetClient::new(&env, &asset).mint(&alice, &2_000);
+    client.deposit(&alice, &asset, &2_000, &vec![&env, 0, 0], &0);
+    assert_eq!(client.balance(&alice), 1_000);
+
+    assert_eq!(client.decimals(), 7);
+    assert_eq!(client.symbol(), String::from_str(&env, "YZBC"));
+
+    client.approve(&alice, &spender, &300, &100);
+    client.transfer_from(&spender, &alice, &spender, &200);
+    assert_eq!(client.allowance(&alice, &spender), 100);
+    assert_eq!(
+        client.try_burn_from(&spender, &alice, &101),
+        Err(Ok(BasketError::InsufficientAllowance.into()))
+    );
+
+    client.burn_from(&spender, &alice, &100);
+    assert_eq!(client.balance(&alice), 700);
+    assert_eq!(client.total_supply(), 1_900);
+}
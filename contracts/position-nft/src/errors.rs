@@ -0,0 +1,14 @@
+use soroban_sdk::contracterror;
+
+/// Typed errors surfaced by the position NFT contract
+#[contracterror]
+#[derive(Copy, Clone, Debug, Eq, PartialEq, PartialOrd, Ord)]
+#[repr(u32)]
+pub enum NftError {
+    TokenNotFound = 1,
+    NotOwner = 2,
+    NotApproved = 3,
+    InvalidAmount = 4,
+    InvalidApproval = 5,
+    UriTooLong = 6,
+}
@@ -0,0 +1,257 @@
+#![no_std]
+
+mod errors;
+mod storage;
+
+#[cfg(test)]
+mod test;
+
+use soroban_sdk::{
+    contract, contractimpl, contracttype, panic_with_error, symbol_short, token::Client as TokenClient,
+    Address, Env, String,
+};
+
+pub use errors::NftError;
+use storage::{Approval, DataKey};
+
+/// Longest token URI `token_uri` will build
+const MAX_URI_LEN: usize = 256;
+
+/// Fixed-point scale of `Receipt::entry_price`
+pub const PRICE_SCALE: i128 = 10_000_000;
+
+/// Vault position held in custody for a token
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Receipt {
+    pub vault: Address,
+    pub asset: Address,       // Asset the vault pays out on withdrawal
+    pub shares: i128,         // Vault shares held by this contract for the token
+    pub entry_price: i128,    // Vault asset per share at mint, scaled by PRICE_SCALE
+    pub minted_at: u64,
+}
+
+/// SEP-50 style NFT whose tokens are transferable claims on vault shares
+///
+/// Only the minter (the Zap contract) mints and burns. It deposits shares with
+/// this contract as receiver before minting, and burning hands them back to it
+/// for the zap-out.
+#[contract]
+pub struct PositionNft;
+
+#[contractimpl]
+impl PositionNft {
+    pub fn __constructor(env: Env, minter: Address, base_uri: String) {
+        storage::set_minter(&env, &minter);
+        storage::set_base_uri(&env, &base_uri);
+    }
+
+    pub fn name(env: Env) -> String {
+        String::from_str(&env, "YieldZap Position")
+    }
+
+    pub fn symbol(env: Env) -> String {
+        String::from_str(&env, "YZPOS")
+    }
+
+    /// `base_uri` followed by the decimal token id
+    pub fn token_uri(env: Env, token_id: u32) -> String {
+        storage::get_owner(&env, token_id);
+
+        let base_uri = storage::get_base_uri(&env);
+        let base_len = base_uri.len() as usize;
+        let mut digits = [0u8; 10];
+        let mut digits_len = 0;
+        let mut id = token_id;
+        loop {
+            digits[digits_len] = b'0' + (id % 10) as u8;
+            digits_len += 1;
+            id /= 10;
+            if id == 0 {
+                break;
+            }
+        }
+        if base_len + digits_len > MAX_URI_LEN {
+            panic_with_error!(&env, NftError::UriTooLong);
+        }
+
+        let mut buf = [0u8; MAX_URI_LEN];
+        base_uri.copy_into_slice(&mut buf[..base_len]);
+        for i in 0..digits_len {
+            buf[base_len + i] = digits[digits_len - 1 - i];
+        }
+        String::from_bytes(&env, &buf[..base_len + digits_len])
+    }
+
+    pub fn minter(env: Env) -> Address {
+        storage::get_minter(&env)
+    }
+
+    pub fn balance(env: Env, owner: Address) -> u32 {
+        storage::get_balance(&env, &owner)
+    }
+
+    pub fn owner_of(env: Env, token_id: u32) -> Address {
+        storage::get_owner(&env, token_id)
+    }
+
+    pub fn get_receipt(env: Env, token_id: u32) -> Receipt {
+        storage::get_receipt(&env, token_id)
+    }
+
+    pub fn transfer(env: Env, from: Address, to: Address, token_id: u32) {
+        from.require_auth();
+        storage::extend_instance(&env);
+
+        Self::move_token(&env, &from, &to, token_id);
+    }
+
+    pub fn transfer_from(env: Env, spender: Address, from: Address, to: Address, token_id: u32) {
+        spender.require_auth();
+        storage::extend_instance(&env);
+
+        if !Self::is_authorized(&env, &spender, &from, token_id) {
+            panic_with_error!(&env, NftError::NotApproved);
+        }
+        Self::move_token(&env, &from, &to, token_id);
+    }
+
+    /// Approve `approved` to transfer `token_id` until `live_until_ledger`
+    ///
+    /// The owner or one of its operators may approve; a past ledger revokes.
+    pub fn approve(
+        env: Env,
+        approver: Address,
+        approved: Address,
+        token_id: u32,
+        live_until_ledger: u32,
+    ) {
+        approver.require_auth();
+        storage::extend_instance(&env);
+
+        let owner = storage::get_owner(&env, token_id);
+        if approver != owner && !Self::is_approved_for_all(env.clone(), owner.clone(), approver) {
+            panic_with_error!(&env, NftError::NotApproved);
+        }
+        if approved == owner {
+            panic_with_error!(&env, NftError::InvalidApproval);
+        }
+
+        let approval = Approval {
+            approved: approved.clone(),
+            live_until_ledger,
+        };
+        storage::set_approval(&env, &DataKey::Approval(token_id), &approval);
+        env.events().publish(
+            (symbol_short!("approve"), owner, token_id),
+            (approved, live_until_ledger),
+        );
+    }
+
+    /// Approve `operator` to transfer all of `owner`'s tokens until `live_until_ledger`
+    pub fn approve_for_all(env: Env, owner: Address, operator: Address, live_until_ledger: u32) {
+        owner.require_auth();
+        storage::extend_instance(&env);
+
+        if operator == owner {
+            panic_with_error!(&env, NftError::InvalidApproval);
+        }
+
+        let approval = Approval {
+            approved: operator.clone(),
+            live_until_ledger,
+        };
+        storage::set_approval(&env, &DataKey::ApprovalForAll(owner.clone(), operator.clone()), &approval);
+        env.events().publish(
+            (symbol_short!("approve"), owner),
+            (operator, live_until_ledger),
+        );
+    }
+
+    pub fn get_approved(env: Env, token_id: u32) -> Option<Address> {
+        storage::get_approval(&env, &DataKey::Approval(token_id))
+    }
+
+    pub fn is_approved_for_all(env: Env, owner: Address, operator: Address) -> bool {
+        storage::get_approval(&env, &DataKey::ApprovalForAll(owner, operator.clone()))
+            .is_some()
+    }
+
+    /// Mint a token for shares the minter already deposited with this contract
+    pub fn mint(
+        env: Env,
+        to: Address,
+        vault: Address,
+        asset: Address,
+        shares: i128,
+        entry_price: i128,
+    ) -> u32 {
+        storage::get_minter(&env).require_auth();
+        storage::extend_instance(&env);
+
+        if shares <= 0 {
+            panic_with_error!(&env, NftError::InvalidAmount);
+        }
+
+        let token_id = storage::next_token_id(&env);
+        let receipt = Receipt {
+            vault,
+            asset,
+            shares,
+            entry_price,
+            minted_at: env.ledger().timestamp(),
+        };
+        storage::set_receipt(&env, token_id, &receipt);
+        storage::set_owner(&env, token_id, &to);
+        storage::set_balance(&env, &to, storage::get_balance(&env, &to) + 1);
+
+        env.events().publish((symbol_short!("mint"), to), token_id);
+        token_id
+    }
+
+    /// Burn `owner`'s token and hand its shares to the minter
+    ///
+    /// The minter is responsible for checking `owner`'s authorization.
+    pub fn burn(env: Env, owner: Address, token_id: u32) -> Receipt {
+        let minter = storage::get_minter(&env);
+        minter.require_auth();
+        storage::extend_instance(&env);
+
+        if storage::get_owner(&env, token_id) != owner {
+            panic_with_error!(&env, NftError::NotOwner);
+        }
+
+        let receipt = storage::get_receipt(&env, token_id);
+        storage::remove_token(&env, token_id);
+        storage::set_balance(&env, &owner, storage::get_balance(&env, &owner) - 1);
+
+        TokenClient::new(&env, &receipt.vault).transfer(
+            &env.current_contract_address(),
+            &minter,
+            &receipt.shares,
+        );
+
+        env.events().publish((symbol_short!("burn"), owner), token_id);
+        receipt
+    }
+}
+
+impl PositionNft {
+    fn is_authorized(env: &Env, spender: &Address, owner: &Address, token_id: u32) -> bool {
+        spender == owner
+            || storage::get_approval(env, &DataKey::Approval(token_id)).as_ref() == Some(spender)
+            || Self::is_approved_for_all(env.clone(), owner.clone(), spender.clone())
+    }
+
+    fn move_token(env: &Env, from: &Address, to: &Address, token_id: u32) {
+        if storage::get_owner(env, token_id) != *from {
+            panic_with_error!(env, NftError::NotOwner);
+        }
+
+        storage::set_owner(env, token_id, to);
+        storage::set_balance(env, from, storage::get_balance(env, from) - 1);
+        storage::set_balance(env, to, storage::get_balance(env, to) + 1);
+
+        env.events().publish((symbol_short!("transfer"), from.clone(), to.clone()), token_id);
+    }
+}
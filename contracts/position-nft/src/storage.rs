@@ -0,0 +1,138 @@
+use soroban_sdk::{contracttype, panic_with_error, Address, Env, String};
+
+use crate::errors::NftError;
+use crate::Receipt;
+
+// Ledgers are ~5s, so one day is ~17280 ledgers
+pub const DAY_IN_LEDGERS: u32 = 17280;
+pub const INSTANCE_BUMP_AMOUNT: u32 = 7 * DAY_IN_LEDGERS;
+pub const INSTANCE_LIFETIME_THRESHOLD: u32 = INSTANCE_BUMP_AMOUNT - DAY_IN_LEDGERS;
+pub const PERSISTENT_BUMP_AMOUNT: u32 = 30 * DAY_IN_LEDGERS;
+pub const PERSISTENT_LIFETIME_THRESHOLD: u32 = PERSISTENT_BUMP_AMOUNT - DAY_IN_LEDGERS;
+
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum DataKey {
+    Minter,
+    BaseUri,
+    NextTokenId,
+    Owner(u32),                  // Token owner, persistent
+    Receipt(u32),                // Custodied position, persistent
+    Balance(Address),            // Tokens held per owner, persistent
+    Approval(u32),               // Single-token approval, temporary
+    ApprovalForAll(Address, Address), // (owner, operator), temporary
+}
+
+/// An approval and the ledger it lapses after
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Approval {
+    pub approved: Address,
+    pub live_until_ledger: u32,
+}
+
+pub fn extend_instance(env: &Env) {
+    env.storage()
+        .instance()
+        .extend_ttl(INSTANCE_LIFETIME_THRESHOLD, INSTANCE_BUMP_AMOUNT);
+}
+
+fn extend(env: &Env, key: &DataKey) {
+    env.storage()
+        .persistent()
+        .extend_ttl(key, PERSISTENT_LIFETIME_THRESHOLD, PERSISTENT_BUMP_AMOUNT);
+}
+
+pub fn get_minter(env: &Env) -> Address {
+    env.storage().instance().get(&DataKey::Minter).unwrap()
+}
+
+pub fn set_minter(env: &Env, minter: &Address) {
+    env.storage().instance().set(&DataKey::Minter, minter);
+}
+
+pub fn get_base_uri(env: &Env) -> String {
+    env.storage().instance().get(&DataKey::BaseUri).unwrap()
+}
+
+pub fn set_base_uri(env: &Env, base_uri: &String) {
+    env.storage().instance().set(&DataKey::BaseUri, base_uri);
+}
+
+pub fn next_token_id(env: &Env) -> u32 {
+    let id: u32 = env.storage().instance().get(&DataKey::NextTokenId).unwrap_or(0);
+    env.storage().instance().set(&DataKey::NextTokenId, &(id + 1));
+    id
+}
+
+pub fn get_owner(env: &Env, token_id: u32) -> Address {
+    env.storage()
+        .persistent()
+        .get(&DataKey::Owner(token_id))
+        .unwrap_or_else(|| panic_with_error!(env, NftError::TokenNotFound))
+}
+
+pub fn set_owner(env: &Env, token_id: u32, owner: &Address) {
+    let key = DataKey::Owner(token_id);
+    env.storage().persistent().set(&key, owner);
+    extend(env, &key);
+    env.storage().temporary().remove(&DataKey::Approval(token_id));
+}
+
+pub fn get_receipt(env: &Env, token_id: u32) -> Receipt {
+    env.storage()
+        .persistent()
+        .get(&DataKey::Receipt(token_id))
+        .unwrap_or_else(|| panic_with_error!(env, NftError::TokenNotFound))
+}
+
+pub fn set_receipt(env: &Env, token_id: u32, receipt: &Receipt) {
+    let key = DataKey::Receipt(token_id);
+    env.storage().persistent().set(&key, receipt);
+    extend(env, &key);
+}
+
+/// Drop a burned token's owner, receipt and approval
+pub fn remove_token(env: &Env, token_id: u32) {
+    env.storage().persistent().remove(&DataKey::Owner(token_id));
+    env.storage().persistent().remove(&DataKey::Receipt(token_id));
+    env.storage().temporary().remove(&DataKey::Approval(token_id));
+}
+
+pub fn get_balance(env: &Env, owner: &Address) -> u32 {
+    env.storage()
+        .persistent()
+        .get(&DataKey::Balance(owner.clone()))
+        .unwrap_or(0)
+}
+
+pub fn set_balance(env: &Env, owner: &Address, balance: u32) {
+    let key = DataKey::Balance(owner.clone());
+    if balance == 0 {
+        env.storage().persistent().remove(&key);
+    } else {
+        env.storage().persistent().set(&key, &balance);
+        extend(env, &key);
+    }
+}
+
+/// Store an approval in temporary storage so it expires with `live_until_ledger`
+pub fn set_approval(env: &Env, key: &DataKey, approval: &Approval) {
+    let current = env.ledger().sequence();
+    if approval.live_until_ledger < current {
+        env.storage().temporary().remove(key);
+        return;
+    }
+    let live_for = approval.live_until_ledger - current;
+    env.storage().temporary().set(key, approval);
+    env.storage().temporary().extend_ttl(key, live_for, live_for);
+}
+
+/// The approved address for `key`, if the approval is still live
+pub fn get_approval(env: &Env, key: &DataKey) -> Option<Address> {
+    env.storage()
+        .temporary()
+        .get(key)
+        .filter(|approval: &Approval| approval.live_until_ledger >= env.ledger().sequence())
+        .map(|approval| approval.approved)
+}
@@ -0,0 +1,98 @@
+#![cfg(test)]
+
+use super::*;
+use soroban_sdk::{
+    testutils::{Address as _, Ledger},
+    token::StellarAssetClient,
+    Address, Env,
+};
+
+/// Register the NFT with a fresh minter and a token standing in for vault shares
+fn setup(env: &Env) -> (PositionNftClient<'_>, Address, Address) {
+    let minter = Address::generate(env);
+    let nft = env.register(
+        PositionNft,
+        (minter.clone(), String::from_str(env, "https://yieldzap.app/positions/")),
+    );
+    let vault = env
+        .register_stellar_asset_contract_v2(Address::generate(env))
+        .address();
+    (PositionNftClient::new(env, &nft), minter, vault)
+}
+
+fn mint(env: &Env, client: &PositionNftClient, vault: &Address, to: &Address, shares: i128) -> u32 {
+    StellarAssetClient::new(env, vault).mint(&client.address, &shares);
+    client.mint(to, vault, vault, &shares, &PRICE_SCALE)
+}
+
+#[test]
+fn test_mint_transfer_and_burn() {
+    let env = Env::default();
+    env.mock_all_auths();
+
+    let (client, minter, vault) = setup(&env);
+    let alice = Address::generate(&env);
+    let bob = Address::generate(&env);
+
+    let first = mint(&env, &client, &vault, &alice, 500);
+    let second = mint(&env, &client, &vault, &alice, 300);
+    assert_eq!((first, second), (0, 1));
+    assert_eq!(client.balance(&alice), 2);
+    assert_eq!(client.get_receipt(&second).shares, 300);
+    assert_eq!(
+        client.token_uri(&second),
+        String::from_str(&env, "https://yieldzap.app/positions/1")
+    );
+
+    client.transfer(&alice, &bob, &first);
+    assert_eq!(client.owner_of(&first), bob);
+    assert_eq!(client.balance(&alice), 1);
+    assert_eq!(client.balance(&bob), 1);
+    assert_eq!(
+        client.try_transfer(&alice, &bob, &first),
+        Err(Ok(NftError::NotOwner.into()))
+    );
+
+    // Only the current owner's token can be burned, and the shares go to the minter
+    assert_eq!(client.try_burn(&alice, &first), Err(Ok(NftError::NotOwner.into())));
+    let receipt = client.burn(&bob, &first);
+    assert_eq!(receipt.shares, 500);
+    assert_eq!(TokenClient::new(&env, &vault).balance(&minter), 500);
+    assert_eq!(client.balance(&bob), 0);
+    assert_eq!(client.try_owner_of(&first), Err(Ok(NftError::TokenNotFound.into())));
+}
+
+#[test]
+fn test_approvals() {
+    let env = Env::default();
+    env.mock_all_auths();
+
+    let (client, _, vault) = setup(&env);
+    let alice = Address::generate(&env);
+    let bob = Address::generate(&env);
+    let market = Address::generate(&env);
+    let token_id = mint(&env, &client, &vault, &alice, 500);
+
+    assert_eq!(
+        client.try_transfer_from(&market, &alice, &bob, &token_id),
+        Err(Ok(NftError::NotApproved.into()))
+    );
+
+    client.approve(&alice, &market, &token_id, &1_000);
+    assert_eq!(client.get_approved(&token_id), Some(market.clone()));
+    client.transfer_from(&market, &alice, &bob, &token_id);
+    assert_eq!(client.owner_of(&token_id), bob);
+    // Transfers clear the single-token approval
+    assert_eq!(client.get_approved(&token_id), None);
+
+    client.approve_for_all(&bob, &market, &1_000);
+    assert!(client.is_approved_for_all(&bob, &market));
+    client.transfer_from(&market, &bob, &alice, &token_id);
+
+    env.ledger().with_mut(|li| li.sequence_number = 1_001);
+    assert!(!client.is_approved_for_all(&bob, &market));
+    assert_eq!(
+        client.try_approve(&alice, &alice, &token_id, &2_000),
+        Err(Ok(NftError::InvalidApproval.into()))
+    );
+}
@@ -0,0 +1,80 @@
+#![no_std]
+
+//! 256-bit fixed-point math shared by the Zap and the contracts built on it
+
+pub const BPS_DENOMINATOR: i128 = 10_000;
+
+/// Rounding direction for fixed-point operations
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum Rounding {
+    Down,
+    Up,
+}
+
+/// Why a fixed-point operation failed; each contract maps these onto its own error type
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum MathError {
+    /// A negative input or a non-positive denominator
+    InvalidAmount,
+    /// The result does not fit in an i128
+    Overflow,
+}
+
+/// Full 256-bit product of two u128 values as (high, low)
+fn widening_mul(a: u128, b: u128) -> (u128, u128) {
+    const MASK: u128 = u64::MAX as u128;
+    let (a_hi, a_lo) = (a >> 64, a & MASK);
+    let (b_hi, b_lo) = (b >> 64, b & MASK);
+
+    let lo_lo = a_lo * b_lo;
+    let hi_lo = a_hi * b_lo;
+    let lo_hi = a_lo * b_hi;
+    let hi_hi = a_hi * b_hi;
+
+    let cross = (lo_lo >> 64) + (hi_lo & MASK) + (lo_hi & MASK);
+    let low = (cross << 64) | (lo_lo & MASK);
+    let high = hi_hi + (hi_lo >> 64) + (lo_hi >> 64) + (cross >> 64);
+    (high, low)
+}
+
+/// Divide the 256-bit value (high, low) by `d`, returning quotient and remainder
+///
+/// Requires high < d so the quotient fits in 128 bits, and d < 2^127.
+fn div_wide(high: u128, low: u128, d: u128) -> (u128, u128) {
+    let mut remainder = high;
+    let mut quotient = 0u128;
+    for i in (0..128).rev() {
+        remainder = (remainder << 1) | ((low >> i) & 1);
+        quotient <<= 1;
+        if remainder >= d {
+            remainder -= d;
+            quotient |= 1;
+        }
+    }
+    (quotient, remainder)
+}
+
+/// Compute a * b / denominator without intermediate overflow
+///
+/// Inputs must be non-negative amounts and the denominator positive.
+pub fn mul_div(a: i128, b: i128, denominator: i128, rounding: Rounding) -> Result<i128, MathError> {
+    if a < 0 || b < 0 || denominator <= 0 {
+        return Err(MathError::InvalidAmount);
+    }
+
+    let d = denominator as u128;
+    let (high, low) = widening_mul(a as u128, b as u128);
+    if high >= d {
+        return Err(MathError::Overflow);
+    }
+
+    let (mut quotient, remainder) = div_wide(high, low, d);
+    if rounding == Rounding::Up && remainder > 0 {
+        quotient += 1;
+    }
+
+    i128::try_from(quotient).map_err(|_| MathError::Overflow)
+}
+
+#[cfg(test)]
+mod test;
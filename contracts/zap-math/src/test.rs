@@ -0,0 +1,15 @@
+use super::*;
+
+#[test]
+fn test_mul_div_beyond_i128_products() {
+    // The product overflows an i128 but the quotient fits
+    assert_eq!(mul_div(i128::MAX, 1 << 20, 1 << 21, Rounding::Down), Ok(i128::MAX / 2));
+    assert_eq!(mul_div(i128::MAX, 3, 3, Rounding::Up), Ok(i128::MAX));
+}
+
+#[test]
+fn test_mul_div_errors() {
+    assert_eq!(mul_div(i128::MAX, 2, 1, Rounding::Down), Err(MathError::Overflow));
+    assert_eq!(mul_div(1, 1, 0, Rounding::Down), Err(MathError::InvalidAmount));
+    assert_eq!(mul_div(1, -1, 1, Rounding::Down), Err(MathError::InvalidAmount));
+}
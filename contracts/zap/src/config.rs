@@ -4,6 +4,7 @@ use soroban_sdk::{contracttype, Address, Env, String};
 #[derive(Clone, Debug, Eq, PartialEq)]
 pub struct ContractAddresses {
     pub soroswap_aggregator: Address,
+    pub soroswap_router: Address,
     pub defindex_factory: Address,
     pub usdc: Address,
     pub xlm: Address,
@@ -19,6 +20,11 @@ impl ContractAddresses {
                 env,
                 "CDLZFC3SYJYDZT7K67VZ75HPJVIEUVNIXF47ZG2FB2RMQQADUHHZX252", // Placeholder
             )),
+            // Replace with actual Soroswap Router address on Futurenet
+            soroswap_router: Address::from_string(&String::from_str(
+                env,
+                "CXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXX", // To be updated
+            )),
             // Replace with actual DeFindex Factory address  
             defindex_factory: Address::from_string(&String::from_str(
                 env,
@@ -50,6 +56,11 @@ impl ContractAddresses {
                 env,
                 "CXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXX", // To be updated
             )),
+            // Replace with actual Soroswap Router address on Mainnet
+            soroswap_router: Address::from_string(&String::from_str(
+                env,
+                "CXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXX", // To be updated
+            )),
             // Replace with actual DeFindex Factory address on Mainnet
             defindex_factory: Address::from_string(&String::from_str(
                 env,
@@ -80,6 +91,10 @@ impl ContractAddresses {
                 env,
                 "CAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAD2", // Local test address
             )),
+            soroswap_router: Address::from_string(&String::from_str(
+                env,
+                "CAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAL2", // Local test address
+            )),
             defindex_factory: Address::from_string(&String::from_str(
                 env,
                 "CAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAE2", // Local test address
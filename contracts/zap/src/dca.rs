@@ -0,0 +1,54 @@
+use soroban_sdk::{contracttype, Address, Env, Vec};
+
+use crate::storage::{self, DataKey, PERSISTENT_BUMP_AMOUNT, PERSISTENT_LIFETIME_THRESHOLD};
+
+/// Recurring zap funded by a budget escrowed in the Zap contract
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct DcaPlan {
+    pub id: u64,
+    pub owner: Address,
+    pub from_token: Address,
+    pub to_token: Address,
+    pub vault: Address,
+    pub amount_per_interval: i128,
+    pub interval: u64,          // Seconds between executions
+    pub slippage_bps: u32,      // Tolerance below the TWAP value at execution time
+    pub remaining: i128,        // Escrowed budget not yet zapped
+    pub next_execution: u64,    // Earliest ledger timestamp for the next slice
+}
+
+fn extend(env: &Env, key: &DataKey) {
+    env.storage()
+        .persistent()
+        .extend_ttl(key, PERSISTENT_LIFETIME_THRESHOLD, PERSISTENT_BUMP_AMOUNT);
+}
+
+pub fn next_id(env: &Env) -> u64 {
+    storage::next_id(env, &DataKey::NextDcaId)
+}
+
+pub fn get_plan(env: &Env, id: u64) -> Option<DcaPlan> {
+    env.storage().persistent().get(&DataKey::DcaPlan(id))
+}
+
+pub fn save_plan(env: &Env, plan: &DcaPlan) {
+    let key = DataKey::DcaPlan(plan.id);
+    let is_new = !env.storage().persistent().has(&key);
+    env.storage().persistent().set(&key, plan);
+    extend(env, &key);
+
+    if is_new {
+        storage::add_id(env, &DataKey::UserDcaPlans(plan.owner.clone()), plan.id);
+    }
+}
+
+pub fn remove_plan(env: &Env, plan: &DcaPlan) {
+    env.storage().persistent().remove(&DataKey::DcaPlan(plan.id));
+    storage::remove_id(env, &DataKey::UserDcaPlans(plan.owner.clone()), plan.id);
+}
+
+/// Ids of the active plans owned by `user`
+pub fn plan_ids(env: &Env, user: &Address) -> Vec<u64> {
+    storage::get_ids(env, &DataKey::UserDcaPlans(user.clone()))
+}
@@ -0,0 +1,47 @@
+use soroban_sdk::{contracterror, panic_with_error, Env};
+
+/// Typed errors surfaced by the Zap contract
+#[contracterror]
+#[derive(Copy, Clone, Debug, Eq, PartialEq, PartialOrd, Ord)]
+#[repr(u32)]
+pub enum ZapError {
+    AlreadyInitialized = 1,
+    NotInitialized = 2,
+    InvalidConfig = 3,
+    InvalidReserves = 4,
+    PriceDeviationTooHigh = 5,
+    InvalidQuote = 6,
+    PriceImpactTooHigh = 7,
+    Reentrancy = 8,
+    ArithmeticOverflow = 9,
+    InvalidAmount = 10,
+    NoRouteFound = 11,
+    InvalidRoute = 12,
+    InsufficientNativeReserve = 13,
+    MissingTrustline = 14,
+    OperatorNotAuthorized = 15,
+    OperatorExpired = 16,
+    OperatorLimitExceeded = 17,
+    OrderNotFound = 18,
+    OrderNotReady = 19,
+    OrderExpired = 20,
+    HarvestTooSoon = 21,
+    SnapshotTooSoon = 22,
+    NoEligibleVault = 23,
+    Unauthorized = 24,
+    InsufficientOutput = 25,
+    InsufficientPriceHistory = 26,
+    MissingPriceGuard = 27,
+    OperatorSlippageExceeded = 28,
+}
+
+/// Turn a typed error into a contract error panic
+pub trait OrPanic<T> {
+    fn or_panic(self, env: &Env) -> T;
+}
+
+impl<T> OrPanic<T> for Result<T, ZapError> {
+    fn or_panic(self, env: &Env) -> T {
+        self.unwrap_or_else(|error| panic_with_error!(env, error))
+    }
+}
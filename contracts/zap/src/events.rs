@@ -0,0 +1,618 @@
+use soroban_sdk::{contracttype, Address, Env, IntoVal, Symbol, Val};
+
+use crate::dca::DcaPlan;
+use crate::exits::ExitOrder;
+use crate::fees::ProtocolFee;
+use crate::harvest::{HarvestConfig, HarvestResult};
+use crate::limit::LimitZap;
+use crate::operators::OperatorPolicy;
+use crate::performance::{BestVault, VaultSample};
+use crate::rebalance::RebalanceResult;
+use crate::storage::{self, DataKey};
+use crate::twap::PriceGuard;
+
+/// Version of the event payloads below, bumped on any breaking change
+///
+/// Zap events use topics `(name, user, vault)`; `zap_id` ties together the
+/// events of one zap or zap-out, including every leg of a migration, rebalance
+/// or keeper-executed order.
+pub const EVENT_VERSION: u32 = 1;
+
+/// Correlation data shared by the events of one zap or zap-out
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct ZapContext {
+    pub id: u64,
+    pub user: Address,
+    pub vault: Address,
+}
+
+/// Start a zap or zap-out, allocating its correlation id
+pub fn begin(env: &Env, user: &Address, vault: &Address) -> ZapContext {
+    ZapContext {
+        id: storage::next_id(env, &DataKey::NextZapId),
+        user: user.clone(),
+        vault: vault.clone(),
+    }
+}
+
+impl ZapContext {
+    /// The same operation's context for events about another vault
+    pub fn with_vault(&self, vault: &Address) -> ZapContext {
+        ZapContext {
+            id: self.id,
+            user: self.user.clone(),
+            vault: vault.clone(),
+        }
+    }
+}
+
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct SwapEvent {
+    pub version: u32,
+    pub zap_id: u64,
+    pub token_in: Address,
+    pub token_out: Address,
+    pub amount_in: i128,
+    pub amount_out: i128,
+    pub price_impact_bps: u32,
+}
+
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct DepositEvent {
+    pub version: u32,
+    pub zap_id: u64,
+    pub asset: Address,
+    pub amount: i128,
+    pub receiver: Address,
+    pub shares: i128,
+}
+
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct WithdrawEvent {
+    pub version: u32,
+    pub zap_id: u64,
+    pub shares: i128,
+    pub assets: i128,
+}
+
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct ZapCompletedEvent {
+    pub version: u32,
+    pub zap_id: u64,
+    pub receiver: Address,
+    pub from_token: Address,
+    pub amount_in: i128,
+    pub to_token: Address,
+    pub amount_swapped: i128,
+    pub vault_shares: i128,
+    pub price_impact_bps: u32,
+    pub protocol_fee: i128,
+}
+
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct ZapOutCompletedEvent {
+    pub version: u32,
+    pub zap_id: u64,
+    pub vault_asset: Address,
+    pub to_token: Address,
+    pub shares_burned: i128,
+    pub assets_withdrawn: i128,
+    pub amount_out: i128,
+    pub price_impact_bps: u32,
+    pub protocol_fee: i128,
+}
+
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct EmergencyWithdrawEvent {
+    pub version: u32,
+    pub token: Address,
+    pub amount: i128,
+    pub to: Address,
+}
+
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct InitializedEvent {
+    pub version: u32,
+    pub admin: Address,
+}
+
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct OperatorSetEvent {
+    pub version: u32,
+    pub user: Address,
+    pub operator: Address,
+    pub policy: OperatorPolicy,
+}
+
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct OperatorRevokedEvent {
+    pub version: u32,
+    pub user: Address,
+    pub operator: Address,
+}
+
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct DcaCreatedEvent {
+    pub version: u32,
+    pub plan: DcaPlan,
+}
+
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct DcaExecutedEvent {
+    pub version: u32,
+    pub zap_id: u64,
+    pub plan_id: u64,
+    pub keeper: Address,
+    pub slice: i128,
+    pub tip: i128,
+    pub remaining: i128,
+}
+
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct DcaCancelledEvent {
+    pub version: u32,
+    pub plan_id: u64,
+    pub owner: Address,
+    pub refunded: i128,
+}
+
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct LimitZapCreatedEvent {
+    pub version: u32,
+    pub order: LimitZap,
+}
+
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct LimitZapFilledEvent {
+    pub version: u32,
+    pub zap_id: u64,
+    pub order_id: u64,
+    pub owner: Address,
+    pub amount_swapped: i128,
+    pub vault_shares: i128,
+}
+
+/// Published as `limit_zap_cancelled` or `limit_zap_refunded`
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct LimitZapClosedEvent {
+    pub version: u32,
+    pub order_id: u64,
+    pub owner: Address,
+    pub amount_in: i128,
+}
+
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct ExitOrderCreatedEvent {
+    pub version: u32,
+    pub order: ExitOrder,
+}
+
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct ExitOrderExecutedEvent {
+    pub version: u32,
+    pub zap_id: u64,
+    pub order_id: u64,
+    pub keeper: Address,
+    pub value: i128,
+    pub tip: i128,
+    pub amount_out: i128,
+}
+
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct ExitOrderCancelledEvent {
+    pub version: u32,
+    pub order_id: u64,
+    pub owner: Address,
+    pub shares: i128,
+}
+
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct HarvestedEvent {
+    pub version: u32,
+    pub zap_id: u64,
+    pub vault: Address,
+    pub keeper: Address,
+    pub result: HarvestResult,
+}
+
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct MigratedEvent {
+    pub version: u32,
+    pub zap_id: u64,
+    pub user: Address,
+    pub from_vault: Address,
+    pub shares: i128,
+    pub to_vault: Address,
+    pub vault_shares: i128,
+}
+
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct RebalancedEvent {
+    pub version: u32,
+    pub zap_id: u64,
+    pub user: Address,
+    pub result: RebalanceResult,
+}
+
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct VaultSnapshotEvent {
+    pub version: u32,
+    pub sample: VaultSample,
+}
+
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct BestVaultChosenEvent {
+    pub version: u32,
+    pub zap_id: u64,
+    pub best: BestVault,
+}
+
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct PriceGuardSetEvent {
+    pub version: u32,
+    pub token_a: Address,
+    pub token_b: Address,
+    pub guard: PriceGuard,
+}
+
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct PriceGuardRemovedEvent {
+    pub version: u32,
+    pub token_a: Address,
+    pub token_b: Address,
+}
+
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct ProtocolFeeSetEvent {
+    pub version: u32,
+    pub fee: ProtocolFee,
+}
+
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct HarvestConfigSetEvent {
+    pub version: u32,
+    pub vault: Address,
+    pub config: HarvestConfig,
+}
+
+fn publish<D: IntoVal<Env, Val>>(env: &Env, name: &str, ctx: &ZapContext, data: D) {
+    publish_for(env, name, &ctx.user, &ctx.vault, data);
+}
+
+/// Publish an event that belongs to a user and vault under the topics `(name, user, vault)`
+fn publish_for<D: IntoVal<Env, Val>>(env: &Env, name: &str, user: &Address, vault: &Address, data: D) {
+    env.events().publish((Symbol::new(env, name), user.clone(), vault.clone()), data);
+}
+
+pub fn swap(
+    env: &Env,
+    ctx: &ZapContext,
+    token_in: &Address,
+    token_out: &Address,
+    amount_in: i128,
+    amount_out: i128,
+    price_impact_bps: u32,
+) {
+    let event = SwapEvent {
+        version: EVENT_VERSION,
+        zap_id: ctx.id,
+        token_in: token_in.clone(),
+        token_out: token_out.clone(),
+        amount_in,
+        amount_out,
+        price_impact_bps,
+    };
+    publish(env, "soroswap_swap", ctx, event);
+}
+
+pub fn deposit(env: &Env, ctx: &ZapContext, asset: &Address, amount: i128, receiver: &Address, shares: i128) {
+    let event = DepositEvent {
+        version: EVENT_VERSION,
+        zap_id: ctx.id,
+        asset: asset.clone(),
+        amount,
+        receiver: receiver.clone(),
+        shares,
+    };
+    publish(env, "defindex_deposit", ctx, event);
+}
+
+pub fn withdraw(env: &Env, ctx: &ZapContext, shares: i128, assets: i128) {
+    let event = WithdrawEvent {
+        version: EVENT_VERSION,
+        zap_id: ctx.id,
+        shares,
+        assets,
+    };
+    publish(env, "defindex_withdraw", ctx, event);
+}
+
+pub fn zap_completed(env: &Env, ctx: &ZapContext, event: ZapCompletedEvent) {
+    publish(env, "zap_completed", ctx, event);
+}
+
+pub fn zap_out_completed(env: &Env, ctx: &ZapContext, event: ZapOutCompletedEvent) {
+    publish(env, "zap_out_completed", ctx, event);
+}
+
+pub fn emergency_withdraw(env: &Env, admin: &Address, token: &Address, amount: i128, to: &Address) {
+    let event = EmergencyWithdrawEvent {
+        version: EVENT_VERSION,
+        token: token.clone(),
+        amount,
+        to: to.clone(),
+    };
+    env.events().publish(
+        (Symbol::new(env, "emergency_withdraw"), admin.clone()),
+        event,
+    );
+}
+
+pub fn initialized(env: &Env, admin: &Address) {
+    let event = InitializedEvent {
+        version: EVENT_VERSION,
+        admin: admin.clone(),
+    };
+    publish_global(env, "initialized", event);
+}
+
+/// Publish an event outside a zap under the topics `(name,)`
+fn publish_global<D: IntoVal<Env, Val>>(env: &Env, name: &str, data: D) {
+    env.events().publish((Symbol::new(env, name),), data);
+}
+
+pub fn operator_set(env: &Env, user: &Address, operator: &Address, policy: &OperatorPolicy) {
+    let event = OperatorSetEvent {
+        version: EVENT_VERSION,
+        user: user.clone(),
+        operator: operator.clone(),
+        policy: policy.clone(),
+    };
+    env.events().publish((Symbol::new(env, "operator_set"), user.clone()), event);
+}
+
+pub fn operator_revoked(env: &Env, user: &Address, operator: &Address) {
+    let event = OperatorRevokedEvent {
+        version: EVENT_VERSION,
+        user: user.clone(),
+        operator: operator.clone(),
+    };
+    env.events().publish((Symbol::new(env, "operator_revoked"), user.clone()), event);
+}
+
+pub fn dca_created(env: &Env, plan: &DcaPlan) {
+    let event = DcaCreatedEvent {
+        version: EVENT_VERSION,
+        plan: plan.clone(),
+    };
+    publish_for(env, "dca_created", &plan.owner, &plan.vault, event);
+}
+
+pub fn dca_executed(
+    env: &Env,
+    ctx: &ZapContext,
+    plan: &DcaPlan,
+    keeper: &Address,
+    slice: i128,
+    tip: i128,
+) {
+    let event = DcaExecutedEvent {
+        version: EVENT_VERSION,
+        zap_id: ctx.id,
+        plan_id: plan.id,
+        keeper: keeper.clone(),
+        slice,
+        tip,
+        remaining: plan.remaining,
+    };
+    publish(env, "dca_executed", ctx, event);
+}
+
+pub fn dca_cancelled(env: &Env, plan: &DcaPlan) {
+    let event = DcaCancelledEvent {
+        version: EVENT_VERSION,
+        plan_id: plan.id,
+        owner: plan.owner.clone(),
+        refunded: plan.remaining,
+    };
+    publish_for(env, "dca_cancelled", &plan.owner, &plan.vault, event);
+}
+
+pub fn limit_zap_created(env: &Env, order: &LimitZap) {
+    let event = LimitZapCreatedEvent {
+        version: EVENT_VERSION,
+        order: order.clone(),
+    };
+    publish_for(env, "limit_zap_created", &order.owner, &order.vault, event);
+}
+
+pub fn limit_zap_filled(
+    env: &Env,
+    ctx: &ZapContext,
+    order: &LimitZap,
+    amount_swapped: i128,
+    vault_shares: i128,
+) {
+    let event = LimitZapFilledEvent {
+        version: EVENT_VERSION,
+        zap_id: ctx.id,
+        order_id: order.id,
+        owner: order.owner.clone(),
+        amount_swapped,
+        vault_shares,
+    };
+    publish(env, "limit_zap_filled", ctx, event);
+}
+
+pub fn limit_zap_closed(env: &Env, name: &str, order: &LimitZap) {
+    let event = LimitZapClosedEvent {
+        version: EVENT_VERSION,
+        order_id: order.id,
+        owner: order.owner.clone(),
+        amount_in: order.amount_in,
+    };
+    publish_for(env, name, &order.owner, &order.vault, event);
+}
+
+pub fn exit_order_created(env: &Env, order: &ExitOrder) {
+    let event = ExitOrderCreatedEvent {
+        version: EVENT_VERSION,
+        order: order.clone(),
+    };
+    publish_for(env, "exit_order_created", &order.owner, &order.vault, event);
+}
+
+pub fn exit_order_executed(
+    env: &Env,
+    ctx: &ZapContext,
+    order: &ExitOrder,
+    keeper: &Address,
+    value: i128,
+    tip: i128,
+    amount_out: i128,
+) {
+    let event = ExitOrderExecutedEvent {
+        version: EVENT_VERSION,
+        zap_id: ctx.id,
+        order_id: order.id,
+        keeper: keeper.clone(),
+        value,
+        tip,
+        amount_out,
+    };
+    publish(env, "exit_order_executed", ctx, event);
+}
+
+pub fn exit_order_cancelled(env: &Env, order: &ExitOrder) {
+    let event = ExitOrderCancelledEvent {
+        version: EVENT_VERSION,
+        order_id: order.id,
+        owner: order.owner.clone(),
+        shares: order.shares,
+    };
+    publish_for(env, "exit_order_cancelled", &order.owner, &order.vault, event);
+}
+
+pub fn harvested(env: &Env, ctx: &ZapContext, keeper: &Address, result: &HarvestResult) {
+    let event = HarvestedEvent {
+        version: EVENT_VERSION,
+        zap_id: ctx.id,
+        vault: ctx.vault.clone(),
+        keeper: keeper.clone(),
+        result: result.clone(),
+    };
+    publish(env, "harvested", ctx, event);
+}
+
+pub fn migrated(
+    env: &Env,
+    ctx: &ZapContext,
+    from_vault: &Address,
+    shares: i128,
+    to_vault: &Address,
+    vault_shares: i128,
+) {
+    let event = MigratedEvent {
+        version: EVENT_VERSION,
+        zap_id: ctx.id,
+        user: ctx.user.clone(),
+        from_vault: from_vault.clone(),
+        shares,
+        to_vault: to_vault.clone(),
+        vault_shares,
+    };
+    publish(env, "migrated", ctx, event);
+}
+
+pub fn rebalanced(env: &Env, ctx: &ZapContext, result: &RebalanceResult) {
+    let event = RebalancedEvent {
+        version: EVENT_VERSION,
+        zap_id: ctx.id,
+        user: ctx.user.clone(),
+        result: result.clone(),
+    };
+    publish(env, "rebalanced", ctx, event);
+}
+
+pub fn vault_snapshot(env: &Env, vault: &Address, sample: &VaultSample) {
+    let event = VaultSnapshotEvent {
+        version: EVENT_VERSION,
+        sample: sample.clone(),
+    };
+    env.events().publish(
+        (Symbol::new(env, "vault_snapshot"), vault.clone()),
+        event,
+    );
+}
+
+pub fn best_vault_chosen(env: &Env, ctx: &ZapContext, best: &BestVault) {
+    let event = BestVaultChosenEvent {
+        version: EVENT_VERSION,
+        zap_id: ctx.id,
+        best: best.clone(),
+    };
+    publish(env, "best_vault_chosen", ctx, event);
+}
+
+pub fn price_guard_set(env: &Env, token_a: &Address, token_b: &Address, guard: &PriceGuard) {
+    let event = PriceGuardSetEvent {
+        version: EVENT_VERSION,
+        token_a: token_a.clone(),
+        token_b: token_b.clone(),
+        guard: guard.clone(),
+    };
+    publish_global(env, "price_guard_set", event);
+}
+
+pub fn price_guard_removed(env: &Env, token_a: &Address, token_b: &Address) {
+    let event = PriceGuardRemovedEvent {
+        version: EVENT_VERSION,
+        token_a: token_a.clone(),
+        token_b: token_b.clone(),
+    };
+    publish_global(env, "price_guard_removed", event);
+}
+
+pub fn protocol_fee_set(env: &Env, fee: &ProtocolFee) {
+    let event = ProtocolFeeSetEvent {
+        version: EVENT_VERSION,
+        fee: fee.clone(),
+    };
+    publish_global(env, "protocol_fee_set", event);
+}
+
+pub fn harvest_config_set(env: &Env, vault: &Address, config: &HarvestConfig) {
+    let event = HarvestConfigSetEvent {
+        version: EVENT_VERSION,
+        vault: vault.clone(),
+        config: config.clone(),
+    };
+    publish_global(env, "harvest_config_set", event);
+}
@@ -0,0 +1,62 @@
+use soroban_sdk::{contracttype, Address, Env, Vec};
+
+use crate::storage::{self, DataKey, PERSISTENT_BUMP_AMOUNT, PERSISTENT_LIFETIME_THRESHOLD};
+
+/// Side of the threshold on which an exit order triggers
+#[contracttype]
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum ExitTrigger {
+    Below, // Stop-loss: position value at or below the threshold
+    Above, // Take-profit: position value at or above the threshold
+}
+
+/// Vault shares escrowed until their value in `to_token` crosses `threshold`
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct ExitOrder {
+    pub id: u64,
+    pub owner: Address,
+    pub vault: Address,
+    pub shares: i128,
+    pub vault_asset: Address,
+    pub to_token: Address,      // Quote token for the trigger and output of the zap-out
+    pub trigger: ExitTrigger,
+    pub threshold: i128,        // Position value in `to_token`
+    pub slippage_bps: u32,      // Tolerance below the TWAP value at execution time
+}
+
+impl ExitOrder {
+    pub fn is_triggered(&self, value: i128) -> bool {
+        match self.trigger {
+            ExitTrigger::Below => value <= self.threshold,
+            ExitTrigger::Above => value >= self.threshold,
+        }
+    }
+}
+
+pub fn next_id(env: &Env) -> u64 {
+    storage::next_id(env, &DataKey::NextExitOrderId)
+}
+
+pub fn get_order(env: &Env, id: u64) -> Option<ExitOrder> {
+    env.storage().persistent().get(&DataKey::ExitOrder(id))
+}
+
+pub fn save_order(env: &Env, order: &ExitOrder) {
+    let key = DataKey::ExitOrder(order.id);
+    env.storage().persistent().set(&key, order);
+    env.storage()
+        .persistent()
+        .extend_ttl(&key, PERSISTENT_LIFETIME_THRESHOLD, PERSISTENT_BUMP_AMOUNT);
+    storage::add_id(env, &DataKey::UserExitOrders(order.owner.clone()), order.id);
+}
+
+pub fn remove_order(env: &Env, order: &ExitOrder) {
+    env.storage().persistent().remove(&DataKey::ExitOrder(order.id));
+    storage::remove_id(env, &DataKey::UserExitOrders(order.owner.clone()), order.id);
+}
+
+/// Ids of the open exit orders owned by `user`
+pub fn order_ids(env: &Env, user: &Address) -> Vec<u64> {
+    storage::get_ids(env, &DataKey::UserExitOrders(user.clone()))
+}
@@ -0,0 +1,108 @@
+use soroban_sdk::{contracttype, panic_with_error, token::Client as TokenClient, Address, Env};
+
+use crate::errors::{OrPanic, ZapError};
+use crate::math::{self, Rounding};
+use crate::storage::DataKey;
+
+/// Upper bound for the protocol fee (1%)
+pub const MAX_PROTOCOL_FEE_BPS: u32 = 100;
+
+/// Upper bound for the keeper tip (1%)
+pub const MAX_KEEPER_TIP_BPS: u32 = 100;
+/// Keeper tip used until the admin sets one (0.1%)
+pub const DEFAULT_KEEPER_TIP_BPS: u32 = 10;
+
+/// Upper bound for the harvest keeper fee (5%)
+pub const MAX_HARVEST_FEE_BPS: u32 = 500;
+/// Harvest keeper fee used until the admin sets one (1%)
+pub const DEFAULT_HARVEST_FEE_BPS: u32 = 100;
+
+/// Fee taken from the input amount of every zap
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct ProtocolFee {
+    pub fee_bps: u32,
+    pub recipient: Address,
+}
+
+pub fn get_protocol_fee(env: &Env) -> Option<ProtocolFee> {
+    env.storage().instance().get(&DataKey::ProtocolFee)
+}
+
+pub fn set_protocol_fee(env: &Env, fee: &ProtocolFee) {
+    if fee.fee_bps > MAX_PROTOCOL_FEE_BPS {
+        panic_with_error!(env, ZapError::InvalidConfig);
+    }
+    env.storage().instance().set(&DataKey::ProtocolFee, fee);
+}
+
+/// Protocol fee owed on `amount`, rounded down in favour of the user
+pub fn fee_for(env: &Env, amount: i128) -> i128 {
+    match get_protocol_fee(env) {
+        Some(fee) => math::apply_bps(amount, fee.fee_bps, Rounding::Down).or_panic(env),
+        None => 0,
+    }
+}
+
+/// Send the protocol fee on `amount` of `token` held by this contract to the fee recipient
+///
+/// Returns the fee taken.
+pub fn collect(env: &Env, token: &Address, amount: i128) -> i128 {
+    let Some(fee) = get_protocol_fee(env) else {
+        return 0;
+    };
+
+    let fee_amount = math::apply_bps(amount, fee.fee_bps, Rounding::Down).or_panic(env);
+    if fee_amount > 0 {
+        TokenClient::new(env, token).transfer(
+            &env.current_contract_address(),
+            &fee.recipient,
+            &fee_amount,
+        );
+    }
+    fee_amount
+}
+
+pub fn get_keeper_tip_bps(env: &Env) -> u32 {
+    env.storage()
+        .instance()
+        .get(&DataKey::KeeperTip)
+        .unwrap_or(DEFAULT_KEEPER_TIP_BPS)
+}
+
+pub fn set_keeper_tip_bps(env: &Env, tip_bps: u32) {
+    if tip_bps > MAX_KEEPER_TIP_BPS {
+        panic_with_error!(env, ZapError::InvalidConfig);
+    }
+    env.storage().instance().set(&DataKey::KeeperTip, &tip_bps);
+}
+
+pub fn get_harvest_fee_bps(env: &Env) -> u32 {
+    env.storage()
+        .instance()
+        .get(&DataKey::HarvestFee)
+        .unwrap_or(DEFAULT_HARVEST_FEE_BPS)
+}
+
+pub fn set_harvest_fee_bps(env: &Env, fee_bps: u32) {
+    if fee_bps > MAX_HARVEST_FEE_BPS {
+        panic_with_error!(env, ZapError::InvalidConfig);
+    }
+    env.storage().instance().set(&DataKey::HarvestFee, &fee_bps);
+}
+
+/// Pay the keeper tip on `amount` of `token` held by this contract to `keeper`
+///
+/// Returns the tip paid.
+pub fn pay_keeper_tip(env: &Env, token: &Address, amount: i128, keeper: &Address) -> i128 {
+    pay_keeper(env, token, amount, get_keeper_tip_bps(env), keeper)
+}
+
+/// Pay `fee_bps` of `amount` of `token` held by this contract to `keeper`
+pub fn pay_keeper(env: &Env, token: &Address, amount: i128, fee_bps: u32, keeper: &Address) -> i128 {
+    let fee = math::apply_bps(amount, fee_bps, Rounding::Down).or_panic(env);
+    if fee > 0 {
+        TokenClient::new(env, token).transfer(&env.current_contract_address(), keeper, &fee);
+    }
+    fee
+}
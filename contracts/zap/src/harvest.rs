@@ -0,0 +1,61 @@
+use soroban_sdk::{contracttype, panic_with_error, Address, Env, Vec};
+
+use crate::errors::ZapError;
+use crate::storage::{DataKey, PERSISTENT_BUMP_AMOUNT, PERSISTENT_LIFETIME_THRESHOLD};
+
+/// Vault registered for keeper harvesting
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct HarvestConfig {
+    pub asset: Address,               // Vault asset rewards are swapped into
+    pub reward_tokens: Vec<Address>,  // Tokens the harvest pays out
+    pub min_interval: u64,            // Seconds between harvests of this vault
+    pub harvester: Address,           // Contract exposing `harvest(to)`: the vault or an adapter
+    pub beneficiary: Address,         // Owner of the position the compounded deposit is credited to
+}
+
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct HarvestResult {
+    pub compounded: i128,   // Vault asset deposited for the beneficiary
+    pub shares: i128,       // Vault shares minted to the beneficiary
+    pub keeper_fee: i128,   // Vault asset paid to the keeper
+}
+
+pub fn get_config(env: &Env, vault: &Address) -> Option<HarvestConfig> {
+    env.storage()
+        .instance()
+        .get(&DataKey::HarvestConfig(vault.clone()))
+}
+
+pub fn set_config(env: &Env, vault: &Address, config: &HarvestConfig) {
+    // Shares minted to the vault itself would just be a donation to every holder
+    if config.reward_tokens.is_empty() || config.min_interval == 0 || config.beneficiary == *vault {
+        panic_with_error!(env, ZapError::InvalidConfig);
+    }
+    env.storage()
+        .instance()
+        .set(&DataKey::HarvestConfig(vault.clone()), config);
+}
+
+pub fn remove_config(env: &Env, vault: &Address) {
+    env.storage()
+        .instance()
+        .remove(&DataKey::HarvestConfig(vault.clone()));
+}
+
+/// Record a harvest now, failing if the vault was harvested less than `min_interval` ago
+pub fn check_and_record(env: &Env, vault: &Address, min_interval: u64) {
+    let key = DataKey::LastHarvest(vault.clone());
+    let now = env.ledger().timestamp();
+    if let Some(last) = env.storage().persistent().get::<_, u64>(&key) {
+        if now < last.saturating_add(min_interval) {
+            panic_with_error!(env, ZapError::HarvestTooSoon);
+        }
+    }
+
+    env.storage().persistent().set(&key, &now);
+    env.storage()
+        .persistent()
+        .extend_ttl(&key, PERSISTENT_LIFETIME_THRESHOLD, PERSISTENT_BUMP_AMOUNT);
+}
@@ -0,0 +1,62 @@
+use soroban_sdk::{panic_with_error, Env};
+
+use crate::errors::{OrPanic, ZapError};
+use crate::math::{self, Rounding};
+use crate::storage::DataKey;
+
+/// Cap applied when neither the user nor the admin picked one (5%)
+pub const DEFAULT_MAX_PRICE_IMPACT_BPS: u32 = 500;
+/// The probe quote uses amount_in / PROBE_DIVISOR to approximate the marginal price
+pub const PROBE_DIVISOR: i128 = 1_000;
+const RATE_SCALE: i128 = 1_000_000_000;
+
+pub fn get_default_cap(env: &Env) -> u32 {
+    env.storage()
+        .instance()
+        .get(&DataKey::MaxPriceImpact)
+        .unwrap_or(DEFAULT_MAX_PRICE_IMPACT_BPS)
+}
+
+pub fn set_default_cap(env: &Env, max_bps: u32) {
+    if max_bps > 10_000 {
+        panic_with_error!(env, ZapError::InvalidConfig);
+    }
+    env.storage().instance().set(&DataKey::MaxPriceImpact, &max_bps);
+}
+
+/// Amount used for the near-zero-impact probe quote
+pub fn probe_amount(amount_in: i128) -> i128 {
+    (amount_in / PROBE_DIVISOR).max(1)
+}
+
+/// Price impact in bps of trading `amount_in` for `amount_out`, relative to the probe rate
+///
+/// Dust amounts whose probe quote rounds to zero cannot be measured and report no impact.
+pub fn price_impact_bps(
+    env: &Env,
+    probe_in: i128,
+    probe_out: i128,
+    amount_in: i128,
+    amount_out: i128,
+) -> u32 {
+    if probe_out <= 0 || amount_in <= 0 {
+        return 0;
+    }
+
+    let rate = |out: i128, input: i128| {
+        math::mul_div(out, RATE_SCALE, input, Rounding::Down).or_panic(env)
+    };
+    let marginal_rate = rate(probe_out, probe_in);
+    let effective_rate = rate(amount_out.max(0), amount_in);
+
+    if marginal_rate == 0 || effective_rate >= marginal_rate {
+        return 0;
+    }
+    math::mul_div(
+        marginal_rate - effective_rate,
+        math::BPS_DENOMINATOR,
+        marginal_rate,
+        Rounding::Up,
+    )
+    .or_panic(env) as u32
+}
@@ -1,14 +1,21 @@
 use soroban_sdk::{contracttype, Address, Vec};
 
-/// Soroswap Aggregator Interface - Documentation Only
-/// 
-/// The actual contract calls are made using env.invoke_contract()
-/// These types document the expected function signatures
+// Soroswap Aggregator Interface - Documentation Only
+//
+// The actual contract calls are made using env.invoke_contract()
+// These types document the expected function signatures
 
-/// DeFindex Vault Interface - Documentation Only
-/// 
-/// The actual contract calls are made using env.invoke_contract()
-/// These types document the expected function signatures
+// DeFindex Vault Interface - Documentation Only
+//
+// The actual contract calls are made using env.invoke_contract()
+// These types document the expected function signatures
+
+// Harvest Adapter Interface - Documentation Only
+//
+// harvest_and_compound calls `harvest(to: Address)` on the `harvester` registered with
+// set_harvest_config, which must transfer the pending reward tokens to `to`. DeFindex vaults
+// do not expose it themselves, so in practice the harvester is an adapter contract that claims
+// the rewards of the vault's strategies; a vault implementing it can be registered directly.
 
 #[contracttype]
 #[derive(Clone, Debug, Eq, PartialEq)]
@@ -33,4 +40,23 @@ pub struct SwapRoute {
     pub expected_amount: i128,    // Expected output amount
     pub minimum_amount: i128,     // Minimum acceptable output
     pub price_impact: u32,        // Price impact in basis points
+    pub venue: SwapVenue,         // Where the route is quoted and executed
+}
+
+/// Where a route is quoted and executed
+#[contracttype]
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum SwapVenue {
+    Aggregator, // Soroswap Aggregator, path + distribution across DEXs
+    Router,     // Soroswap Router, single path through Soroswap pairs
+}
+
+/// One leg of a split zap, executing `weight_bps` of the input along its own path
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct SubRoute {
+    pub weight_bps: u32,          // Share of the input amount, legs must sum to 10_000
+    pub path: Vec<Address>,       // Token path (if empty, will use optimal routing)
+    pub distribution: Vec<u32>,   // Distribution across DEXs for aggregator paths
+    pub venue: SwapVenue,         // Venue used for a non-empty path
 }
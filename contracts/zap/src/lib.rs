@@ -1,98 +1,1731 @@
 #![no_std]
+#![allow(clippy::too_many_arguments)]
 
 mod interfaces;
+mod limit;
 mod config;
+mod dca;
+mod errors;
+mod events;
+mod exits;
+mod fees;
+mod harvest;
+mod impact;
+pub mod math;
+mod native;
+mod nft;
+mod operators;
+mod performance;
+mod positions;
+mod rebalance;
+mod routing;
+mod storage;
+mod trustline;
+mod twap;
+
+#[cfg(test)]
+mod test;
 
 use soroban_sdk::{
-    contract, contractimpl, contracttype, Address, Env, IntoVal, Symbol, TryIntoVal, Val, Vec, vec,
-    token::Client as TokenClient,
+    contract, contractimpl, contracttype, panic_with_error, Address, Env, IntoVal, Symbol,
+    TryIntoVal, Val, Vec, vec, token::Client as TokenClient,
+};
+
+pub use config::{ContractAddresses, VaultAddresses};
+pub use dca::DcaPlan;
+pub use errors::ZapError;
+pub use events::{
+    BestVaultChosenEvent, DcaCancelledEvent, DcaCreatedEvent, DcaExecutedEvent, DepositEvent,
+    EmergencyWithdrawEvent, ExitOrderCancelledEvent, ExitOrderCreatedEvent, ExitOrderExecutedEvent,
+    HarvestConfigSetEvent, HarvestedEvent, InitializedEvent, LimitZapClosedEvent,
+    LimitZapCreatedEvent, LimitZapFilledEvent, MigratedEvent, OperatorRevokedEvent,
+    OperatorSetEvent, PriceGuardRemovedEvent, PriceGuardSetEvent, ProtocolFeeSetEvent,
+    RebalancedEvent, SwapEvent, VaultSnapshotEvent, WithdrawEvent, ZapCompletedEvent,
+    ZapOutCompletedEvent, EVENT_VERSION,
 };
+use events::ZapContext;
+pub use exits::{ExitOrder, ExitTrigger};
+use errors::OrPanic;
+pub use fees::ProtocolFee;
+pub use harvest::{HarvestConfig, HarvestResult};
+pub use interfaces::{SubRoute, SwapRoute, SwapVenue};
+pub use limit::LimitZap;
+pub use nft::{NftZapResult, PositionReceipt};
+pub use operators::OperatorPolicy;
+pub use performance::{BestVault, VaultConstraints, VaultSample};
+pub use positions::Position;
+pub use rebalance::{RebalanceLeg, RebalanceResult};
+pub use twap::PriceGuard;
+
+/// Slippage levels, in bps, for which quotes suggest minimum outputs
+pub const SUGGESTED_SLIPPAGE_BPS: [u32; 3] = [10, 50, 100];
+/// Slippage used for the route's own `minimum_amount` in quotes
+pub const DEFAULT_SLIPPAGE_BPS: u32 = 50;
+
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct SwapPath {
+    pub token_in: Address,
+    pub token_out: Address,
+    pub amount_in: i128,
+    pub amount_out_min: i128,
+    pub distribution: Vec<u32>, // Distribution percentages across multiple DEXs
+    pub path: Vec<Address>,     // Token addresses in swap path
+}
+
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct ZapResult {
+    pub amount_swapped: i128,
+    pub vault_shares: i128,
+    pub vault_address: Address,
+    pub price_impact_bps: u32, // Price impact of the swap leg
+    pub protocol_fee: i128,    // Fee taken from the input amount
+}
+
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct ZapOutResult {
+    pub shares_burned: i128,
+    pub assets_withdrawn: i128, // Vault asset received before the swap
+    pub amount_out: i128,       // Amount of the output token sent to the user
+    pub price_impact_bps: u32,
+    pub protocol_fee: i128,     // Fee taken from the withdrawn assets
+}
+
+/// Minimum outputs to pass to a zap for a given slippage tolerance
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct MinOutSuggestion {
+    pub slippage_bps: u32,
+    pub min_amount_out: i128,
+    pub min_vault_shares: i128,
+}
+
+/// Full expected outcome of a zap, as returned by `quote_zap`
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct ZapQuote {
+    pub amount_in: i128,
+    pub protocol_fee: i128,
+    pub expected_amount_out: i128,  // Expected output of the swap leg
+    pub price_impact_bps: u32,
+    pub expected_vault_shares: i128,
+    pub route: SwapRoute,           // Resolved route (empty path when no swap is needed)
+    pub min_out_suggestions: Vec<MinOutSuggestion>,
+}
+
+/// Outcome of `zap_to_best_vault`: the vault picked and the zap into it
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct BestVaultResult {
+    pub best: BestVault,
+    pub zap: ZapResult,
+}
+
+#[contract]
+pub struct Zap;
+
+#[contractimpl]
+impl Zap {
+    /// Main zap function: swap tokens via Soroswap then deposit into DeFindex Vault
+    /// 
+    /// # Arguments
+    /// * `user` - Address of the user performing the zap
+    /// * `from_token` - Address of input token to swap from
+    /// * `amount_in` - Amount of input tokens to swap
+    /// * `to_token` - Address of output token to receive
+    /// * `vault_address` - Address of DeFindex vault to deposit into
+    /// * `min_amount_out` - Minimum amount of output tokens from swap
+    /// * `swap_path` - Optional custom swap path (if empty, will use optimal routing)
+    /// * `distribution` - Optional custom distribution across DEXs
+    /// * `max_price_impact_bps` - Optional price impact cap (defaults to the admin cap)
+    /// * `receiver` - Optional address credited with the vault shares (defaults to `user`)
+    /// 
+    /// # Returns
+    /// * ZapResult containing swap amount, vault shares received and price impact
+    pub fn zap_and_deposit(
+        env: Env,
+        user: Address,
+        from_token: Address,
+        amount_in: i128,
+        to_token: Address,
+        vault_address: Address,
+        min_amount_out: i128,
+        swap_path: Vec<Address>,
+        distribution: Vec<u32>,
+        max_price_impact_bps: Option<u32>,
+        receiver: Option<Address>,
+    ) -> ZapResult {
+        user.require_auth();
+        storage::extend_instance(&env);
+
+        // Validate inputs
+        if amount_in <= 0 {
+            panic_with_error!(&env, ZapError::InvalidAmount);
+        }
+
+        // Block re-entry while the contract holds user funds
+        storage::lock(&env);
+
+        // Native XLM comes straight from the classic balance, minus the reserve headroom
+        native::check_headroom(&env, &user, &from_token, amount_in);
+
+        // Step 1: Transfer input tokens from user to this contract
+        Self::take(&env, &from_token, &user, amount_in, false);
+
+        let route = Self::single_route(swap_path, distribution);
+        let receiver = receiver.unwrap_or_else(|| user.clone());
+        let ctx = events::begin(&env, &user, &vault_address);
+        let result = Self::execute_zap(
+            &env,
+            &ctx,
+            receiver,
+            from_token,
+            amount_in,
+            to_token,
+            min_amount_out,
+            vec![&env, route],
+            max_price_impact_bps,
+        );
+
+        storage::unlock(&env);
+        result
+    }
+
+    /// Zap with the swap split across several weighted sub-routes
+    ///
+    /// Each leg swaps its share of the input along its own path (e.g. 60% direct,
+    /// 40% via XLM); outputs are summed and `min_amount_out` applies to the total.
+    ///
+    /// # Arguments
+    /// * `user` - Address of the user performing the zap
+    /// * `from_token` - Address of input token to swap from
+    /// * `amount_in` - Amount of input tokens to swap
+    /// * `to_token` - Address of output token to receive
+    /// * `vault_address` - Address of DeFindex vault to deposit into
+    /// * `min_amount_out` - Minimum total output across all legs
+    /// * `routes` - Sub-routes whose `weight_bps` sum to 10_000
+    /// * `max_price_impact_bps` - Optional cap on the weighted price impact (defaults to the admin cap)
+    /// * `receiver` - Optional address credited with the vault shares (defaults to `user`)
+    pub fn zap_and_deposit_split(
+        env: Env,
+        user: Address,
+        from_token: Address,
+        amount_in: i128,
+        to_token: Address,
+        vault_address: Address,
+        min_amount_out: i128,
+        routes: Vec<SubRoute>,
+        max_price_impact_bps: Option<u32>,
+        receiver: Option<Address>,
+    ) -> ZapResult {
+        user.require_auth();
+        storage::extend_instance(&env);
+
+        if amount_in <= 0 {
+            panic_with_error!(&env, ZapError::InvalidAmount);
+        }
+        if from_token == to_token {
+            panic_with_error!(&env, ZapError::InvalidRoute);
+        }
+
+        storage::lock(&env);
+
+        native::check_headroom(&env, &user, &from_token, amount_in);
+        Self::take(&env, &from_token, &user, amount_in, false);
+
+        let receiver = receiver.unwrap_or_else(|| user.clone());
+        let ctx = events::begin(&env, &user, &vault_address);
+        let result = Self::execute_zap(
+            &env,
+            &ctx,
+            receiver,
+            from_token,
+            amount_in,
+            to_token,
+            min_amount_out,
+            routes,
+            max_price_impact_bps,
+        );
+
+        storage::unlock(&env);
+        result
+    }
+
+    /// Zap out: redeem vault shares and swap the vault asset into `to_token`
+    ///
+    /// Pass the native XLM contract (`ContractAddresses::xlm`) as `to_token` to
+    /// receive native XLM straight into a classic account balance.
+    ///
+    /// # Arguments
+    /// * `user` - Address redeeming the shares and receiving the output
+    /// * `vault_address` - Address of DeFindex vault whose shares are redeemed
+    /// * `shares` - Amount of vault shares to redeem
+    /// * `vault_asset` - Asset the vault pays out on withdrawal
+    /// * `to_token` - Address of the token to receive
+    /// * `min_amount_out` - Minimum amount of `to_token` sent to the user
+    /// * `swap_path` - Optional custom swap path (if empty, will use optimal routing)
+    /// * `distribution` - Optional custom distribution across DEXs
+    /// * `max_price_impact_bps` - Optional price impact cap (defaults to the admin cap)
+    pub fn zap_out(
+        env: Env,
+        user: Address,
+        vault_address: Address,
+        shares: i128,
+        vault_asset: Address,
+        to_token: Address,
+        min_amount_out: i128,
+        swap_path: Vec<Address>,
+        distribution: Vec<u32>,
+        max_price_impact_bps: Option<u32>,
+    ) -> ZapOutResult {
+        user.require_auth();
+        storage::extend_instance(&env);
+
+        Self::execute_zap_out(
+            &env,
+            user,
+            vault_address,
+            shares,
+            vault_asset,
+            to_token,
+            min_amount_out,
+            swap_path,
+            distribution,
+            max_price_impact_bps,
+            false,
+        )
+    }
+
+    /// Zap into a vault and receive a transferable position NFT instead of the shares
+    ///
+    /// The shares are deposited with the position NFT contract, which holds them in
+    /// custody for the token. Requires NFT mode (`set_position_nft`).
+    ///
+    /// # Arguments
+    /// Same as `zap_and_deposit`; the NFT is minted to `user`.
+    pub fn zap_to_nft(
+        env: Env,
+        user: Address,
+        from_token: Address,
+        amount_in: i128,
+        to_token: Address,
+        vault_address: Address,
+        min_amount_out: i128,
+        swap_path: Vec<Address>,
+        distribution: Vec<u32>,
+        max_price_impact_bps: Option<u32>,
+    ) -> NftZapResult {
+        user.require_auth();
+        storage::extend_instance(&env);
+
+        if amount_in <= 0 {
+            panic_with_error!(&env, ZapError::InvalidAmount);
+        }
+        let nft = nft::require_contract(&env);
+
+        storage::lock(&env);
+
+        native::check_headroom(&env, &user, &from_token, amount_in);
+        Self::take(&env, &from_token, &user, amount_in, false);
+
+        let route = Self::single_route(swap_path, distribution);
+        let ctx = events::begin(&env, &user, &vault_address);
+        let zap = Self::execute_zap(
+            &env,
+            &ctx,
+            nft.clone(),
+            from_token,
+            amount_in,
+            to_token.clone(),
+            min_amount_out,
+            vec![&env, route],
+            max_price_impact_bps,
+        );
+        let token_id = nft::mint(&env, &nft, &user, &to_token, &zap);
+
+        storage::unlock(&env);
+        NftZapResult { token_id, zap }
+    }
+
+    /// Redeem a position NFT: burn it, withdraw its shares and swap into `to_token`
+    ///
+    /// # Arguments
+    /// * `owner` - Current owner of the token, receiving the output
+    /// * `token_id` - Position NFT to redeem
+    /// * `to_token` - Address of the token to receive
+    /// * `min_amount_out` - Minimum amount of `to_token` sent to the owner
+    /// * `swap_path` - Optional custom swap path (if empty, will use optimal routing)
+    /// * `distribution` - Optional custom distribution across DEXs
+    /// * `max_price_impact_bps` - Optional price impact cap (defaults to the admin cap)
+    pub fn redeem_nft(
+        env: Env,
+        owner: Address,
+        token_id: u32,
+        to_token: Address,
+        min_amount_out: i128,
+        swap_path: Vec<Address>,
+        distribution: Vec<u32>,
+        max_price_impact_bps: Option<u32>,
+    ) -> ZapOutResult {
+        owner.require_auth();
+        storage::extend_instance(&env);
+
+        let nft = nft::require_contract(&env);
+        trustline::require(&env, &to_token, &owner);
+
+        storage::lock(&env);
+
+        let receipt = nft::burn(&env, &nft, &owner, token_id);
+        let ctx = events::begin(&env, &owner, &receipt.vault);
+        let assets_withdrawn = Self::withdraw_from_defindex_vault(
+            &env,
+            &ctx,
+            receipt.vault.clone(),
+            receipt.shares,
+            env.current_contract_address(),
+        );
+        positions::record_withdrawal(&env, &nft, &receipt.vault, assets_withdrawn, receipt.shares);
+        let result = Self::swap_out(
+            &env,
+            &ctx,
+            receipt.shares,
+            assets_withdrawn,
+            receipt.asset,
+            to_token,
+            min_amount_out,
+            Self::single_route(swap_path, distribution),
+            max_price_impact_bps,
+        );
+
+        storage::unlock(&env);
+        result
+    }
+
+    /// Zap into whichever available vault for `asset` earns the most
+    ///
+    /// Candidates are the `get_available_vaults(asset)` list, filtered by the
+    /// constraints and ranked by `get_vault_apy` over `constraints.apy_window`.
+    /// Vaults without enough snapshots are skipped.
+    ///
+    /// # Arguments
+    /// * `user` - Address of the user performing the zap, credited with the shares
+    /// * `from_token` - Address of input token to swap from
+    /// * `amount_in` - Amount of input tokens to swap
+    /// * `asset` - Vault asset to swap into, using optimal routing
+    /// * `constraints` - Allowed vaults, TVL minimum, fee cap and swap limits
+    pub fn zap_to_best_vault(
+        env: Env,
+        user: Address,
+        from_token: Address,
+        amount_in: i128,
+        asset: Address,
+        constraints: VaultConstraints,
+    ) -> BestVaultResult {
+        user.require_auth();
+        storage::extend_instance(&env);
+
+        if amount_in <= 0 {
+            panic_with_error!(&env, ZapError::InvalidAmount);
+        }
+        let candidates = Self::get_available_vaults(env.clone(), asset.clone());
+        let best = performance::best_vault(&env, &candidates, &constraints)
+            .unwrap_or_else(|| panic_with_error!(&env, ZapError::NoEligibleVault));
+
+        storage::lock(&env);
+
+        native::check_headroom(&env, &user, &from_token, amount_in);
+        Self::take(&env, &from_token, &user, amount_in, false);
+
+        let ctx = events::begin(&env, &user, &best.vault);
+        let zap = Self::execute_zap(
+            &env,
+            &ctx,
+            user.clone(),
+            from_token,
+            amount_in,
+            asset,
+            constraints.min_amount_out,
+            vec![&env, Self::single_route(Vec::new(&env), Vec::new(&env))],
+            constraints.max_price_impact_bps,
+        );
+
+        events::best_vault_chosen(&env, &ctx, &best);
+
+        storage::unlock(&env);
+        BestVaultResult { best, zap }
+    }
+
+    /// Preview the vault `zap_to_best_vault` would pick for `asset`, if any
+    pub fn get_best_vault(env: Env, asset: Address, constraints: VaultConstraints) -> Option<BestVault> {
+        let candidates = Self::get_available_vaults(env.clone(), asset);
+        performance::best_vault(&env, &candidates, &constraints)
+    }
+
+    /// Move a position between vaults: redeem shares, swap the asset and deposit into `to_vault`
+    ///
+    /// The swap uses optimal routing; `min_amount_out` applies to the swapped `to_asset`.
+    ///
+    /// # Arguments
+    /// * `user` - Owner of the shares, credited with the new vault shares
+    /// * `from_vault` - Vault whose shares are redeemed
+    /// * `shares` - Amount of `from_vault` shares to redeem
+    /// * `from_asset` - Asset `from_vault` pays out on withdrawal
+    /// * `to_vault` - Vault to deposit into
+    /// * `to_asset` - Asset accepted by `to_vault`
+    /// * `min_amount_out` - Minimum amount of `to_asset` deposited
+    /// * `max_price_impact_bps` - Optional price impact cap (defaults to the admin cap)
+    pub fn migrate(
+        env: Env,
+        user: Address,
+        from_vault: Address,
+        shares: i128,
+        from_asset: Address,
+        to_vault: Address,
+        to_asset: Address,
+        min_amount_out: i128,
+        max_price_impact_bps: Option<u32>,
+    ) -> ZapResult {
+        user.require_auth();
+        storage::extend_instance(&env);
+
+        Self::execute_migrate(
+            &env,
+            user,
+            from_vault,
+            shares,
+            from_asset,
+            to_vault,
+            to_asset,
+            min_amount_out,
+            max_price_impact_bps,
+            false,
+        )
+    }
+
+    /// Register `operator` to zap, zap out and migrate for `user` within `policy`
+    ///
+    /// Replaces any previous policy for the pair and resets its spending. Token
+    /// movements go through allowances, so the user must also `approve` this
+    /// contract on the tokens and vault shares the operator may move.
+    pub fn set_operator(env: Env, user: Address, operator: Address, policy: OperatorPolicy) {
+        user.require_auth();
+        operators::set_policy(&env, &user, &operator, &policy);
+        storage::extend_instance(&env);
+
+        events::operator_set(&env, &user, &operator, &policy);
+    }
+
+    /// Remove `operator`'s permission to act for `user`
+    pub fn revoke_operator(env: Env, user: Address, operator: Address) {
+        user.require_auth();
+        operators::remove_policy(&env, &user, &operator);
+
+        events::operator_revoked(&env, &user, &operator);
+    }
+
+    /// Get the policy under which `operator` acts for `user`, if any
+    pub fn get_operator(env: Env, user: Address, operator: Address) -> Option<OperatorPolicy> {
+        operators::get_policy(&env, &user, &operator)
+    }
+
+    /// `zap_and_deposit` signed by an operator for `user`, pulling funds from the user's allowance
+    ///
+    /// The vault and both tokens must be allowed by the user's policy and
+    /// `amount_in` counts against its per-period limit for `from_token`.
+    /// `min_amount_out` is raised to the swap's TWAP value less the policy's
+    /// slippage. Shares go to `user`.
+    pub fn operator_zap(
+        env: Env,
+        operator: Address,
+        user: Address,
+        from_token: Address,
+        amount_in: i128,
+        to_token: Address,
+        vault_address: Address,
+        min_amount_out: i128,
+        swap_path: Vec<Address>,
+        distribution: Vec<u32>,
+        max_price_impact_bps: Option<u32>,
+    ) -> ZapResult {
+        operator.require_auth();
+        storage::extend_instance(&env);
+
+        if amount_in <= 0 {
+            panic_with_error!(&env, ZapError::InvalidAmount);
+        }
+        let policy = operators::consume(
+            &env,
+            &user,
+            &operator,
+            &[&vault_address],
+            &[&from_token, &to_token],
+            &from_token,
+            amount_in,
+        );
+        let min_amount_out =
+            Self::operator_min_out(&env, &policy, &from_token, &to_token, amount_in, min_amount_out);
+
+        storage::lock(&env);
+
+        native::check_headroom(&env, &user, &from_token, amount_in);
+        Self::take(&env, &from_token, &user, amount_in, true);
+
+        let ctx = events::begin(&env, &user, &vault_address);
+        let result = Self::execute_zap(
+            &env,
+            &ctx,
+            user,
+            from_token,
+            amount_in,
+            to_token,
+            min_amount_out,
+            vec![&env, Self::single_route(swap_path, distribution)],
+            max_price_impact_bps,
+        );
+
+        storage::unlock(&env);
+        result
+    }
+
+    /// `zap_out` signed by an operator for `user`, pulling shares from the user's allowance
+    ///
+    /// The vault and both tokens must be allowed by the user's policy and
+    /// `shares` counts against its per-period limit for the vault's shares.
+    /// `min_amount_out` is raised to the TWAP value of the assets the shares
+    /// preview to, less the policy's slippage. The output goes to `user` and the
+    /// admin price impact cap applies.
+    pub fn operator_zap_out(
+        env: Env,
+        operator: Address,
+        user: Address,
+        vault_address: Address,
+        shares: i128,
+        vault_asset: Address,
+        to_token: Address,
+        min_amount_out: i128,
+        swap_path: Vec<Address>,
+        distribution: Vec<u32>,
+    ) -> ZapOutResult {
+        operator.require_auth();
+        storage::extend_instance(&env);
+
+        let policy = operators::consume(
+            &env,
+            &user,
+            &operator,
+            &[&vault_address],
+            &[&vault_asset, &to_token],
+            &vault_address,
+            shares,
+        );
+        let assets = Self::preview_withdraw(&env, &vault_address, shares);
+        let min_amount_out =
+            Self::operator_min_out(&env, &policy, &vault_asset, &to_token, assets, min_amount_out);
+
+        Self::execute_zap_out(
+            &env,
+            user,
+            vault_address,
+            shares,
+            vault_asset,
+            to_token,
+            min_amount_out,
+            swap_path,
+            distribution,
+            None,
+            true,
+        )
+    }
+
+    /// `migrate` signed by an operator for `user`, pulling shares from the user's allowance
+    ///
+    /// Both vaults and assets must be allowed by the user's policy and `shares`
+    /// counts against its per-period limit for `from_vault`'s shares.
+    /// `min_amount_out` is raised to the TWAP value of the assets the shares
+    /// preview to, less the policy's slippage.
+    pub fn operator_migrate(
+        env: Env,
+        operator: Address,
+        user: Address,
+        from_vault: Address,
+        shares: i128,
+        from_asset: Address,
+        to_vault: Address,
+        to_asset: Address,
+        min_amount_out: i128,
+        max_price_impact_bps: Option<u32>,
+    ) -> ZapResult {
+        operator.require_auth();
+        storage::extend_instance(&env);
+
+        let policy = operators::consume(
+            &env,
+            &user,
+            &operator,
+            &[&from_vault, &to_vault],
+            &[&from_asset, &to_asset],
+            &from_vault,
+            shares,
+        );
+        let assets = Self::preview_withdraw(&env, &from_vault, shares);
+        let min_amount_out =
+            Self::operator_min_out(&env, &policy, &from_asset, &to_asset, assets, min_amount_out);
+
+        Self::execute_migrate(
+            &env,
+            user,
+            from_vault,
+            shares,
+            from_asset,
+            to_vault,
+            to_asset,
+            min_amount_out,
+            max_price_impact_bps,
+            true,
+        )
+    }
+
+    /// Rebalance a user's shares across vaults toward target weights in one transaction
+    ///
+    /// Positions are valued through the vaults' `preview_withdraw`, converted into the
+    /// first target's asset. Overweight vaults are redeemed and the proceeds swapped
+    /// and deposited into the underweight ones in proportion to their shortfall.
+    /// Deviations below the minimum trade size (`set_min_rebalance`) are left alone.
+    ///
+    /// # Arguments
+    /// * `user` - Owner of the shares
+    /// * `targets` - (vault, weight in bps) pairs, weights summing to 10_000
+    /// * `max_slippage_bps` - Tolerance below the pair's TWAP value for every swap leg
+    pub fn rebalance(
+        env: Env,
+        user: Address,
+        targets: Vec<(Address, u32)>,
+        max_slippage_bps: u32,
+    ) -> RebalanceResult {
+        user.require_auth();
+        storage::extend_instance(&env);
+
+        Self::execute_rebalance(&env, user, targets, max_slippage_bps, None)
+    }
+
+    /// Rebalance a user's shares as their operator
+    ///
+    /// Every target vault and asset must be allowed by the user's policy, the
+    /// shares redeemed count against its per-period limit for each vault's shares
+    /// and `max_slippage_bps` may not exceed the policy's.
+    pub fn operator_rebalance(
+        env: Env,
+        operator: Address,
+        user: Address,
+        targets: Vec<(Address, u32)>,
+        max_slippage_bps: u32,
+    ) -> RebalanceResult {
+        operator.require_auth();
+        storage::extend_instance(&env);
+
+        Self::execute_rebalance(&env, user, targets, max_slippage_bps, Some(operator))
+    }
+
+    /// Create a dollar-cost-averaging plan funded by an escrowed budget
+    ///
+    /// `budget` of `from_token` moves into the Zap contract now; every `interval`
+    /// seconds a keeper may zap the next `amount_per_interval` slice into `vault`.
+    /// The first slice is executable immediately. Returns the plan id.
+    ///
+    /// # Arguments
+    /// * `user` - Owner of the plan, credited with the vault shares
+    /// * `from_token` - Token the budget is held in
+    /// * `budget` - Total amount escrowed for the plan
+    /// * `amount_per_interval` - Amount zapped per execution (the last slice may be smaller)
+    /// * `interval` - Seconds between executions
+    /// * `to_token` - Address of the vault asset
+    /// * `vault_address` - Address of DeFindex vault to deposit into
+    /// * `slippage_bps` - Tolerance below the TWAP value at execution time
+    pub fn create_dca(
+        env: Env,
+        user: Address,
+        from_token: Address,
+        budget: i128,
+        amount_per_interval: i128,
+        interval: u64,
+        to_token: Address,
+        vault_address: Address,
+        slippage_bps: u32,
+    ) -> u64 {
+        user.require_auth();
+        storage::extend_instance(&env);
+
+        if budget <= 0 || amount_per_interval <= 0 || amount_per_interval > budget {
+            panic_with_error!(&env, ZapError::InvalidAmount);
+        }
+        if interval == 0 || slippage_bps as i128 > math::BPS_DENOMINATOR {
+            panic_with_error!(&env, ZapError::InvalidConfig);
+        }
+
+        storage::lock(&env);
+
+        native::check_headroom(&env, &user, &from_token, budget);
+        Self::take(&env, &from_token, &user, budget, false);
+
+        let plan = DcaPlan {
+            id: dca::next_id(&env),
+            owner: user,
+            from_token,
+            to_token,
+            vault: vault_address,
+            amount_per_interval,
+            interval,
+            slippage_bps,
+            remaining: budget,
+            next_execution: env.ledger().timestamp(),
+        };
+        dca::save_plan(&env, &plan);
+
+        events::dca_created(&env, &plan);
+
+        storage::unlock(&env);
+        plan.id
+    }
+
+    /// Zap the next slice of a DCA plan once its interval has elapsed (callable by any keeper)
+    ///
+    /// The keeper receives the keeper tip out of the slice. The minimum output is
+    /// the slice valued at the pair's TWAP guard less the plan's slippage, so the
+    /// pair must be guarded. Exhausted plans are removed.
+    pub fn execute_dca(env: Env, keeper: Address, plan_id: u64) -> ZapResult {
+        keeper.require_auth();
+        storage::extend_instance(&env);
+
+        let mut plan = dca::get_plan(&env, plan_id)
+            .unwrap_or_else(|| panic_with_error!(&env, ZapError::OrderNotFound));
+        let now = env.ledger().timestamp();
+        if now < plan.next_execution {
+            panic_with_error!(&env, ZapError::OrderNotReady);
+        }
+
+        storage::lock(&env);
+
+        let slice = plan.amount_per_interval.min(plan.remaining);
+        plan.remaining = math::sub(plan.remaining, slice).or_panic(&env);
+        plan.next_execution = now + plan.interval;
+        if plan.remaining == 0 {
+            dca::remove_plan(&env, &plan);
+        } else {
+            dca::save_plan(&env, &plan);
+        }
+
+        let tip = fees::pay_keeper_tip(&env, &plan.from_token, slice, &keeper);
+        let amount_in = math::sub(slice, tip).or_panic(&env);
+        let min_amount_out =
+            Self::twap_min_out(&env, &plan.from_token, &plan.to_token, amount_in, plan.slippage_bps);
+
+        let ctx = events::begin(&env, &plan.owner, &plan.vault);
+        let result = Self::execute_zap(
+            &env,
+            &ctx,
+            plan.owner.clone(),
+            plan.from_token.clone(),
+            amount_in,
+            plan.to_token.clone(),
+            min_amount_out,
+            vec![&env, Self::single_route(Vec::new(&env), Vec::new(&env))],
+            None,
+        );
+
+        events::dca_executed(&env, &ctx, &plan, &keeper, slice, tip);
+
+        storage::unlock(&env);
+        result
+    }
+
+    /// Cancel a DCA plan and refund its remaining budget to the owner
+    ///
+    /// Returns the amount refunded.
+    pub fn cancel_dca(env: Env, plan_id: u64) -> i128 {
+        let plan = dca::get_plan(&env, plan_id)
+            .unwrap_or_else(|| panic_with_error!(&env, ZapError::OrderNotFound));
+        plan.owner.require_auth();
+        storage::extend_instance(&env);
+
+        storage::lock(&env);
+
+        dca::remove_plan(&env, &plan);
+        TokenClient::new(&env, &plan.from_token).transfer(
+            &env.current_contract_address(),
+            &plan.owner,
+            &plan.remaining,
+        );
+
+        events::dca_cancelled(&env, &plan);
+
+        storage::unlock(&env);
+        plan.remaining
+    }
+
+    /// Get a DCA plan by id, if it is still active
+    pub fn get_dca_plan(env: Env, plan_id: u64) -> Option<DcaPlan> {
+        dca::get_plan(&env, plan_id)
+    }
+
+    /// List the active DCA plans owned by `user`
+    pub fn get_dca_plans(env: Env, user: Address) -> Vec<DcaPlan> {
+        let mut plans = Vec::new(&env);
+        for id in dca::plan_ids(&env, &user).iter() {
+            if let Some(plan) = dca::get_plan(&env, id) {
+                plans.push_back(plan);
+            }
+        }
+        plans
+    }
+
+    /// Escrow a zap that executes only once its swap yields at least `min_amount_out`
+    ///
+    /// E.g. "zap 1000 XLM into the USDC vault once it buys 150 USDC". Returns the order id.
+    ///
+    /// # Arguments
+    /// * `user` - Owner of the order, credited with the vault shares
+    /// * `from_token` - Address of input token to swap from
+    /// * `amount_in` - Amount escrowed and zapped on fill
+    /// * `to_token` - Address of the vault asset
+    /// * `vault_address` - Address of DeFindex vault to deposit into
+    /// * `min_amount_out` - Target output of the swap, after the protocol fee
+    /// * `expires_at` - Ledger timestamp after which the order can no longer fill
+    pub fn create_limit_zap(
+        env: Env,
+        user: Address,
+        from_token: Address,
+        amount_in: i128,
+        to_token: Address,
+        vault_address: Address,
+        min_amount_out: i128,
+        expires_at: u64,
+    ) -> u64 {
+        user.require_auth();
+        storage::extend_instance(&env);
+
+        if amount_in <= 0 || min_amount_out <= 0 {
+            panic_with_error!(&env, ZapError::InvalidAmount);
+        }
+        if from_token == to_token || expires_at <= env.ledger().timestamp() {
+            panic_with_error!(&env, ZapError::InvalidConfig);
+        }
+
+        storage::lock(&env);
+
+        native::check_headroom(&env, &user, &from_token, amount_in);
+        Self::take(&env, &from_token, &user, amount_in, false);
+
+        let order = LimitZap {
+            id: limit::next_id(&env),
+            owner: user,
+            from_token,
+            amount_in,
+            to_token,
+            vault: vault_address,
+            min_amount_out,
+            expires_at,
+        };
+        limit::save_order(&env, &order);
+
+        events::limit_zap_created(&env, &order);
+
+        storage::unlock(&env);
+        order.id
+    }
+
+    /// Fill a limit zap along `routes` (callable by anyone)
+    ///
+    /// Reverts, leaving the order open, unless the realised swap output meets the
+    /// order's target. `routes` are weighted sub-routes as in `zap_and_deposit_split`.
+    pub fn fill_limit_zap(env: Env, order_id: u64, routes: Vec<SubRoute>) -> ZapResult {
+        storage::extend_instance(&env);
+
+        let order = limit::get_order(&env, order_id)
+            .unwrap_or_else(|| panic_with_error!(&env, ZapError::OrderNotFound));
+        if env.ledger().timestamp() >= order.expires_at {
+            panic_with_error!(&env, ZapError::OrderExpired);
+        }
+
+        storage::lock(&env);
+
+        limit::remove_order(&env, &order);
+        let ctx = events::begin(&env, &order.owner, &order.vault);
+        let result = Self::execute_zap(
+            &env,
+            &ctx,
+            order.owner.clone(),
+            order.from_token.clone(),
+            order.amount_in,
+            order.to_token.clone(),
+            order.min_amount_out,
+            routes,
+            None,
+        );
+
+        events::limit_zap_filled(&env, &ctx, &order, result.amount_swapped, result.vault_shares);
+
+        storage::unlock(&env);
+        result
+    }
+
+    /// Cancel an open limit zap and return its escrow (owner only)
+    pub fn cancel_limit_zap(env: Env, order_id: u64) -> i128 {
+        let order = limit::get_order(&env, order_id)
+            .unwrap_or_else(|| panic_with_error!(&env, ZapError::OrderNotFound));
+        order.owner.require_auth();
+
+        Self::close_limit_zap(&env, order, "limit_zap_cancelled")
+    }
+
+    /// Return the escrow of an expired limit zap to its owner (callable by anyone)
+    pub fn refund_limit_zap(env: Env, order_id: u64) -> i128 {
+        let order = limit::get_order(&env, order_id)
+            .unwrap_or_else(|| panic_with_error!(&env, ZapError::OrderNotFound));
+        if env.ledger().timestamp() < order.expires_at {
+            panic_with_error!(&env, ZapError::OrderNotReady);
+        }
+
+        Self::close_limit_zap(&env, order, "limit_zap_refunded")
+    }
+
+    /// Get an open limit zap by id
+    pub fn get_limit_zap(env: Env, order_id: u64) -> Option<LimitZap> {
+        limit::get_order(&env, order_id)
+    }
+
+    /// List the open limit zaps owned by `user`
+    pub fn get_limit_zaps(env: Env, user: Address) -> Vec<LimitZap> {
+        let mut orders = Vec::new(&env);
+        for id in limit::order_ids(&env, &user).iter() {
+            if let Some(order) = limit::get_order(&env, id) {
+                orders.push_back(order);
+            }
+        }
+        orders
+    }
+
+    fn close_limit_zap(env: &Env, order: LimitZap, event: &str) -> i128 {
+        storage::extend_instance(env);
+        storage::lock(env);
+
+        limit::remove_order(env, &order);
+        TokenClient::new(env, &order.from_token).transfer(
+            &env.current_contract_address(),
+            &order.owner,
+            &order.amount_in,
+        );
+
+        events::limit_zap_closed(env, event, &order);
+
+        storage::unlock(env);
+        order.amount_in
+    }
+
+    /// Escrow vault shares in a stop-loss or take-profit order
+    ///
+    /// The order triggers once the shares' value in `to_token` (vault
+    /// `preview_withdraw` valued at the pair's TWAP) is at or `Below` / `Above` `threshold`,
+    /// after which any keeper can zap the shares out into `to_token` for the owner.
+    /// Returns the order id.
+    ///
+    /// # Arguments
+    /// * `user` - Owner of the shares, receives the output
+    /// * `vault_address` - Vault whose shares are escrowed
+    /// * `shares` - Amount of vault shares to escrow
+    /// * `vault_asset` - Asset the vault pays out on withdrawal
+    /// * `to_token` - Quote token for the trigger and token received on exit
+    /// * `trigger` - `Below` for a stop-loss, `Above` for a take-profit
+    /// * `threshold` - Position value in `to_token` at which the order triggers
+    /// * `slippage_bps` - Tolerance below the TWAP value at execution time
+    pub fn create_exit_order(
+        env: Env,
+        user: Address,
+        vault_address: Address,
+        shares: i128,
+        vault_asset: Address,
+        to_token: Address,
+        trigger: ExitTrigger,
+        threshold: i128,
+        slippage_bps: u32,
+    ) -> u64 {
+        user.require_auth();
+        storage::extend_instance(&env);
+
+        if shares <= 0 || threshold <= 0 {
+            panic_with_error!(&env, ZapError::InvalidAmount);
+        }
+        if slippage_bps as i128 > math::BPS_DENOMINATOR {
+            panic_with_error!(&env, ZapError::InvalidConfig);
+        }
+        trustline::require(&env, &to_token, &user);
+
+        storage::lock(&env);
+
+        Self::take(&env, &vault_address, &user, shares, false);
+        let order = ExitOrder {
+            id: exits::next_id(&env),
+            owner: user,
+            vault: vault_address,
+            shares,
+            vault_asset,
+            to_token,
+            trigger,
+            threshold,
+            slippage_bps,
+        };
+        exits::save_order(&env, &order);
+
+        events::exit_order_created(&env, &order);
+
+        storage::unlock(&env);
+        order.id
+    }
+
+    /// Zap out a triggered exit order for its owner (callable by any keeper)
+    ///
+    /// The trigger and the minimum output both value the assets at the pair's TWAP
+    /// guard, so a keeper cannot trigger an order or lower its floor by moving the
+    /// pool around the call. The keeper receives the keeper tip out of the withdrawn
+    /// assets; the minimum output is their TWAP value less the order's slippage.
+    pub fn execute_exit_order(env: Env, keeper: Address, order_id: u64) -> ZapOutResult {
+        keeper.require_auth();
+        storage::extend_instance(&env);
+
+        let order = exits::get_order(&env, order_id)
+            .unwrap_or_else(|| panic_with_error!(&env, ZapError::OrderNotFound));
+        let value = Self::position_value(&env, &order);
+        if !order.is_triggered(value) {
+            panic_with_error!(&env, ZapError::OrderNotReady);
+        }
+
+        storage::lock(&env);
+
+        exits::remove_order(&env, &order);
+        let ctx = events::begin(&env, &order.owner, &order.vault);
+        let assets_withdrawn = Self::withdraw_from_defindex_vault(
+            &env,
+            &ctx,
+            order.vault.clone(),
+            order.shares,
+            env.current_contract_address(),
+        );
+        positions::record_withdrawal(&env, &order.owner, &order.vault, assets_withdrawn, order.shares);
+        let tip = fees::pay_keeper_tip(&env, &order.vault_asset, assets_withdrawn, &keeper);
+        let assets = math::sub(assets_withdrawn, tip).or_panic(&env);
+        let min_amount_out =
+            Self::twap_min_out(&env, &order.vault_asset, &order.to_token, assets, order.slippage_bps);
+
+        let result = Self::swap_out(
+            &env,
+            &ctx,
+            order.shares,
+            assets,
+            order.vault_asset.clone(),
+            order.to_token.clone(),
+            min_amount_out,
+            Self::single_route(Vec::new(&env), Vec::new(&env)),
+            None,
+        );
+
+        events::exit_order_executed(&env, &ctx, &order, &keeper, value, tip, result.amount_out);
+
+        storage::unlock(&env);
+        result
+    }
+
+    /// Cancel an exit order and return the escrowed shares (owner only)
+    pub fn cancel_exit_order(env: Env, order_id: u64) -> i128 {
+        let order = exits::get_order(&env, order_id)
+            .unwrap_or_else(|| panic_with_error!(&env, ZapError::OrderNotFound));
+        order.owner.require_auth();
+        storage::extend_instance(&env);
+
+        storage::lock(&env);
+
+        exits::remove_order(&env, &order);
+        TokenClient::new(&env, &order.vault).transfer(
+            &env.current_contract_address(),
+            &order.owner,
+            &order.shares,
+        );
+
+        events::exit_order_cancelled(&env, &order);
+
+        storage::unlock(&env);
+        order.shares
+    }
+
+    /// Current value in `to_token` of the shares escrowed by an exit order, at the pair's TWAP
+    pub fn get_exit_order_value(env: Env, order_id: u64) -> i128 {
+        let order = exits::get_order(&env, order_id)
+            .unwrap_or_else(|| panic_with_error!(&env, ZapError::OrderNotFound));
+        Self::position_value(&env, &order)
+    }
+
+    /// Get an open exit order by id
+    pub fn get_exit_order(env: Env, order_id: u64) -> Option<ExitOrder> {
+        exits::get_order(&env, order_id)
+    }
+
+    /// List the open exit orders owned by `user`
+    pub fn get_exit_orders(env: Env, user: Address) -> Vec<ExitOrder> {
+        let mut orders = Vec::new(&env);
+        for id in exits::order_ids(&env, &user).iter() {
+            if let Some(order) = exits::get_order(&env, id) {
+                orders.push_back(order);
+            }
+        }
+        orders
+    }
+
+    /// Value in `to_token` of the order's shares: vault `preview_withdraw` plus a swap quote
+    fn position_value(env: &Env, order: &ExitOrder) -> i128 {
+        let assets = Self::preview_withdraw(env, &order.vault, order.shares);
+        if assets <= 0 || order.vault_asset == order.to_token {
+            return assets;
+        }
+
+        twap::value(env, &order.vault_asset, &order.to_token, assets)
+    }
+
+    /// Harvest a registered vault's rewards and compound them (callable by any keeper)
+    ///
+    /// Calls the registered harvester's `harvest(to)` so rewards land in this
+    /// contract, swaps each reward token into the vault asset with a TWAP floor,
+    /// pays the keeper the harvest fee and deposits the rest into the vault for the
+    /// configured beneficiary, whose position is credited with the minted shares.
+    /// Each vault can be harvested at most once per `min_interval`.
+    pub fn harvest_and_compound(env: Env, keeper: Address, vault_address: Address) -> HarvestResult {
+        keeper.require_auth();
+        storage::extend_instance(&env);
+
+        let config = harvest::get_config(&env, &vault_address)
+            .unwrap_or_else(|| panic_with_error!(&env, ZapError::InvalidConfig));
+        harvest::check_and_record(&env, &vault_address, config.min_interval);
+
+        storage::lock(&env);
+
+        // Measure what the harvest pays so escrowed funds are never swept
+        let this = env.current_contract_address();
+        let mut balances_before = Vec::new(&env);
+        for token in config.reward_tokens.iter() {
+            balances_before.push_back(TokenClient::new(&env, &token).balance(&this));
+        }
+        env.invoke_contract::<()>(
+            &config.harvester,
+            &Symbol::new(&env, "harvest"),
+            vec![&env, this.into_val(&env)],
+        );
+
+        let ctx = events::begin(&env, &config.beneficiary, &vault_address);
+        let mut total = 0i128;
+        for (token, before) in config.reward_tokens.iter().zip(balances_before.iter()) {
+            let reward = math::sub(TokenClient::new(&env, &token).balance(&this), before).or_panic(&env);
+            if reward <= 0 {
+                continue;
+            }
+
+            let amount = if token == config.asset {
+                reward
+            } else {
+                // Harvest swaps pay no protocol fee, so the floor is the plain TWAP value
+                let min_amount_out =
+                    math::sub_bps(twap::value(&env, &token, &config.asset, reward), DEFAULT_SLIPPAGE_BPS)
+                        .or_panic(&env);
+                let max_impact = impact::get_default_cap(&env);
+                Self::swap_via_soroswap(
+                    &env,
+                    &ctx,
+                    token,
+                    config.asset.clone(),
+                    reward,
+                    min_amount_out,
+                    vec![&env, Self::single_route(Vec::new(&env), Vec::new(&env))],
+                    max_impact,
+                )
+                .0
+            };
+            total = math::add(total, amount).or_panic(&env);
+        }
+
+        let keeper_fee =
+            fees::pay_keeper(&env, &config.asset, total, fees::get_harvest_fee_bps(&env), &keeper);
+        let compounded = math::sub(total, keeper_fee).or_panic(&env);
+        let mut shares = 0;
+        if compounded > 0 {
+            TokenClient::new(&env, &config.asset).approve(
+                &this,
+                &vault_address,
+                &compounded,
+                &(env.ledger().sequence() + 1000),
+            );
+            shares = Self::deposit_to_defindex_vault(
+                &env,
+                &ctx,
+                vault_address.clone(),
+                config.asset.clone(),
+                compounded,
+                config.beneficiary.clone(),
+            );
+            positions::record_deposit(&env, &config.beneficiary, &vault_address, compounded, shares);
+        }
+
+        let result = HarvestResult { compounded, shares, keeper_fee };
+        events::harvested(&env, &ctx, &keeper, &result);
+
+        storage::unlock(&env);
+        result
+    }
+
+    /// Minimum output for keeper-executed zaps of `amount_in`: its value after fees at the
+    /// pair's TWAP less `slippage_bps`
+    ///
+    /// Unlike a quote taken in the same transaction, the keeper cannot move this floor by
+    /// sandwiching the zap.
+    fn twap_min_out(
+        env: &Env,
+        from_token: &Address,
+        to_token: &Address,
+        amount_in: i128,
+        slippage_bps: u32,
+    ) -> i128 {
+        let amount_to_swap = math::sub(amount_in, fees::fee_for(env, amount_in)).or_panic(env);
+        let expected = if from_token == to_token {
+            amount_to_swap
+        } else {
+            twap::value(env, from_token, to_token, amount_to_swap)
+        };
+        math::sub_bps(expected, slippage_bps).or_panic(env)
+    }
+
+    /// An operator's `min_amount_out` for swapping `amount_in`, raised to its TWAP value
+    /// less the policy's slippage so the operator cannot accept a worse price
+    fn operator_min_out(
+        env: &Env,
+        policy: &OperatorPolicy,
+        from_token: &Address,
+        to_token: &Address,
+        amount_in: i128,
+        min_amount_out: i128,
+    ) -> i128 {
+        let floor = Self::twap_min_out(env, from_token, to_token, amount_in, policy.max_slippage_bps);
+        min_amount_out.max(floor)
+    }
+
+    /// Assets `shares` of `vault` redeem for, per the vault's `preview_withdraw`
+    fn preview_withdraw(env: &Env, vault: &Address, shares: i128) -> i128 {
+        env.invoke_contract(
+            vault,
+            &Symbol::new(env, "preview_withdraw"),
+            vec![env, shares.into_val(env)],
+        )
+    }
+
+    /// Pull `amount` of `token` from `from` into this contract
+    ///
+    /// Delegated pulls spend the allowance `from` granted this contract instead
+    /// of requiring `from`'s signature.
+    fn take(env: &Env, token: &Address, from: &Address, amount: i128, delegated: bool) {
+        let token_client = TokenClient::new(env, token);
+        let this = env.current_contract_address();
+        if delegated {
+            token_client.transfer_from(&this, from, &this, &amount);
+        } else {
+            token_client.transfer(from, &this, &amount);
+        }
+    }
+
+    /// Route the whole amount through one path, empty for optimal routing
+    fn single_route(path: Vec<Address>, distribution: Vec<u32>) -> SubRoute {
+        SubRoute {
+            weight_bps: math::BPS_DENOMINATOR as u32,
+            path,
+            distribution,
+            venue: SwapVenue::Aggregator,
+        }
+    }
+
+    /// Take custody of `user`'s vault shares and redeem them, returning the assets received
+    fn redeem(env: &Env, ctx: &ZapContext, shares: i128, delegated: bool) -> i128 {
+        // Vault shares are a token; take custody before redeeming them
+        Self::take(env, &ctx.vault, &ctx.user, shares, delegated);
+        Self::withdraw_from_defindex_vault(
+            env,
+            ctx,
+            ctx.vault.clone(),
+            shares,
+            env.current_contract_address(),
+        )
+    }
+
+    fn execute_zap_out(
+        env: &Env,
+        user: Address,
+        vault_address: Address,
+        shares: i128,
+        vault_asset: Address,
+        to_token: Address,
+        min_amount_out: i128,
+        swap_path: Vec<Address>,
+        distribution: Vec<u32>,
+        max_price_impact_bps: Option<u32>,
+        delegated: bool,
+    ) -> ZapOutResult {
+        if shares <= 0 {
+            panic_with_error!(env, ZapError::InvalidAmount);
+        }
+
+        // Classic accounts need a trustline for the output, check before redeeming anything
+        trustline::require(env, &to_token, &user);
+
+        storage::lock(env);
+
+        let ctx = events::begin(env, &user, &vault_address);
+        let assets_withdrawn = Self::redeem(env, &ctx, shares, delegated);
+        positions::record_withdrawal(env, &user, &vault_address, assets_withdrawn, shares);
+        let result = Self::swap_out(
+            env,
+            &ctx,
+            shares,
+            assets_withdrawn,
+            vault_asset,
+            to_token,
+            min_amount_out,
+            Self::single_route(swap_path, distribution),
+            max_price_impact_bps,
+        );
+
+        storage::unlock(env);
+        result
+    }
+
+    /// Swap `assets_withdrawn` of `vault_asset` held by this contract into `to_token` for `ctx.user`
+    fn swap_out(
+        env: &Env,
+        ctx: &ZapContext,
+        shares: i128,
+        assets_withdrawn: i128,
+        vault_asset: Address,
+        to_token: Address,
+        min_amount_out: i128,
+        route: SubRoute,
+        max_price_impact_bps: Option<u32>,
+    ) -> ZapOutResult {
+        let protocol_fee = fees::collect(env, &vault_asset, assets_withdrawn);
+        let amount_to_swap = math::sub(assets_withdrawn, protocol_fee).or_panic(env);
+
+        let (amount_out, price_impact_bps) = if vault_asset == to_token {
+            (amount_to_swap, 0)
+        } else {
+            let max_impact = max_price_impact_bps.unwrap_or_else(|| impact::get_default_cap(env));
+            Self::swap_via_soroswap(
+                env,
+                ctx,
+                vault_asset.clone(),
+                to_token.clone(),
+                amount_to_swap,
+                min_amount_out,
+                vec![env, route],
+                max_impact,
+            )
+        };
+
+        if amount_out < min_amount_out {
+            panic_with_error!(env, ZapError::InsufficientOutput);
+        }
+
+        TokenClient::new(env, &to_token).transfer(
+            &env.current_contract_address(),
+            &ctx.user,
+            &amount_out,
+        );
+
+        let result = ZapOutResult {
+            shares_burned: shares,
+            assets_withdrawn,
+            amount_out,
+            price_impact_bps,
+            protocol_fee,
+        };
+
+        let event = ZapOutCompletedEvent {
+            version: EVENT_VERSION,
+            zap_id: ctx.id,
+            vault_asset,
+            to_token,
+            shares_burned: shares,
+            assets_withdrawn,
+            amount_out,
+            price_impact_bps,
+            protocol_fee,
+        };
+        events::zap_out_completed(env, ctx, event);
+
+        result
+    }
+
+    fn execute_migrate(
+        env: &Env,
+        user: Address,
+        from_vault: Address,
+        shares: i128,
+        from_asset: Address,
+        to_vault: Address,
+        to_asset: Address,
+        min_amount_out: i128,
+        max_price_impact_bps: Option<u32>,
+        delegated: bool,
+    ) -> ZapResult {
+        if shares <= 0 {
+            panic_with_error!(env, ZapError::InvalidAmount);
+        }
+        if from_vault == to_vault {
+            panic_with_error!(env, ZapError::InvalidRoute);
+        }
+
+        storage::lock(env);
+
+        // The redeem and the deposit share one zap id
+        let ctx = events::begin(env, &user, &from_vault);
+        let assets = Self::redeem(env, &ctx, shares, delegated);
+        positions::record_withdrawal(env, &user, &from_vault, assets, shares);
+        let result = Self::execute_zap(
+            env,
+            &ctx.with_vault(&to_vault),
+            user.clone(),
+            from_asset,
+            assets,
+            to_asset,
+            min_amount_out,
+            vec![env, Self::single_route(Vec::new(env), Vec::new(env))],
+            max_price_impact_bps,
+        );
 
-use config::{ContractAddresses, VaultAddresses};
+        events::migrated(env, &ctx, &from_vault, shares, &to_vault, result.vault_shares);
 
-#[contracttype]
-#[derive(Clone, Debug, Eq, PartialEq)]
-pub struct SwapPath {
-    pub token_in: Address,
-    pub token_out: Address,
-    pub amount_in: i128,
-    pub amount_out_min: i128,
-    pub distribution: Vec<u32>, // Distribution percentages across multiple DEXs
-    pub path: Vec<Address>,     // Token addresses in swap path
-}
+        storage::unlock(env);
+        result
+    }
 
-#[contracttype]
-#[derive(Clone, Debug, Eq, PartialEq)]
-pub struct ZapResult {
-    pub amount_swapped: i128,
-    pub vault_shares: i128,
-    pub vault_address: Address,
-}
+    fn execute_rebalance(
+        env: &Env,
+        user: Address,
+        targets: Vec<(Address, u32)>,
+        max_slippage_bps: u32,
+        operator: Option<Address>,
+    ) -> RebalanceResult {
+        rebalance::validate_targets(env, &targets, max_slippage_bps);
 
-#[contract]
-pub struct Zap;
+        // Value every position in the first target's asset: (vault, asset, shares, value)
+        let numeraire_vault = targets.first_unchecked().0;
+        let numeraire = rebalance::vault_asset(env, &numeraire_vault);
+        let mut legs: Vec<(Address, Address, i128, i128)> = Vec::new(env);
+        let mut total_value: i128 = 0;
+        for (vault, _) in targets.iter() {
+            let asset = rebalance::vault_asset(env, &vault);
+            if let Some(operator) = &operator {
+                let policy = operators::consume(env, &user, operator, &[&vault], &[&asset], &vault, 0);
+                if max_slippage_bps > policy.max_slippage_bps {
+                    panic_with_error!(env, ZapError::OperatorSlippageExceeded);
+                }
+            }
 
-#[contractimpl]
-impl Zap {
-    /// Main zap function: swap tokens via Soroswap then deposit into DeFindex Vault
-    /// 
-    /// # Arguments
-    /// * `user` - Address of the user performing the zap
-    /// * `from_token` - Address of input token to swap from
-    /// * `amount_in` - Amount of input tokens to swap
-    /// * `to_token` - Address of output token to receive
-    /// * `vault_address` - Address of DeFindex vault to deposit into
-    /// * `min_amount_out` - Minimum amount of output tokens from swap
-    /// * `swap_path` - Optional custom swap path (if empty, will use optimal routing)
-    /// * `distribution` - Optional custom distribution across DEXs
-    /// 
-    /// # Returns
-    /// * ZapResult containing swap amount and vault shares received
-    pub fn zap_and_deposit(
-        env: Env,
-        user: Address,
+            let shares = TokenClient::new(env, &vault).balance(&user);
+            let value = if shares > 0 {
+                let assets = Self::preview_withdraw(env, &vault, shares);
+                Self::value_in(env, &asset, &numeraire, assets)
+            } else {
+                0
+            };
+            total_value = math::add(total_value, value).or_panic(env);
+            legs.push_back((vault, asset, shares, value));
+        }
+        if total_value <= 0 {
+            panic_with_error!(env, ZapError::InvalidAmount);
+        }
+
+        // Signed distance from each target, ignoring deviations below the minimum trade
+        let min_trade_bps = rebalance::get_min_trade_bps(env);
+        let min_trade = math::apply_bps(total_value, min_trade_bps, math::Rounding::Up)
+            .or_panic(env)
+            .max(1);
+        let mut deviations: Vec<i128> = Vec::new(env);
+        let mut total_deficit: i128 = 0;
+        for ((_, weight_bps), (_, _, _, value)) in targets.iter().zip(legs.iter()) {
+            let target = math::mul_div(
+                total_value,
+                weight_bps as i128,
+                math::BPS_DENOMINATOR,
+                math::Rounding::Down,
+            )
+            .or_panic(env);
+            let deviation = math::sub(value, target).or_panic(env);
+            if deviation.abs() < min_trade {
+                deviations.push_back(0);
+            } else {
+                deviations.push_back(deviation);
+                if deviation < 0 {
+                    total_deficit = math::sub(total_deficit, deviation).or_panic(env);
+                }
+            }
+        }
+
+        let mut result = RebalanceResult {
+            total_value,
+            withdrawals: Vec::new(env),
+            deposits: Vec::new(env),
+        };
+        if total_deficit == 0 || !deviations.iter().any(|deviation| deviation > 0) {
+            return result;
+        }
+
+        storage::lock(env);
+
+        // Every redeem and deposit of the rebalance shares one zap id
+        let ctx = events::begin(env, &user, &numeraire_vault);
+
+        // Redeem the excess of every overweight vault
+        let mut proceeds: Vec<(Address, i128)> = Vec::new(env);
+        for ((vault, asset, shares, value), excess) in legs.iter().zip(deviations.iter()) {
+            if excess <= 0 {
+                continue;
+            }
+            let redeemed = math::mul_div(shares, excess, value, math::Rounding::Down).or_panic(env);
+            if redeemed == 0 {
+                continue;
+            }
+            if let Some(operator) = &operator {
+                operators::consume(env, &user, operator, &[&vault], &[&asset], &vault, redeemed);
+            }
+
+            let assets = Self::redeem(env, &ctx.with_vault(&vault), redeemed, operator.is_some());
+            positions::record_withdrawal(env, &user, &vault, assets, redeemed);
+            proceeds.push_back((asset, assets));
+            result.withdrawals.push_back(RebalanceLeg {
+                vault,
+                shares: redeemed,
+            });
+        }
+
+        // Split each proceed across the underweight vaults by shortfall
+        let mut received: Vec<i128> = Vec::new(env);
+        for _ in deviations.iter() {
+            received.push_back(0);
+        }
+        for (from_asset, amount) in proceeds.iter() {
+            let mut remaining = amount;
+            let mut funded_deficit: i128 = 0;
+            let underweight = legs.iter().zip(deviations.iter()).enumerate();
+            for (i, ((vault, asset, _, _), deviation)) in underweight {
+                if deviation >= 0 {
+                    continue;
+                }
+                // The last underweight vault takes the rounding remainder
+                funded_deficit = math::sub(funded_deficit, deviation).or_panic(env);
+                let part = if funded_deficit == total_deficit {
+                    remaining
+                } else {
+                    math::mul_div(amount, -deviation, total_deficit, math::Rounding::Down).or_panic(env)
+                };
+                remaining = math::sub(remaining, part).or_panic(env);
+                if part == 0 {
+                    continue;
+                }
+
+                let min_amount_out =
+                    Self::twap_min_out(env, &from_asset, &asset, part, max_slippage_bps);
+                let zap = Self::execute_zap(
+                    env,
+                    &ctx.with_vault(&vault),
+                    user.clone(),
+                    from_asset.clone(),
+                    part,
+                    asset,
+                    min_amount_out,
+                    vec![env, Self::single_route(Vec::new(env), Vec::new(env))],
+                    None,
+                );
+                let i = i as u32;
+                let total = math::add(received.get_unchecked(i), zap.vault_shares).or_panic(env);
+                received.set(i, total);
+            }
+        }
+        for ((vault, _, _, _), shares) in legs.iter().zip(received.iter()) {
+            if shares > 0 {
+                result.deposits.push_back(RebalanceLeg { vault, shares });
+            }
+        }
+
+        events::rebalanced(env, &ctx, &result);
+
+        storage::unlock(env);
+        result
+    }
+
+    /// Expected value of `amount` of `asset` in `numeraire`
+    fn value_in(env: &Env, asset: &Address, numeraire: &Address, amount: i128) -> i128 {
+        if asset == numeraire || amount <= 0 {
+            return amount;
+        }
+        routing::plan(
+            env,
+            asset,
+            numeraire,
+            amount,
+            Vec::new(env),
+            Vec::new(env),
+            SwapVenue::Aggregator,
+        )
+        .expected_amount
+    }
+
+    /// Swap and deposit `amount_in` of `from_token` already held by this contract
+    ///
+    /// `user` paid for the zap, the vault shares are minted to `receiver`.
+    fn execute_zap(
+        env: &Env,
+        ctx: &ZapContext,
+        receiver: Address,
         from_token: Address,
         amount_in: i128,
         to_token: Address,
-        vault_address: Address,
         min_amount_out: i128,
-        swap_path: Vec<Address>,
-        distribution: Vec<u32>,
+        routes: Vec<SubRoute>,
+        max_price_impact_bps: Option<u32>,
     ) -> ZapResult {
-        user.require_auth();
-
-        // Validate inputs
-        if amount_in <= 0 {
-            panic!("Amount must be positive");
-        }
+        let vault_address = ctx.vault.clone();
 
-        // Step 1: Transfer input tokens from user to this contract
-        let token_client = TokenClient::new(&env, &from_token);
-        token_client.transfer(&user, &env.current_contract_address(), &amount_in);
+        // Take the protocol fee off the input before swapping
+        let protocol_fee = fees::collect(env, &from_token, amount_in);
+        let amount_to_swap = math::sub(amount_in, protocol_fee).or_panic(env);
 
         // Step 2: Perform swap via Soroswap Aggregator
-        let swapped_amount = if from_token == to_token {
+        let (swapped_amount, price_impact_bps) = if from_token == to_token {
             // No swap needed if tokens are the same
-            amount_in
+            (amount_to_swap, 0)
         } else {
+            let max_impact = max_price_impact_bps.unwrap_or_else(|| impact::get_default_cap(env));
             Self::swap_via_soroswap(
-                &env,
+                env,
+                ctx,
                 from_token.clone(),
                 to_token.clone(),
-                amount_in,
+                amount_to_swap,
                 min_amount_out,
-                swap_path,
-                distribution,
+                routes,
+                max_impact,
             )
         };
 
         // Ensure we received at least the minimum amount
         if swapped_amount < min_amount_out {
-            panic!("Insufficient output amount from swap");
+            panic_with_error!(env, ZapError::InsufficientOutput);
         }
 
         // Step 3: Approve vault to spend the swapped tokens
-        let to_token_client = TokenClient::new(&env, &to_token);
+        let to_token_client = TokenClient::new(env, &to_token);
         to_token_client.approve(
             &env.current_contract_address(),
             &vault_address,
@@ -102,116 +1735,97 @@ impl Zap {
 
         // Step 4: Deposit into DeFindex Vault
         let vault_shares = Self::deposit_to_defindex_vault(
-            &env,
+            env,
+            ctx,
             vault_address.clone(),
-            to_token,
+            to_token.clone(),
             swapped_amount,
-            user.clone(),
+            receiver.clone(),
         );
+        positions::record_deposit(env, &receiver, &vault_address, swapped_amount, vault_shares);
 
         let result = ZapResult {
             amount_swapped: swapped_amount,
             vault_shares,
             vault_address: vault_address.clone(),
+            price_impact_bps,
+            protocol_fee,
         };
 
-        env.events().publish(
-            (Symbol::new(&env, "zap_completed"),),
-            (user, from_token, amount_in, vault_address, result.clone()),
-        );
+        let event = ZapCompletedEvent {
+            version: EVENT_VERSION,
+            zap_id: ctx.id,
+            receiver,
+            from_token,
+            amount_in,
+            to_token,
+            amount_swapped: swapped_amount,
+            vault_shares,
+            price_impact_bps,
+            protocol_fee,
+        };
+        events::zap_completed(env, ctx, event);
 
         result
     }
 
-    /// Swap tokens using Soroswap Aggregator
+    /// Swap tokens using Soroswap Aggregator, splitting across sub-routes
+    ///
+    /// All legs are planned and checked against the price impact cap before any
+    /// of them executes. Returns the total amount received and the input-weighted
+    /// price impact in bps.
     fn swap_via_soroswap(
         env: &Env,
+        ctx: &ZapContext,
         token_in: Address,
         token_out: Address,
         amount_in: i128,
         min_amount_out: i128,
-        path: Vec<Address>,
-        distribution: Vec<u32>,
-    ) -> i128 {
-        let config = ContractAddresses::futurenet(env); // Use appropriate network
-        let soroswap_aggregator = config.soroswap_aggregator;
-
-        // Approve Soroswap to spend our tokens
-        let token_in_client = TokenClient::new(env, &token_in);
-        token_in_client.approve(
-            &env.current_contract_address(),
-            &soroswap_aggregator,
-            &amount_in,
-            &(env.ledger().sequence() + 100),
-        );
-
-        // Use provided path/distribution or get optimal route
-        let (swap_path, swap_distribution) = if path.is_empty() {
-            // Get optimal route from Soroswap
-            Self::get_optimal_route(env, &soroswap_aggregator, token_in.clone(), token_out.clone(), amount_in)
-        } else {
-            (path, distribution)
-        };
+        routes: Vec<SubRoute>,
+        max_price_impact_bps: u32,
+    ) -> (i128, u32) {
+        let amounts = routing::split_amounts(env, amount_in, &routes);
 
-        // Prepare swap parameters
-        let swap_params = vec![
-            env,
-            token_in.into_val(env),
-            token_out.into_val(env),
-            amount_in.into_val(env),
-            min_amount_out.into_val(env),
-            swap_path.into_val(env),
-            swap_distribution.into_val(env),
-        ];
-
-        // Call Soroswap Aggregator's swap function
-        let result: Val = env.invoke_contract(
-            &soroswap_aggregator,
-            &Symbol::new(env, "swap"),
-            swap_params,
-        );
+        let mut planned = Vec::new(env);
+        let mut price_impact = 0i128;
+        for (leg, amount) in routes.iter().zip(amounts.iter()) {
+            let route = routing::plan(
+                env, &token_in, &token_out, amount, leg.path, leg.distribution, leg.venue,
+            );
+            let leg_impact =
+                math::mul_div(amount, route.price_impact as i128, amount_in, math::Rounding::Up)
+                    .or_panic(env);
+            price_impact = math::add(price_impact, leg_impact).or_panic(env);
+            planned.push_back(route);
+        }
 
-        // Extract the amount received from swap
-        let amount_out: i128 = result.try_into_val(env).unwrap();
+        let price_impact_bps = price_impact as u32;
+        if price_impact_bps > max_price_impact_bps {
+            panic_with_error!(env, ZapError::PriceImpactTooHigh);
+        }
 
-        env.events().publish(
-            (Symbol::new(env, "soroswap_swap"),),
-            (token_in, token_out, amount_in, amount_out),
-        );
+        // A single route enforces the minimum inside the swap, split legs on the total
+        let leg_min_out = if planned.len() == 1 { min_amount_out } else { 0 };
 
-        amount_out
-    }
+        let mut amount_out = 0i128;
+        for (route, amount) in planned.iter().zip(amounts.iter()) {
+            // Refuse to trade through guarded pools whose spot price drifted from the TWAP
+            twap::check_route(env, &route.path);
 
-    /// Get optimal route from Soroswap
-    fn get_optimal_route(
-        env: &Env,
-        soroswap_aggregator: &Address,
-        token_in: Address,
-        token_out: Address,
-        amount_in: i128,
-    ) -> (Vec<Address>, Vec<u32>) {
-        let route_params = vec![
-            env,
-            token_in.into_val(env),
-            token_out.into_val(env),
-            amount_in.into_val(env),
-        ];
+            let leg_out =
+                routing::execute(env, &route, &token_in, &token_out, amount, leg_min_out);
+            amount_out = math::add(amount_out, leg_out).or_panic(env);
+        }
 
-        let result: Val = env.invoke_contract(
-            soroswap_aggregator,
-            &Symbol::new(env, "get_best_route"),
-            route_params,
-        );
+        events::swap(env, ctx, &token_in, &token_out, amount_in, amount_out, price_impact_bps);
 
-        // Parse result into path and distribution
-        // This would need to match Soroswap's actual return format
-        let (path, distribution): (Vec<Address>, Vec<u32>) = result.try_into_val(env).unwrap();
-        (path, distribution)
+        (amount_out, price_impact_bps)
     }
 
     /// Deposit tokens into DeFindex Vault
     fn deposit_to_defindex_vault(
         env: &Env,
+        ctx: &ZapContext,
         vault_address: Address,
         asset: Address,
         amount: i128,
@@ -235,14 +1849,33 @@ impl Zap {
         // Extract vault shares received
         let shares: i128 = result.try_into_val(env).unwrap();
 
-        env.events().publish(
-            (Symbol::new(env, "defindex_deposit"),),
-            (vault_address, asset, amount, receiver, shares),
-        );
+        events::deposit(env, ctx, &asset, amount, &receiver, shares);
 
         shares
     }
 
+    /// Redeem vault shares held by this contract, returning the assets received
+    fn withdraw_from_defindex_vault(
+        env: &Env,
+        ctx: &ZapContext,
+        vault_address: Address,
+        shares: i128,
+        receiver: Address,
+    ) -> i128 {
+        let withdraw_params = vec![env, shares.into_val(env), receiver.into_val(env)];
+
+        let result: Val = env.invoke_contract(
+            &vault_address,
+            &Symbol::new(env, "withdraw"),
+            withdraw_params,
+        );
+        let assets: i128 = result.try_into_val(env).unwrap();
+
+        events::withdraw(env, ctx, shares, assets);
+
+        assets
+    }
+
     /// Get quote from Soroswap for amount out estimation
     pub fn get_swap_quote(
         env: Env,
@@ -252,25 +1885,63 @@ impl Zap {
         path: Vec<Address>,
         distribution: Vec<u32>,
     ) -> i128 {
-        let config = ContractAddresses::futurenet(&env);
-        let soroswap_aggregator = config.soroswap_aggregator;
+        let config = storage::get_addresses(&env);
 
-        let quote_params = vec![
+        routing::quote(
             &env,
-            token_in.into_val(&env),
-            token_out.into_val(&env),
-            amount_in.into_val(&env),
-            path.into_val(&env),
-            distribution.into_val(&env),
-        ];
+            &config,
+            SwapVenue::Aggregator,
+            &token_in,
+            &token_out,
+            amount_in,
+            &path,
+            &distribution,
+        )
+    }
 
-        let result: Val = env.invoke_contract(
-            &soroswap_aggregator,
-            &Symbol::new(&env, "get_amounts_out"),
-            quote_params,
-        );
+    /// Whether `account` can receive `token` (classic accounts need an authorized trustline)
+    pub fn can_receive(env: Env, token: Address, account: Address) -> bool {
+        trustline::can_receive(&env, &token, &account)
+    }
 
-        result.try_into_val(&env).unwrap()
+    /// Get `user`'s recorded position in a vault, if any
+    pub fn get_position(env: Env, user: Address, vault_address: Address) -> Option<Position> {
+        positions::get_position(&env, &user, &vault_address)
+    }
+
+    /// Get all of `user`'s recorded vault positions
+    pub fn get_positions(env: Env, user: Address) -> Vec<Position> {
+        let mut result = Vec::new(&env);
+        for vault in positions::vaults(&env, &user).iter() {
+            if let Some(position) = positions::get_position(&env, &user, &vault) {
+                result.push_back(position);
+            }
+        }
+        result
+    }
+
+    /// Record a vault's total assets and shares (callable by anyone, e.g. keepers)
+    ///
+    /// Samples are kept in a per-vault ring buffer and spaced at least an hour apart.
+    pub fn snapshot_vault(env: Env, vault_address: Address) -> VaultSample {
+        storage::extend_instance(&env);
+        let sample = performance::snapshot(&env, &vault_address);
+
+        events::vault_snapshot(&env, &vault_address, &sample);
+        sample
+    }
+
+    /// Get a vault's recorded samples, oldest first
+    pub fn get_vault_samples(env: Env, vault_address: Address) -> Vec<VaultSample> {
+        performance::get_samples(&env, &vault_address)
+    }
+
+    /// Annualized share-price growth of a vault over `window` seconds, in bps
+    ///
+    /// Computed from recorded samples only, so any client gets the same ranking.
+    /// Negative when the share price fell; None until the samples span the window.
+    pub fn get_vault_apy(env: Env, vault_address: Address, window: u64) -> Option<i128> {
+        performance::apy_bps(&env, &vault_address, window)
     }
 
     /// Get vault information from DeFindex
@@ -288,21 +1959,106 @@ impl Zap {
 
     /// Preview how many vault shares would be received for a deposit
     pub fn preview_vault_deposit(env: Env, vault_address: Address, amount: i128) -> i128 {
-        let preview_params = vec![&env, amount.into_val(&env)];
+        Self::preview_deposit(&env, &vault_address, amount)
+    }
+
+    fn preview_deposit(env: &Env, vault_address: &Address, amount: i128) -> i128 {
+        let preview_params = vec![env, amount.into_val(env)];
 
         let result: Val = env.invoke_contract(
-            &vault_address,
-            &Symbol::new(&env, "preview_deposit"),
+            vault_address,
+            &Symbol::new(env, "preview_deposit"),
             preview_params,
         );
 
-        result.try_into_val(&env).unwrap()
+        result.try_into_val(env).unwrap()
+    }
+
+    /// Simulate a full zap: protocol fee, swap leg, price impact and vault shares
+    ///
+    /// Read-only; meant to be called through transaction simulation so clients can
+    /// show a complete preview and pick `min_amount_out` from the suggestions.
+    /// TWAP guards are not evaluated since they record observations.
+    ///
+    /// # Arguments
+    /// * `from_token` - Address of input token to swap from
+    /// * `amount_in` - Amount of input tokens, before the protocol fee
+    /// * `to_token` - Address of the vault asset
+    /// * `vault_address` - Address of DeFindex vault to deposit into
+    /// * `swap_path` - Optional custom swap path (if empty, will use optimal routing)
+    /// * `distribution` - Optional custom distribution across DEXs
+    pub fn quote_zap(
+        env: Env,
+        from_token: Address,
+        amount_in: i128,
+        to_token: Address,
+        vault_address: Address,
+        swap_path: Vec<Address>,
+        distribution: Vec<u32>,
+    ) -> ZapQuote {
+        if amount_in <= 0 {
+            panic_with_error!(&env, ZapError::InvalidAmount);
+        }
+
+        let protocol_fee = fees::fee_for(&env, amount_in);
+        let amount_to_swap = math::sub(amount_in, protocol_fee).or_panic(&env);
+
+        let mut route = if from_token == to_token {
+            SwapRoute {
+                path: Vec::new(&env),
+                pools: Vec::new(&env),
+                distribution: Vec::new(&env),
+                expected_amount: amount_to_swap,
+                minimum_amount: 0,
+                price_impact: 0,
+                venue: SwapVenue::Aggregator,
+            }
+        } else {
+            routing::plan(
+                &env,
+                &from_token,
+                &to_token,
+                amount_to_swap,
+                swap_path,
+                distribution,
+                SwapVenue::Aggregator,
+            )
+        };
+
+        let expected_vault_shares =
+            Self::preview_deposit(&env, &vault_address, route.expected_amount);
+
+        let mut min_out_suggestions = Vec::new(&env);
+        for slippage_bps in SUGGESTED_SLIPPAGE_BPS {
+            let min_amount_out = math::sub_bps(route.expected_amount, slippage_bps).or_panic(&env);
+            min_out_suggestions.push_back(MinOutSuggestion {
+                slippage_bps,
+                min_amount_out,
+                min_vault_shares: math::sub_bps(expected_vault_shares, slippage_bps).or_panic(&env),
+            });
+        }
+        route.minimum_amount =
+            math::sub_bps(route.expected_amount, DEFAULT_SLIPPAGE_BPS).or_panic(&env);
+
+        ZapQuote {
+            amount_in,
+            protocol_fee,
+            expected_amount_out: route.expected_amount,
+            price_impact_bps: route.price_impact,
+            expected_vault_shares,
+            route,
+            min_out_suggestions,
+        }
     }
 
     /// Get available vaults for a given asset
     pub fn get_available_vaults(env: Env, asset: Address) -> Vec<Address> {
-        let vaults = VaultAddresses::futurenet(&env);
-        let config = ContractAddresses::futurenet(&env);
+        if let Some(vaults) = storage::get_asset_vaults(&env, &asset) {
+            return vaults;
+        }
+
+        let vaults = storage::get_vaults(&env);
+        let config = storage::get_addresses(&env);
         
         // This is a simplified version - in practice, you'd query the DeFindex factory
         let mut available_vaults = Vec::new(&env);
@@ -324,6 +2080,8 @@ impl Zap {
     }
 
     /// Emergency function to withdraw stuck tokens (admin only)
+    ///
+    /// `admin` must be the stored admin, otherwise the call fails with `ZapError::Unauthorized`.
     pub fn emergency_withdraw(
         env: Env,
         admin: Address,
@@ -331,26 +2089,247 @@ impl Zap {
         amount: i128,
         to: Address,
     ) {
-        admin.require_auth();
-        
-        // TODO: Add proper admin verification
+        if admin != storage::require_admin(&env) {
+            panic_with_error!(&env, ZapError::Unauthorized);
+        }
+
+        storage::lock(&env);
         let token_client = TokenClient::new(&env, &token);
         token_client.transfer(&env.current_contract_address(), &to, &amount);
+        storage::unlock(&env);
 
-        env.events().publish(
-            (Symbol::new(&env, "emergency_withdraw"),),
-            (admin, token, amount, to),
-        );
+        events::emergency_withdraw(&env, &admin, &token, amount, &to);
     }
 
     /// Initialize contract with admin (call once after deployment)
     pub fn initialize(env: Env, admin: Address) {
+        if storage::has_admin(&env) {
+            panic_with_error!(&env, ZapError::AlreadyInitialized);
+        }
+
         // Store admin in contract storage for future admin-only operations
-        env.storage().instance().set(&Symbol::new(&env, "admin"), &admin);
+        storage::set_admin(&env, &admin);
+        storage::extend_instance(&env);
         
-        env.events().publish(
-            (Symbol::new(&env, "initialized"),),
-            admin,
-        );
+        events::initialized(&env, &admin);
+    }
+
+    /// Override the Soroswap/DeFindex/token addresses used by the contract (admin only)
+    pub fn set_addresses(env: Env, addresses: ContractAddresses) {
+        storage::require_admin(&env);
+        storage::set_addresses(&env, &addresses);
+        storage::extend_instance(&env);
+    }
+
+    /// Override the known vault addresses (admin only)
+    pub fn set_vaults(env: Env, vaults: VaultAddresses) {
+        storage::require_admin(&env);
+        storage::set_vaults(&env, &vaults);
+        storage::extend_instance(&env);
+    }
+
+    /// Register the vaults offered for `asset`, replacing the defaults (admin only)
+    ///
+    /// An empty list falls back to the default vault set.
+    pub fn set_asset_vaults(env: Env, asset: Address, vaults: Vec<Address>) {
+        storage::require_admin(&env);
+        storage::set_asset_vaults(&env, &asset, &vaults);
+        storage::extend_instance(&env);
+    }
+
+    /// Enable the TWAP manipulation guard for a token pair (admin only)
+    ///
+    /// # Arguments
+    /// * `token_a` / `token_b` - Token pair to guard (order does not matter)
+    /// * `pool` - Soroswap pair contract whose reserves are observed
+    /// * `tolerance_bps` - Max deviation of the spot price from the TWAP
+    /// * `window` - TWAP window in seconds
+    /// * `min_history` - Seconds of observations required before guarded swaps go through
+    ///   (at least 1, at most `window`)
+    pub fn set_price_guard(
+        env: Env,
+        token_a: Address,
+        token_b: Address,
+        pool: Address,
+        tolerance_bps: u32,
+        window: u64,
+        min_history: u64,
+    ) {
+        storage::require_admin(&env);
+        let guard = PriceGuard { pool, tolerance_bps, window, min_history };
+        twap::set_guard(&env, &token_a, &token_b, &guard);
+        storage::extend_instance(&env);
+
+        events::price_guard_set(&env, &token_a, &token_b, &guard);
+    }
+
+    /// Disable the TWAP manipulation guard for a token pair (admin only)
+    ///
+    /// Also drops the pool's observations, so after a repricing beyond the tolerance
+    /// the admin can set the guard again and start a fresh history.
+    pub fn remove_price_guard(env: Env, token_a: Address, token_b: Address) {
+        storage::require_admin(&env);
+        twap::remove_guard(&env, &token_a, &token_b);
+
+        events::price_guard_removed(&env, &token_a, &token_b);
+    }
+
+    /// Get the TWAP guard configured for a token pair, if any
+    pub fn get_price_guard(env: Env, token_a: Address, token_b: Address) -> Option<PriceGuard> {
+        twap::get_guard(&env, &token_a, &token_b)
+    }
+
+    /// Set the default price impact cap in bps applied when a zap does not pass one (admin only)
+    pub fn set_max_price_impact(env: Env, max_bps: u32) {
+        storage::require_admin(&env);
+        impact::set_default_cap(&env, max_bps);
+        storage::extend_instance(&env);
+    }
+
+    /// Get the default price impact cap in bps
+    pub fn get_max_price_impact(env: Env) -> u32 {
+        impact::get_default_cap(&env)
+    }
+
+    /// Set the protocol fee taken from every zap's input amount (admin only)
+    ///
+    /// # Arguments
+    /// * `fee_bps` - Fee in basis points, at most `MAX_PROTOCOL_FEE_BPS`
+    /// * `recipient` - Address receiving the fees
+    pub fn set_protocol_fee(env: Env, fee_bps: u32, recipient: Address) {
+        storage::require_admin(&env);
+        let fee = ProtocolFee { fee_bps, recipient };
+        fees::set_protocol_fee(&env, &fee);
+        storage::extend_instance(&env);
+
+        events::protocol_fee_set(&env, &fee);
+    }
+
+    /// Set the intermediate tokens tried when the aggregator has no route (admin only)
+    pub fn set_hub_tokens(env: Env, hubs: Vec<Address>) {
+        storage::require_admin(&env);
+        routing::set_hub_tokens(&env, &hubs);
+        storage::extend_instance(&env);
+    }
+
+    /// Get the intermediate tokens used for fallback routing
+    pub fn get_hub_tokens(env: Env) -> Vec<Address> {
+        routing::get_hub_tokens(&env)
+    }
+
+    /// Set the XLM a classic account must keep after zapping native XLM (admin only)
+    pub fn set_native_headroom(env: Env, headroom: i128) {
+        storage::require_admin(&env);
+        native::set_headroom(&env, headroom);
+        storage::extend_instance(&env);
+    }
+
+    /// Get the native XLM headroom kept in classic accounts
+    pub fn get_native_headroom(env: Env) -> i128 {
+        native::get_headroom(&env)
+    }
+
+    /// Set the tip, in bps of the executed amount, paid to keepers (admin only)
+    pub fn set_keeper_tip(env: Env, tip_bps: u32) {
+        storage::require_admin(&env);
+        fees::set_keeper_tip_bps(&env, tip_bps);
+        storage::extend_instance(&env);
+    }
+
+    /// Get the keeper tip in bps
+    pub fn get_keeper_tip(env: Env) -> u32 {
+        fees::get_keeper_tip_bps(&env)
+    }
+
+    /// Register a vault for keeper harvesting (admin only)
+    ///
+    /// The harvester must send the pending rewards to the address passed to its
+    /// `harvest(to)` (see the harvest adapter interface in `interfaces`).
+    ///
+    /// # Arguments
+    /// * `vault_address` - Vault to register
+    /// * `asset` - Vault asset the rewards are swapped into
+    /// * `reward_tokens` - Tokens the harvest pays out
+    /// * `min_interval` - Minimum seconds between harvests
+    /// * `harvester` - The vault itself or an adapter that claims its rewards
+    /// * `beneficiary` - Receives the shares of the compounded deposit (not the vault itself)
+    pub fn set_harvest_config(
+        env: Env,
+        vault_address: Address,
+        asset: Address,
+        reward_tokens: Vec<Address>,
+        min_interval: u64,
+        harvester: Address,
+        beneficiary: Address,
+    ) {
+        storage::require_admin(&env);
+        let config = HarvestConfig { asset, reward_tokens, min_interval, harvester, beneficiary };
+        harvest::set_config(&env, &vault_address, &config);
+        storage::extend_instance(&env);
+
+        events::harvest_config_set(&env, &vault_address, &config);
+    }
+
+    /// Stop keeper harvesting for a vault (admin only)
+    pub fn remove_harvest_config(env: Env, vault_address: Address) {
+        storage::require_admin(&env);
+        harvest::remove_config(&env, &vault_address);
+    }
+
+    /// Get a vault's harvest registration, if any
+    pub fn get_harvest_config(env: Env, vault_address: Address) -> Option<HarvestConfig> {
+        harvest::get_config(&env, &vault_address)
+    }
+
+    /// Set the fee, in bps of the harvested amount, paid to harvest keepers (admin only)
+    pub fn set_harvest_fee(env: Env, fee_bps: u32) {
+        storage::require_admin(&env);
+        fees::set_harvest_fee_bps(&env, fee_bps);
+        storage::extend_instance(&env);
+    }
+
+    /// Get the harvest keeper fee in bps
+    pub fn get_harvest_fee(env: Env) -> u32 {
+        fees::get_harvest_fee_bps(&env)
+    }
+
+    /// Enable NFT mode with the position NFT contract deployed for this Zap (admin only)
+    ///
+    /// The NFT contract must name this contract as its minter.
+    pub fn set_position_nft(env: Env, nft: Address) {
+        storage::require_admin(&env);
+        nft::set_contract(&env, &nft);
+        storage::extend_instance(&env);
+    }
+
+    /// Get the position NFT contract, if NFT mode is enabled
+    pub fn get_position_nft(env: Env) -> Option<Address> {
+        nft::get_contract(&env)
+    }
+
+    /// Set the smallest deviation, in bps of the position value, that `rebalance` trades (admin only)
+    pub fn set_min_rebalance(env: Env, min_trade_bps: u32) {
+        storage::require_admin(&env);
+        rebalance::set_min_trade_bps(&env, min_trade_bps);
+        storage::extend_instance(&env);
+    }
+
+    /// Get the minimum rebalance trade size in bps
+    pub fn get_min_rebalance(env: Env) -> u32 {
+        rebalance::get_min_trade_bps(&env)
+    }
+
+    /// Get the protocol fee configuration, if any
+    pub fn get_protocol_fee(env: Env) -> Option<ProtocolFee> {
+        fees::get_protocol_fee(&env)
+    }
+
+    /// Record a price observation for a guarded pair (callable by anyone, e.g. keepers)
+    ///
+    /// Keeps the TWAP moving when the market reprices between zaps. Once the pair has a
+    /// TWAP, a spot price beyond the guard's tolerance fails with
+    /// `ZapError::PriceDeviationTooHigh`. Returns the observed spot price.
+    pub fn update_price_observation(env: Env, token_a: Address, token_b: Address) -> i128 {
+        twap::observe(&env, &token_a, &token_b)
     }
 }
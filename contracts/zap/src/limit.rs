@@ -0,0 +1,44 @@
+use soroban_sdk::{contracttype, Address, Env, Vec};
+
+use crate::storage::{self, DataKey, PERSISTENT_BUMP_AMOUNT, PERSISTENT_LIFETIME_THRESHOLD};
+
+/// Escrowed zap that fills only once the swap yields at least `min_amount_out`
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct LimitZap {
+    pub id: u64,
+    pub owner: Address,
+    pub from_token: Address,
+    pub amount_in: i128,
+    pub to_token: Address,
+    pub vault: Address,
+    pub min_amount_out: i128,   // Target output of the swap leg
+    pub expires_at: u64,        // Ledger timestamp after which the order can only be refunded
+}
+
+pub fn next_id(env: &Env) -> u64 {
+    storage::next_id(env, &DataKey::NextLimitZapId)
+}
+
+pub fn get_order(env: &Env, id: u64) -> Option<LimitZap> {
+    env.storage().persistent().get(&DataKey::LimitZap(id))
+}
+
+pub fn save_order(env: &Env, order: &LimitZap) {
+    let key = DataKey::LimitZap(order.id);
+    env.storage().persistent().set(&key, order);
+    env.storage()
+        .persistent()
+        .extend_ttl(&key, PERSISTENT_LIFETIME_THRESHOLD, PERSISTENT_BUMP_AMOUNT);
+    storage::add_id(env, &DataKey::UserLimitZaps(order.owner.clone()), order.id);
+}
+
+pub fn remove_order(env: &Env, order: &LimitZap) {
+    env.storage().persistent().remove(&DataKey::LimitZap(order.id));
+    storage::remove_id(env, &DataKey::UserLimitZaps(order.owner.clone()), order.id);
+}
+
+/// Ids of the open orders owned by `user`
+pub fn order_ids(env: &Env, user: &Address) -> Vec<u64> {
+    storage::get_ids(env, &DataKey::UserLimitZaps(user.clone()))
+}
@@ -0,0 +1,71 @@
+use crate::errors::ZapError;
+
+pub use zap_math::{Rounding, BPS_DENOMINATOR};
+use zap_math::MathError;
+
+impl From<MathError> for ZapError {
+    fn from(err: MathError) -> Self {
+        match err {
+            MathError::InvalidAmount => ZapError::InvalidAmount,
+            MathError::Overflow => ZapError::ArithmeticOverflow,
+        }
+    }
+}
+
+/// Compute a * b / denominator without intermediate overflow
+///
+/// Inputs must be non-negative amounts and the denominator positive.
+pub fn mul_div(a: i128, b: i128, denominator: i128, rounding: Rounding) -> Result<i128, ZapError> {
+    Ok(zap_math::mul_div(a, b, denominator, rounding)?)
+}
+
+/// `bps` basis points of `amount`
+pub fn apply_bps(amount: i128, bps: u32, rounding: Rounding) -> Result<i128, ZapError> {
+    mul_div(amount, bps as i128, BPS_DENOMINATOR, rounding)
+}
+
+/// `amount` reduced by `bps` basis points, rounded down (e.g. minimum outputs)
+pub fn sub_bps(amount: i128, bps: u32) -> Result<i128, ZapError> {
+    if bps as i128 > BPS_DENOMINATOR {
+        return Err(ZapError::InvalidAmount);
+    }
+    mul_div(amount, BPS_DENOMINATOR - bps as i128, BPS_DENOMINATOR, Rounding::Down)
+}
+
+/// Vault shares worth `assets`, 1:1 for an empty vault
+pub fn assets_to_shares(
+    assets: i128,
+    total_assets: i128,
+    total_shares: i128,
+    rounding: Rounding,
+) -> Result<i128, ZapError> {
+    if total_assets == 0 || total_shares == 0 {
+        return if assets < 0 { Err(ZapError::InvalidAmount) } else { Ok(assets) };
+    }
+    mul_div(assets, total_shares, total_assets, rounding)
+}
+
+/// Assets backing `shares`, 1:1 for an empty vault
+pub fn shares_to_assets(
+    shares: i128,
+    total_assets: i128,
+    total_shares: i128,
+    rounding: Rounding,
+) -> Result<i128, ZapError> {
+    if total_assets == 0 || total_shares == 0 {
+        return if shares < 0 { Err(ZapError::InvalidAmount) } else { Ok(shares) };
+    }
+    mul_div(shares, total_assets, total_shares, rounding)
+}
+
+pub fn add(a: i128, b: i128) -> Result<i128, ZapError> {
+    a.checked_add(b).ok_or(ZapError::ArithmeticOverflow)
+}
+
+pub fn sub(a: i128, b: i128) -> Result<i128, ZapError> {
+    a.checked_sub(b).ok_or(ZapError::ArithmeticOverflow)
+}
+
+pub fn mul(a: i128, b: i128) -> Result<i128, ZapError> {
+    a.checked_mul(b).ok_or(ZapError::ArithmeticOverflow)
+}
@@ -0,0 +1,58 @@
+use soroban_sdk::{panic_with_error, token::Client as TokenClient, Address, Env};
+
+use crate::errors::{OrPanic, ZapError};
+use crate::math;
+use crate::storage::{self, DataKey};
+
+/// Length of a G... or C... strkey
+const STRKEY_LEN: usize = 56;
+
+/// XLM a classic account keeps after zapping native XLM by default (2 XLM)
+///
+/// Covers the base reserve of an account with a couple of subentries plus fees.
+pub const DEFAULT_NATIVE_HEADROOM: i128 = 20_000_000;
+
+pub fn get_headroom(env: &Env) -> i128 {
+    env.storage()
+        .instance()
+        .get(&DataKey::NativeHeadroom)
+        .unwrap_or(DEFAULT_NATIVE_HEADROOM)
+}
+
+pub fn set_headroom(env: &Env, headroom: i128) {
+    if headroom < 0 {
+        panic_with_error!(env, ZapError::InvalidConfig);
+    }
+    env.storage().instance().set(&DataKey::NativeHeadroom, &headroom);
+}
+
+/// Whether `token` is the native XLM Stellar Asset Contract
+pub fn is_native(env: &Env, token: &Address) -> bool {
+    *token == storage::get_addresses(env).xlm
+}
+
+/// Whether `address` is a classic Stellar account (G...) rather than a contract
+pub fn is_account(address: &Address) -> bool {
+    let strkey = address.to_string();
+    if strkey.len() as usize != STRKEY_LEN {
+        return false;
+    }
+    let mut buf = [0u8; STRKEY_LEN];
+    strkey.copy_into_slice(&mut buf);
+    buf[0] == b'G'
+}
+
+/// Refuse to pull native XLM that would leave a classic account below its headroom
+///
+/// The SAC moves classic XLM balances directly, so no wrapping step is needed;
+/// this only keeps enough behind for the account's reserve and future fees.
+pub fn check_headroom(env: &Env, user: &Address, token: &Address, amount: i128) {
+    if !is_native(env, token) || !is_account(user) {
+        return;
+    }
+
+    let balance = TokenClient::new(env, token).balance(user);
+    if math::sub(balance, amount).or_panic(env) < get_headroom(env) {
+        panic_with_error!(env, ZapError::InsufficientNativeReserve);
+    }
+}
@@ -0,0 +1,80 @@
+use soroban_sdk::{contracttype, panic_with_error, vec, Address, Env, IntoVal, Symbol};
+
+use crate::errors::{OrPanic, ZapError};
+use crate::math::{self, Rounding};
+use crate::storage::DataKey;
+use crate::ZapResult;
+
+/// Fixed-point scale of `PositionReceipt::entry_price`, matching the NFT contract
+pub const PRICE_SCALE: i128 = 10_000_000;
+
+/// Vault position held in custody by the position NFT contract
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct PositionReceipt {
+    pub vault: Address,
+    pub asset: Address,       // Asset the vault pays out on withdrawal
+    pub shares: i128,
+    pub entry_price: i128,    // Vault asset per share at mint, scaled by PRICE_SCALE
+    pub minted_at: u64,
+}
+
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct NftZapResult {
+    pub token_id: u32,
+    pub zap: ZapResult,
+}
+
+pub fn get_contract(env: &Env) -> Option<Address> {
+    env.storage().instance().get(&DataKey::PositionNft)
+}
+
+pub fn set_contract(env: &Env, nft: &Address) {
+    env.storage().instance().set(&DataKey::PositionNft, nft);
+}
+
+/// The configured NFT contract, failing when the mode is not enabled
+pub fn require_contract(env: &Env) -> Address {
+    get_contract(env).unwrap_or_else(|| panic_with_error!(env, ZapError::InvalidConfig))
+}
+
+/// Mint a token to `to` for shares already deposited with `nft` as receiver
+///
+/// Fails with `ZapError::InvalidAmount` when the deposit minted no shares, which
+/// would leave nothing in custody and no entry price.
+pub fn mint(env: &Env, nft: &Address, to: &Address, asset: &Address, result: &ZapResult) -> u32 {
+    if result.vault_shares <= 0 {
+        panic_with_error!(env, ZapError::InvalidAmount);
+    }
+
+    let entry_price = math::mul_div(
+        result.amount_swapped,
+        PRICE_SCALE,
+        result.vault_shares,
+        Rounding::Down,
+    )
+    .or_panic(env);
+
+    env.invoke_contract(
+        nft,
+        &Symbol::new(env, "mint"),
+        vec![
+            env,
+            to.into_val(env),
+            result.vault_address.into_val(env),
+            asset.into_val(env),
+            result.vault_shares.into_val(env),
+            entry_price.into_val(env),
+        ],
+    )
+}
+
+/// Burn `owner`'s token; the NFT contract sends the custodied shares to this contract
+pub fn burn(env: &Env, nft: &Address, owner: &Address, token_id: u32) -> PositionReceipt {
+    env.invoke_contract(
+        nft,
+        &Symbol::new(env, "burn"),
+        vec![env, owner.into_val(env), token_id.into_val(env)],
+    )
+}
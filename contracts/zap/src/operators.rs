@@ -0,0 +1,116 @@
+use soroban_sdk::{contracttype, panic_with_error, Address, Env, Map, Vec};
+
+use crate::errors::{OrPanic, ZapError};
+use crate::math;
+use crate::storage::{DataKey, PERSISTENT_BUMP_AMOUNT, PERSISTENT_LIFETIME_THRESHOLD};
+
+/// Limits within which an operator may act for a user
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct OperatorPolicy {
+    pub vaults: Vec<Address>,          // Vaults the operator may deposit into or redeem from
+    pub tokens: Vec<Address>,          // Tokens the operator may swap from or into
+    pub max_amount_per_period: i128,   // Per unit: each input token (zaps) and each vault's shares
+    pub max_slippage_bps: u32,         // Largest tolerance below the TWAP value of a swap
+    pub period: u64,                   // Length of a spending period in seconds
+    pub expires_at: u64,               // Ledger timestamp after which the policy is void
+}
+
+/// Amounts spent by an operator in the current period
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct OperatorUsage {
+    pub period_start: u64,
+    pub spent: Map<Address, i128>,     // Keyed by input token, or by vault for its shares
+}
+
+fn extend(env: &Env, key: &DataKey) {
+    env.storage()
+        .persistent()
+        .extend_ttl(key, PERSISTENT_LIFETIME_THRESHOLD, PERSISTENT_BUMP_AMOUNT);
+}
+
+pub fn get_policy(env: &Env, user: &Address, operator: &Address) -> Option<OperatorPolicy> {
+    env.storage()
+        .persistent()
+        .get(&DataKey::Operator(user.clone(), operator.clone()))
+}
+
+pub fn set_policy(env: &Env, user: &Address, operator: &Address, policy: &OperatorPolicy) {
+    if user == operator
+        || policy.max_amount_per_period <= 0
+        || policy.max_slippage_bps as i128 > math::BPS_DENOMINATOR
+        || policy.period == 0
+        || policy.expires_at <= env.ledger().timestamp()
+    {
+        panic_with_error!(env, ZapError::InvalidConfig);
+    }
+
+    let key = DataKey::Operator(user.clone(), operator.clone());
+    env.storage().persistent().set(&key, policy);
+    extend(env, &key);
+
+    // A new policy starts with a fresh allowance
+    env.storage()
+        .persistent()
+        .remove(&DataKey::OperatorUsage(user.clone(), operator.clone()));
+}
+
+pub fn remove_policy(env: &Env, user: &Address, operator: &Address) {
+    env.storage()
+        .persistent()
+        .remove(&DataKey::Operator(user.clone(), operator.clone()));
+    env.storage()
+        .persistent()
+        .remove(&DataKey::OperatorUsage(user.clone(), operator.clone()));
+}
+
+/// Check an operator action against the user's policy and count `amount` against its limit
+///
+/// `unit` is what `amount` is denominated in: the input token for zaps, the vault for shares.
+/// Each unit has its own counter so token amounts and share amounts never add up.
+/// Returns the policy so callers can enforce its slippage bound.
+pub fn consume(
+    env: &Env,
+    user: &Address,
+    operator: &Address,
+    vaults: &[&Address],
+    tokens: &[&Address],
+    unit: &Address,
+    amount: i128,
+) -> OperatorPolicy {
+    let policy = get_policy(env, user, operator)
+        .unwrap_or_else(|| panic_with_error!(env, ZapError::OperatorNotAuthorized));
+
+    let now = env.ledger().timestamp();
+    if now >= policy.expires_at {
+        panic_with_error!(env, ZapError::OperatorExpired);
+    }
+    if vaults.iter().any(|vault| !policy.vaults.contains(*vault))
+        || tokens.iter().any(|token| !policy.tokens.contains(*token))
+    {
+        panic_with_error!(env, ZapError::OperatorNotAuthorized);
+    }
+
+    let key = DataKey::OperatorUsage(user.clone(), operator.clone());
+    let mut usage = env
+        .storage()
+        .persistent()
+        .get(&key)
+        .filter(|usage: &OperatorUsage| now < usage.period_start.saturating_add(policy.period))
+        .unwrap_or(OperatorUsage {
+            period_start: now,
+            spent: Map::new(env),
+        });
+
+    let spent = math::add(usage.spent.get(unit.clone()).unwrap_or(0), amount).or_panic(env);
+    if spent > policy.max_amount_per_period {
+        panic_with_error!(env, ZapError::OperatorLimitExceeded);
+    }
+    usage.spent.set(unit.clone(), spent);
+
+    env.storage().persistent().set(&key, &usage);
+    extend(env, &key);
+    extend(env, &DataKey::Operator(user.clone(), operator.clone()));
+    policy
+}
@@ -0,0 +1,174 @@
+use soroban_sdk::{
+    contracttype, panic_with_error, vec, Address, Env, Map, Symbol, TryFromVal, Val, Vec,
+};
+
+use crate::errors::{OrPanic, ZapError};
+use crate::math::{self, Rounding};
+use crate::storage::{DataKey, PERSISTENT_BUMP_AMOUNT, PERSISTENT_LIFETIME_THRESHOLD};
+
+/// Number of samples kept per vault
+pub const MAX_VAULT_SAMPLES: u32 = 64;
+/// Minimum spacing between samples, so spam cannot flush the history
+pub const MIN_SNAPSHOT_INTERVAL: u64 = 3_600;
+/// Fixed-point scale of share prices
+pub const SHARE_PRICE_SCALE: i128 = 10_000_000;
+const SECONDS_PER_YEAR: i128 = 365 * 86_400;
+
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct VaultSample {
+    pub timestamp: u64,
+    pub total_assets: i128,
+    pub total_shares: i128,
+}
+
+impl VaultSample {
+    /// Assets per share, scaled by SHARE_PRICE_SCALE
+    fn share_price(&self) -> Result<i128, ZapError> {
+        math::mul_div(self.total_assets, SHARE_PRICE_SCALE, self.total_shares, Rounding::Down)
+    }
+}
+
+pub fn get_samples(env: &Env, vault: &Address) -> Vec<VaultSample> {
+    env.storage()
+        .persistent()
+        .get(&DataKey::VaultSamples(vault.clone()))
+        .unwrap_or_else(|| Vec::new(env))
+}
+
+/// Read the vault's current totals and append them, dropping the oldest sample when full
+pub fn snapshot(env: &Env, vault: &Address) -> VaultSample {
+    let now = env.ledger().timestamp();
+    let mut samples = get_samples(env, vault);
+    if let Some(last) = samples.last() {
+        if now < last.timestamp + MIN_SNAPSHOT_INTERVAL {
+            panic_with_error!(env, ZapError::SnapshotTooSoon);
+        }
+    }
+
+    let total_shares: i128 = env.invoke_contract(vault, &Symbol::new(env, "total_supply"), vec![env]);
+    let funds: Vec<Map<Symbol, Val>> = env.invoke_contract(
+        vault,
+        &Symbol::new(env, "fetch_total_managed_funds"),
+        vec![env],
+    );
+    let total_assets = funds
+        .first()
+        .and_then(|asset| asset.get(Symbol::new(env, "total_amount")))
+        .and_then(|amount| i128::try_from_val(env, &amount).ok())
+        .unwrap_or_else(|| panic_with_error!(env, ZapError::InvalidConfig));
+
+    let sample = VaultSample {
+        timestamp: now,
+        total_assets,
+        total_shares,
+    };
+    samples.push_back(sample.clone());
+    while samples.len() > MAX_VAULT_SAMPLES {
+        samples.pop_front();
+    }
+
+    let key = DataKey::VaultSamples(vault.clone());
+    env.storage().persistent().set(&key, &samples);
+    env.storage()
+        .persistent()
+        .extend_ttl(&key, PERSISTENT_LIFETIME_THRESHOLD, PERSISTENT_BUMP_AMOUNT);
+    sample
+}
+
+/// Annualized share-price growth, in bps, between the latest sample and the last one
+/// at least `window` seconds older
+///
+/// Negative when the share price fell. None until the history spans the window or
+/// while a sample has no shares.
+pub fn apy_bps(env: &Env, vault: &Address, window: u64) -> Option<i128> {
+    let samples = get_samples(env, vault);
+    let end = samples.last()?;
+    let cutoff = end.timestamp.checked_sub(window)?;
+    let start = samples.iter().rev().find(|sample| sample.timestamp <= cutoff)?;
+
+    let elapsed = (end.timestamp - start.timestamp) as i128;
+    if elapsed == 0 || start.total_shares <= 0 || end.total_shares <= 0 {
+        return None;
+    }
+
+    let start_price = start.share_price().or_panic(env);
+    let end_price = end.share_price().or_panic(env);
+    if start_price <= 0 {
+        return None;
+    }
+
+    // Scale the magnitude of the change and restore its sign, rounding toward zero
+    let change = math::sub(end_price, start_price).or_panic(env);
+    let growth = math::mul_div(
+        change.abs(),
+        math::BPS_DENOMINATOR,
+        start_price,
+        Rounding::Down,
+    )
+    .or_panic(env);
+    let apy = math::mul_div(growth, SECONDS_PER_YEAR, elapsed, Rounding::Down).or_panic(env);
+    Some(if change < 0 { -apy } else { apy })
+}
+
+/// Filters applied when picking a vault automatically
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct VaultConstraints {
+    pub allowed_vaults: Vec<Address>,       // Restrict the candidates (empty allows all)
+    pub min_tvl: i128,                      // Minimum total assets in the latest sample
+    pub max_fee_bps: u32,                   // Cap on the vault's fees (vault + protocol)
+    pub apy_window: u64,                    // Window, in seconds, the APY is measured over
+    pub min_amount_out: i128,               // Minimum output of the swap into the asset
+    pub max_price_impact_bps: Option<u32>,  // Price impact cap (defaults to the admin cap)
+}
+
+/// Vault picked by `best_vault` and the figures it was ranked on
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct BestVault {
+    pub vault: Address,
+    pub apy_bps: i128,
+    pub tvl: i128,
+    pub fee_bps: u32,
+}
+
+/// Total fees a DeFindex vault charges, from its `get_fees` (vault fee, protocol fee)
+fn fee_bps(env: &Env, vault: &Address) -> u32 {
+    let (vault_fee, protocol_fee): (u32, u32) =
+        env.invoke_contract(vault, &Symbol::new(env, "get_fees"), vec![env]);
+    vault_fee.saturating_add(protocol_fee)
+}
+
+/// Highest-APY candidate meeting the constraints; vaults without enough samples are skipped
+pub fn best_vault(env: &Env, candidates: &Vec<Address>, constraints: &VaultConstraints) -> Option<BestVault> {
+    let mut best: Option<BestVault> = None;
+    for vault in candidates.iter() {
+        if !constraints.allowed_vaults.is_empty() && !constraints.allowed_vaults.contains(&vault) {
+            continue;
+        }
+        let Some(tvl) = get_samples(env, &vault).last().map(|sample| sample.total_assets) else {
+            continue;
+        };
+        if tvl < constraints.min_tvl {
+            continue;
+        }
+        let Some(apy_bps) = apy_bps(env, &vault, constraints.apy_window) else {
+            continue;
+        };
+        if best.as_ref().is_some_and(|best| apy_bps <= best.apy_bps) {
+            continue;
+        }
+        let fee_bps = fee_bps(env, &vault);
+        if fee_bps > constraints.max_fee_bps {
+            continue;
+        }
+        best = Some(BestVault {
+            vault,
+            apy_bps,
+            tvl,
+            fee_bps,
+        });
+    }
+    best
+}
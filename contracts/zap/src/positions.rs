@@ -0,0 +1,78 @@
+use soroban_sdk::{contracttype, Address, Env, Vec};
+
+use crate::errors::OrPanic;
+use crate::math;
+use crate::storage::{DataKey, PERSISTENT_BUMP_AMOUNT, PERSISTENT_LIFETIME_THRESHOLD};
+
+/// Cost basis of a user's vault position built through the Zap contract
+///
+/// Amounts are in the vault asset, so PnL is the current value of the open
+/// shares plus `assets_withdrawn` minus `assets_deposited`.
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Position {
+    pub vault: Address,
+    pub assets_deposited: i128,
+    pub shares_received: i128,
+    pub assets_withdrawn: i128,
+    pub shares_withdrawn: i128,
+    pub updated_at: u64,
+}
+
+fn extend(env: &Env, key: &DataKey) {
+    env.storage()
+        .persistent()
+        .extend_ttl(key, PERSISTENT_LIFETIME_THRESHOLD, PERSISTENT_BUMP_AMOUNT);
+}
+
+pub fn get_position(env: &Env, user: &Address, vault: &Address) -> Option<Position> {
+    env.storage()
+        .persistent()
+        .get(&DataKey::Position(user.clone(), vault.clone()))
+}
+
+/// Vaults `user` has a recorded position in
+pub fn vaults(env: &Env, user: &Address) -> Vec<Address> {
+    env.storage()
+        .persistent()
+        .get(&DataKey::UserVaults(user.clone()))
+        .unwrap_or_else(|| Vec::new(env))
+}
+
+fn update(env: &Env, user: &Address, vault: &Address, apply: impl FnOnce(&mut Position)) {
+    let key = DataKey::Position(user.clone(), vault.clone());
+    let mut position = get_position(env, user, vault).unwrap_or_else(|| {
+        let vaults_key = DataKey::UserVaults(user.clone());
+        let mut vaults = vaults(env, user);
+        vaults.push_back(vault.clone());
+        env.storage().persistent().set(&vaults_key, &vaults);
+        Position {
+            vault: vault.clone(),
+            assets_deposited: 0,
+            shares_received: 0,
+            assets_withdrawn: 0,
+            shares_withdrawn: 0,
+            updated_at: 0,
+        }
+    });
+
+    apply(&mut position);
+    position.updated_at = env.ledger().timestamp();
+    env.storage().persistent().set(&key, &position);
+    extend(env, &key);
+    extend(env, &DataKey::UserVaults(user.clone()));
+}
+
+pub fn record_deposit(env: &Env, user: &Address, vault: &Address, assets: i128, shares: i128) {
+    update(env, user, vault, |position| {
+        position.assets_deposited = math::add(position.assets_deposited, assets).or_panic(env);
+        position.shares_received = math::add(position.shares_received, shares).or_panic(env);
+    });
+}
+
+pub fn record_withdrawal(env: &Env, user: &Address, vault: &Address, assets: i128, shares: i128) {
+    update(env, user, vault, |position| {
+        position.assets_withdrawn = math::add(position.assets_withdrawn, assets).or_panic(env);
+        position.shares_withdrawn = math::add(position.shares_withdrawn, shares).or_panic(env);
+    });
+}
@@ -0,0 +1,65 @@
+use soroban_sdk::{
+    contracttype, panic_with_error, vec, Address, Env, Map, Symbol, TryFromVal, Val, Vec,
+};
+
+use crate::errors::{OrPanic, ZapError};
+use crate::math::{self, BPS_DENOMINATOR};
+use crate::storage::DataKey;
+
+/// Smallest deviation from target, in bps of the total value, that is traded by default
+pub const DEFAULT_MIN_REBALANCE_BPS: u32 = 100;
+
+/// Vault shares redeemed or received by one leg of a rebalance
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct RebalanceLeg {
+    pub vault: Address,
+    pub shares: i128,
+}
+
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct RebalanceResult {
+    pub total_value: i128,              // Position value before trading, in the first target's asset
+    pub withdrawals: Vec<RebalanceLeg>, // Overweight vaults and the shares redeemed
+    pub deposits: Vec<RebalanceLeg>,    // Underweight vaults and the shares received
+}
+
+pub fn get_min_trade_bps(env: &Env) -> u32 {
+    env.storage()
+        .instance()
+        .get(&DataKey::MinRebalanceBps)
+        .unwrap_or(DEFAULT_MIN_REBALANCE_BPS)
+}
+
+pub fn set_min_trade_bps(env: &Env, min_trade_bps: u32) {
+    if min_trade_bps as i128 > BPS_DENOMINATOR {
+        panic_with_error!(env, ZapError::InvalidConfig);
+    }
+    env.storage().instance().set(&DataKey::MinRebalanceBps, &min_trade_bps);
+}
+
+/// Reject empty or duplicate targets, and weights that do not sum to 100%
+pub fn validate_targets(env: &Env, targets: &Vec<(Address, u32)>, max_slippage_bps: u32) {
+    let mut total: i128 = 0;
+    for (i, (vault, weight_bps)) in targets.iter().enumerate() {
+        if targets.iter().skip(i + 1).any(|(other, _)| other == vault) {
+            panic_with_error!(env, ZapError::InvalidRoute);
+        }
+        total = math::add(total, weight_bps as i128).or_panic(env);
+    }
+    if targets.is_empty() || total != BPS_DENOMINATOR || max_slippage_bps as i128 > BPS_DENOMINATOR {
+        panic_with_error!(env, ZapError::InvalidRoute);
+    }
+}
+
+/// The asset a DeFindex vault accepts, the first entry of its `get_assets`
+pub fn vault_asset(env: &Env, vault: &Address) -> Address {
+    let assets: Vec<Map<Symbol, Val>> =
+        env.invoke_contract(vault, &Symbol::new(env, "get_assets"), vec![env]);
+    assets
+        .first()
+        .and_then(|asset| asset.get(Symbol::new(env, "address")))
+        .and_then(|address| Address::try_from_val(env, &address).ok())
+        .unwrap_or_else(|| panic_with_error!(env, ZapError::InvalidConfig))
+}
@@ -0,0 +1,298 @@
+use soroban_sdk::{
+    auth::{ContractContext, InvokerContractAuthEntry, SubContractInvocation},
+    panic_with_error, token::Client as TokenClient, vec, Address, Env, IntoVal, Symbol,
+    TryIntoVal, Val, Vec,
+};
+
+use crate::config::ContractAddresses;
+use crate::errors::{OrPanic, ZapError};
+use crate::impact;
+use crate::interfaces::{SubRoute, SwapRoute, SwapVenue};
+use crate::math::{self, Rounding};
+use crate::storage::{self, DataKey};
+
+/// Upper bound on legs in a split zap
+pub const MAX_SUB_ROUTES: u32 = 5;
+
+/// Intermediate tokens tried when the aggregator has no route, defaulting to XLM and USDC
+pub fn get_hub_tokens(env: &Env) -> Vec<Address> {
+    env.storage().instance().get(&DataKey::HubTokens).unwrap_or_else(|| {
+        let config = storage::get_addresses(env);
+        vec![env, config.xlm, config.usdc]
+    })
+}
+
+pub fn set_hub_tokens(env: &Env, hubs: &Vec<Address>) {
+    env.storage().instance().set(&DataKey::HubTokens, hubs);
+}
+
+/// Ask the aggregator for its best route, `None` if it fails or has no path
+fn aggregator_route(
+    env: &Env,
+    config: &ContractAddresses,
+    token_in: &Address,
+    token_out: &Address,
+    amount_in: i128,
+) -> Option<(Vec<Address>, Vec<u32>)> {
+    let route_params = vec![
+        env,
+        token_in.into_val(env),
+        token_out.into_val(env),
+        amount_in.into_val(env),
+    ];
+
+    let result = env.try_invoke_contract::<(Vec<Address>, Vec<u32>), soroban_sdk::Error>(
+        &config.soroswap_aggregator,
+        &Symbol::new(env, "get_best_route"),
+        route_params,
+    );
+
+    match result {
+        Ok(Ok((path, distribution))) if path.len() >= 2 => Some((path, distribution)),
+        _ => None,
+    }
+}
+
+/// Output of `amount_in` along `path` through the Soroswap Router, `None` if any pair is missing
+fn router_quote(env: &Env, router: &Address, amount_in: i128, path: &Vec<Address>) -> Option<i128> {
+    let result = env.try_invoke_contract::<Vec<i128>, soroban_sdk::Error>(
+        router,
+        &Symbol::new(env, "router_get_amounts_out"),
+        vec![env, amount_in.into_val(env), path.into_val(env)],
+    );
+
+    match result {
+        Ok(Ok(amounts)) => amounts.last().filter(|amount| *amount > 0),
+        _ => None,
+    }
+}
+
+/// Best direct or single-hop path through the configured hub tokens
+fn fallback_path(
+    env: &Env,
+    config: &ContractAddresses,
+    token_in: &Address,
+    token_out: &Address,
+    amount_in: i128,
+) -> Option<Vec<Address>> {
+    let mut candidates = vec![env, vec![env, token_in.clone(), token_out.clone()]];
+    for hub in get_hub_tokens(env).iter() {
+        if hub != *token_in && hub != *token_out {
+            candidates.push_back(vec![env, token_in.clone(), hub, token_out.clone()]);
+        }
+    }
+
+    let mut best: Option<(Vec<Address>, i128)> = None;
+    for path in candidates.iter() {
+        if let Some(amount_out) = router_quote(env, &config.soroswap_router, amount_in, &path) {
+            if best.as_ref().is_none_or(|(_, best_out)| amount_out > *best_out) {
+                best = Some((path, amount_out));
+            }
+        }
+    }
+    best.map(|(path, _)| path)
+}
+
+/// Quote the output of a swap on the given venue
+pub fn quote(
+    env: &Env,
+    config: &ContractAddresses,
+    venue: SwapVenue,
+    token_in: &Address,
+    token_out: &Address,
+    amount_in: i128,
+    path: &Vec<Address>,
+    distribution: &Vec<u32>,
+) -> i128 {
+    match venue {
+        SwapVenue::Aggregator => {
+            let quote_params = vec![
+                env,
+                token_in.into_val(env),
+                token_out.into_val(env),
+                amount_in.into_val(env),
+                path.into_val(env),
+                distribution.into_val(env),
+            ];
+
+            let result: Val = env.invoke_contract(
+                &config.soroswap_aggregator,
+                &Symbol::new(env, "get_amounts_out"),
+                quote_params,
+            );
+
+            result.try_into_val(env).unwrap()
+        }
+        SwapVenue::Router => router_quote(env, &config.soroswap_router, amount_in, path)
+            .unwrap_or_else(|| panic_with_error!(env, ZapError::NoRouteFound)),
+    }
+}
+
+/// Resolve the swap route and measure its expected output and price impact
+///
+/// Uses the provided path/distribution on `venue`, else the aggregator's best route,
+/// else the best direct or hub path through the Soroswap Router.
+/// Price impact compares a tiny probe quote with the quote for the full amount.
+/// The returned route has no minimum amount set.
+pub fn plan(
+    env: &Env,
+    token_in: &Address,
+    token_out: &Address,
+    amount_in: i128,
+    path: Vec<Address>,
+    distribution: Vec<u32>,
+    venue: SwapVenue,
+) -> SwapRoute {
+    let config = storage::get_addresses(env);
+
+    let (venue, path, distribution) = if !path.is_empty() {
+        if venue == SwapVenue::Router
+            && (path.len() < 2
+                || path.first_unchecked() != *token_in
+                || path.last_unchecked() != *token_out)
+        {
+            panic_with_error!(env, ZapError::InvalidRoute);
+        }
+        (venue, path, distribution)
+    } else if let Some((path, distribution)) =
+        aggregator_route(env, &config, token_in, token_out, amount_in)
+    {
+        (SwapVenue::Aggregator, path, distribution)
+    } else if let Some(path) = fallback_path(env, &config, token_in, token_out, amount_in) {
+        (SwapVenue::Router, path, vec![env, 100u32])
+    } else {
+        panic_with_error!(env, ZapError::NoRouteFound);
+    };
+
+    let probe_in = impact::probe_amount(amount_in);
+    let probe_out = quote(env, &config, venue, token_in, token_out, probe_in, &path, &distribution);
+    let expected_amount =
+        quote(env, &config, venue, token_in, token_out, amount_in, &path, &distribution);
+    let price_impact = impact::price_impact_bps(env, probe_in, probe_out, amount_in, expected_amount);
+
+    SwapRoute {
+        path,
+        pools: Vec::new(env),
+        distribution,
+        expected_amount,
+        minimum_amount: 0,
+        price_impact,
+        venue,
+    }
+}
+
+/// Execute `route` with tokens held by this contract and return the amount received
+pub fn execute(
+    env: &Env,
+    route: &SwapRoute,
+    token_in: &Address,
+    token_out: &Address,
+    amount_in: i128,
+    min_amount_out: i128,
+) -> i128 {
+    let config = storage::get_addresses(env);
+    let token_in_client = TokenClient::new(env, token_in);
+
+    match route.venue {
+        SwapVenue::Aggregator => {
+            // Approve Soroswap to spend our tokens
+            token_in_client.approve(
+                &env.current_contract_address(),
+                &config.soroswap_aggregator,
+                &amount_in,
+                &(env.ledger().sequence() + 100),
+            );
+
+            // Prepare swap parameters
+            let swap_params = vec![
+                env,
+                token_in.into_val(env),
+                token_out.into_val(env),
+                amount_in.into_val(env),
+                min_amount_out.into_val(env),
+                route.path.into_val(env),
+                route.distribution.into_val(env),
+            ];
+
+            // Call Soroswap Aggregator's swap function
+            let result: Val = env.invoke_contract(
+                &config.soroswap_aggregator,
+                &Symbol::new(env, "swap"),
+                swap_params,
+            );
+
+            // Extract the amount received from swap
+            result.try_into_val(env).unwrap()
+        }
+        SwapVenue::Router => {
+            let router = config.soroswap_router;
+
+            // The router pulls the input from us into the first pair
+            let first_pair: Address = env.invoke_contract(
+                &router,
+                &Symbol::new(env, "router_pair_for"),
+                vec![
+                    env,
+                    route.path.get_unchecked(0).into_val(env),
+                    route.path.get_unchecked(1).into_val(env),
+                ],
+            );
+            env.authorize_as_current_contract(vec![
+                env,
+                InvokerContractAuthEntry::Contract(SubContractInvocation {
+                    context: ContractContext {
+                        contract: token_in.clone(),
+                        fn_name: Symbol::new(env, "transfer"),
+                        args: (env.current_contract_address(), first_pair, amount_in).into_val(env),
+                    },
+                    sub_invocations: vec![env],
+                }),
+            ]);
+
+            let amounts: Vec<i128> = env.invoke_contract(
+                &router,
+                &Symbol::new(env, "swap_exact_tokens_for_tokens"),
+                vec![
+                    env,
+                    amount_in.into_val(env),
+                    min_amount_out.into_val(env),
+                    route.path.into_val(env),
+                    env.current_contract_address().into_val(env),
+                    (env.ledger().timestamp() + 300).into_val(env),
+                ],
+            );
+            amounts.last().unwrap_or(0)
+        }
+    }
+}
+
+/// Split `amount_in` across legs by weight, the last leg taking the rounding remainder
+pub fn split_amounts(env: &Env, amount_in: i128, legs: &Vec<SubRoute>) -> Vec<i128> {
+    if legs.is_empty() || legs.len() > MAX_SUB_ROUTES {
+        panic_with_error!(env, ZapError::InvalidRoute);
+    }
+
+    let total_weight = legs
+        .iter()
+        .try_fold(0u32, |total, leg| total.checked_add(leg.weight_bps))
+        .unwrap_or_else(|| panic_with_error!(env, ZapError::InvalidRoute));
+    if total_weight != math::BPS_DENOMINATOR as u32 || legs.iter().any(|leg| leg.weight_bps == 0) {
+        panic_with_error!(env, ZapError::InvalidRoute);
+    }
+
+    let mut amounts = Vec::new(env);
+    let mut remaining = amount_in;
+    for (i, leg) in legs.iter().enumerate() {
+        let amount = if i as u32 == legs.len() - 1 {
+            remaining
+        } else {
+            math::apply_bps(amount_in, leg.weight_bps, Rounding::Down).or_panic(env)
+        };
+        if amount <= 0 {
+            panic_with_error!(env, ZapError::InvalidAmount);
+        }
+        remaining = math::sub(remaining, amount).or_panic(env);
+        amounts.push_back(amount);
+    }
+    amounts
+}
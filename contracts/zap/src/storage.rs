@@ -0,0 +1,170 @@
+use soroban_sdk::{contracttype, panic_with_error, Address, Env, Vec};
+
+use crate::config::{ContractAddresses, VaultAddresses};
+use crate::errors::ZapError;
+
+// Ledgers are ~5s, so one day is ~17280 ledgers
+pub const DAY_IN_LEDGERS: u32 = 17280;
+pub const INSTANCE_BUMP_AMOUNT: u32 = 7 * DAY_IN_LEDGERS;
+pub const INSTANCE_LIFETIME_THRESHOLD: u32 = INSTANCE_BUMP_AMOUNT - DAY_IN_LEDGERS;
+pub const PERSISTENT_BUMP_AMOUNT: u32 = 30 * DAY_IN_LEDGERS;
+pub const PERSISTENT_LIFETIME_THRESHOLD: u32 = PERSISTENT_BUMP_AMOUNT - DAY_IN_LEDGERS;
+
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum DataKey {
+    Admin,
+    Addresses,
+    Vaults,
+    MaxPriceImpact,
+    ProtocolFee,
+    HubTokens,
+    PriceGuard(Address, Address), // Keyed by the sorted token pair
+    Observations(Address),        // Keyed by the pool address
+    Lock,                         // Reentrancy lock, temporary storage
+    NativeHeadroom,
+    Operator(Address, Address),      // (user, operator) policy, persistent
+    OperatorUsage(Address, Address), // (user, operator) spending in the current period
+    KeeperTip,
+    NextDcaId,
+    DcaPlan(u64),
+    UserDcaPlans(Address),
+    NextLimitZapId,
+    LimitZap(u64),
+    UserLimitZaps(Address),
+    NextExitOrderId,
+    ExitOrder(u64),
+    UserExitOrders(Address),
+    HarvestFee,
+    HarvestConfig(Address),       // Keyed by the vault
+    LastHarvest(Address),         // Keyed by the vault, persistent
+    Position(Address, Address),   // (user, vault) cost basis, persistent
+    UserVaults(Address),          // Vaults with a recorded position per user
+    PositionNft,                  // Position NFT contract, when NFT mode is enabled
+    MinRebalanceBps,
+    VaultSamples(Address),        // Share price ring buffer per vault, persistent
+    AssetVaults(Address),         // Vaults registered for an asset
+    NextZapId,                    // Correlation id of the next zap or zap-out
+}
+
+pub fn extend_instance(env: &Env) {
+    env.storage()
+        .instance()
+        .extend_ttl(INSTANCE_LIFETIME_THRESHOLD, INSTANCE_BUMP_AMOUNT);
+}
+
+pub fn has_admin(env: &Env) -> bool {
+    env.storage().instance().has(&DataKey::Admin)
+}
+
+pub fn set_admin(env: &Env, admin: &Address) {
+    env.storage().instance().set(&DataKey::Admin, admin);
+}
+
+pub fn get_admin(env: &Env) -> Address {
+    env.storage()
+        .instance()
+        .get(&DataKey::Admin)
+        .unwrap_or_else(|| panic_with_error!(env, ZapError::NotInitialized))
+}
+
+/// Reads the admin and requires its authorization
+pub fn require_admin(env: &Env) -> Address {
+    let admin = get_admin(env);
+    admin.require_auth();
+    admin
+}
+
+/// Protocol addresses, falling back to the Futurenet defaults when none were set
+pub fn get_addresses(env: &Env) -> ContractAddresses {
+    env.storage()
+        .instance()
+        .get(&DataKey::Addresses)
+        .unwrap_or_else(|| ContractAddresses::futurenet(env))
+}
+
+pub fn set_addresses(env: &Env, addresses: &ContractAddresses) {
+    env.storage().instance().set(&DataKey::Addresses, addresses);
+}
+
+/// Known vault addresses, falling back to the Futurenet defaults when none were set
+pub fn get_vaults(env: &Env) -> VaultAddresses {
+    env.storage()
+        .instance()
+        .get(&DataKey::Vaults)
+        .unwrap_or_else(|| VaultAddresses::futurenet(env))
+}
+
+pub fn set_vaults(env: &Env, vaults: &VaultAddresses) {
+    env.storage().instance().set(&DataKey::Vaults, vaults);
+}
+
+/// Vaults the admin registered for `asset`, if any
+pub fn get_asset_vaults(env: &Env, asset: &Address) -> Option<Vec<Address>> {
+    env.storage().instance().get(&DataKey::AssetVaults(asset.clone()))
+}
+
+pub fn set_asset_vaults(env: &Env, asset: &Address, vaults: &Vec<Address>) {
+    let key = DataKey::AssetVaults(asset.clone());
+    if vaults.is_empty() {
+        env.storage().instance().remove(&key);
+    } else {
+        env.storage().instance().set(&key, vaults);
+    }
+}
+
+/// Take the reentrancy lock, failing if an entry point is already executing
+///
+/// The lock lives in temporary storage and is released by `unlock` before the
+/// entry point returns; a failing invocation rolls it back with everything else.
+pub fn lock(env: &Env) {
+    let storage = env.storage().temporary();
+    if storage.has(&DataKey::Lock) {
+        panic_with_error!(env, ZapError::Reentrancy);
+    }
+    storage.set(&DataKey::Lock, &true);
+}
+
+pub fn unlock(env: &Env) {
+    env.storage().temporary().remove(&DataKey::Lock);
+}
+
+/// Ids listed under a per-user index key in persistent storage
+pub fn get_ids(env: &Env, key: &DataKey) -> Vec<u64> {
+    env.storage()
+        .persistent()
+        .get(key)
+        .unwrap_or_else(|| Vec::new(env))
+}
+
+pub fn add_id(env: &Env, key: &DataKey, id: u64) {
+    let mut ids = get_ids(env, key);
+    ids.push_back(id);
+    set_ids(env, key, &ids);
+}
+
+pub fn remove_id(env: &Env, key: &DataKey, id: u64) {
+    let mut ids = get_ids(env, key);
+    if let Some(index) = ids.first_index_of(id) {
+        ids.remove(index);
+    }
+    set_ids(env, key, &ids);
+}
+
+fn set_ids(env: &Env, key: &DataKey, ids: &Vec<u64>) {
+    if ids.is_empty() {
+        env.storage().persistent().remove(key);
+    } else {
+        env.storage().persistent().set(key, ids);
+        env.storage()
+            .persistent()
+            .extend_ttl(key, PERSISTENT_LIFETIME_THRESHOLD, PERSISTENT_BUMP_AMOUNT);
+    }
+}
+
+/// Next id from the counter stored under `key`
+pub fn next_id(env: &Env, key: &DataKey) -> u64 {
+    let id: u64 = env.storage().instance().get(key).unwrap_or(0);
+    env.storage().instance().set(key, &(id + 1));
+    id
+}
@@ -0,0 +1,36 @@
+use soroban_sdk::{panic_with_error, vec, Address, Env, IntoVal, Symbol};
+
+use crate::errors::ZapError;
+use crate::native;
+
+/// Error the Stellar Asset Contract returns for accounts without a trustline
+const SAC_TRUSTLINE_MISSING: u32 = 13;
+
+/// Whether `account` can receive `token`
+///
+/// Only classic accounts holding a Stellar Asset Contract token need an authorized
+/// trustline; contracts, native XLM and custom tokens are always accepted.
+pub fn can_receive(env: &Env, token: &Address, account: &Address) -> bool {
+    if !native::is_account(account) || native::is_native(env, token) {
+        return true;
+    }
+
+    let result = env.try_invoke_contract::<bool, soroban_sdk::Error>(
+        token,
+        &Symbol::new(env, "authorized"),
+        vec![env, account.into_val(env)],
+    );
+    match result {
+        Ok(Ok(authorized)) => authorized,
+        Err(Ok(error)) => error != soroban_sdk::Error::from_contract_error(SAC_TRUSTLINE_MISSING),
+        // Tokens without `authorized` have no trustlines
+        _ => true,
+    }
+}
+
+/// Fail before moving funds if `account` cannot receive `token`
+pub fn require(env: &Env, token: &Address, account: &Address) {
+    if !can_receive(env, token, account) {
+        panic_with_error!(env, ZapError::MissingTrustline);
+    }
+}
@@ -0,0 +1,256 @@
+use soroban_sdk::{
+    contracttype, panic_with_error, vec, Address, Env, Symbol, TryIntoVal, Val, Vec,
+};
+
+use crate::errors::{OrPanic, ZapError};
+use crate::math::{self, Rounding};
+use crate::storage::{DataKey, PERSISTENT_BUMP_AMOUNT, PERSISTENT_LIFETIME_THRESHOLD};
+
+/// Fixed-point scale used for pool prices
+pub const PRICE_SCALE: i128 = 10_000_000;
+/// Number of observations kept per pool
+pub const MAX_OBSERVATIONS: u32 = 16;
+
+/// Manipulation guard configured for a token pair
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct PriceGuard {
+    pub pool: Address,       // Soroswap pair used to read reserves
+    pub tolerance_bps: u32,  // Max allowed spot vs TWAP deviation
+    pub window: u64,         // TWAP window in seconds
+    pub min_history: u64,    // Seconds of observations required before guarded swaps are allowed
+}
+
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Observation {
+    pub timestamp: u64,
+    pub price_cumulative: i128,
+}
+
+/// Rolling price accumulator recorded by the Zap contract itself
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct ObservationState {
+    pub last_price: i128,       // token_1 per token_0 of the pool, scaled by PRICE_SCALE
+    pub last_timestamp: u64,
+    pub price_cumulative: i128,
+    pub history: Vec<Observation>,
+}
+
+fn pair_key(token_a: &Address, token_b: &Address) -> DataKey {
+    if token_a < token_b {
+        DataKey::PriceGuard(token_a.clone(), token_b.clone())
+    } else {
+        DataKey::PriceGuard(token_b.clone(), token_a.clone())
+    }
+}
+
+pub fn get_guard(env: &Env, token_a: &Address, token_b: &Address) -> Option<PriceGuard> {
+    env.storage().instance().get(&pair_key(token_a, token_b))
+}
+
+pub fn set_guard(env: &Env, token_a: &Address, token_b: &Address, guard: &PriceGuard) {
+    if token_a == token_b
+        || guard.tolerance_bps == 0
+        || guard.window == 0
+        || guard.min_history == 0
+        || guard.min_history > guard.window
+    {
+        panic_with_error!(env, ZapError::InvalidConfig);
+    }
+    env.storage().instance().set(&pair_key(token_a, token_b), guard);
+}
+
+/// Remove a pair's guard and the observations of its pool, so a new guard starts a fresh history
+pub fn remove_guard(env: &Env, token_a: &Address, token_b: &Address) {
+    let key = pair_key(token_a, token_b);
+    if let Some(guard) = env.storage().instance().get::<_, PriceGuard>(&key) {
+        env.storage().persistent().remove(&DataKey::Observations(guard.pool));
+    }
+    env.storage().instance().remove(&key);
+}
+
+/// Current pool price from Soroswap pair reserves
+pub fn spot_price(env: &Env, pool: &Address) -> i128 {
+    let result: Val = env.invoke_contract(pool, &Symbol::new(env, "get_reserves"), vec![env]);
+    let (reserve_0, reserve_1): (i128, i128) = result.try_into_val(env).unwrap();
+
+    if reserve_0 <= 0 || reserve_1 <= 0 {
+        panic_with_error!(env, ZapError::InvalidReserves);
+    }
+
+    let price = math::mul_div(reserve_1, PRICE_SCALE, reserve_0, Rounding::Down).or_panic(env);
+    if price == 0 {
+        panic_with_error!(env, ZapError::InvalidReserves);
+    }
+    price
+}
+
+fn load_state(env: &Env, pool: &Address) -> Option<ObservationState> {
+    env.storage()
+        .persistent()
+        .get(&DataKey::Observations(pool.clone()))
+}
+
+/// Accumulated price up to `now`, using the price in effect before this ledger
+fn cumulative_at(state: &ObservationState, now: u64) -> Result<i128, ZapError> {
+    let elapsed = now.saturating_sub(state.last_timestamp) as i128;
+    math::add(state.price_cumulative, math::mul(state.last_price, elapsed)?)
+}
+
+/// Time-weighted average price over roughly `window` seconds
+///
+/// None unless the oldest usable observation is at least `min_history` seconds old, so a pool
+/// observed only in this ledger (or shortly before) never yields an average.
+pub fn twap(
+    state: &ObservationState,
+    now: u64,
+    window: u64,
+    min_history: u64,
+) -> Result<Option<i128>, ZapError> {
+    let target = now.saturating_sub(window);
+    let Some(mut reference) = state.history.first() else {
+        return Ok(None);
+    };
+    for observation in state.history.iter().rev() {
+        if observation.timestamp <= target {
+            reference = observation;
+            break;
+        }
+    }
+
+    if reference.timestamp >= now || now - reference.timestamp < min_history {
+        return Ok(None);
+    }
+
+    let elapsed = (now - reference.timestamp) as i128;
+    let accumulated = math::sub(cumulative_at(state, now)?, reference.price_cumulative)?;
+    Ok(Some(accumulated / elapsed))
+}
+
+/// Deviation between two positive prices in basis points
+pub fn deviation_bps(spot: i128, reference: i128) -> Result<i128, ZapError> {
+    let difference = math::sub(spot, reference)?
+        .checked_abs()
+        .ok_or(ZapError::ArithmeticOverflow)?;
+    math::mul_div(difference, math::BPS_DENOMINATOR, reference, Rounding::Down)
+}
+
+/// The pool's TWAP under `guard`, None until it has `min_history` of observations
+fn current_twap(env: &Env, guard: &PriceGuard, now: u64) -> Option<i128> {
+    load_state(env, &guard.pool)
+        .and_then(|state| twap(&state, now, guard.window, guard.min_history).or_panic(env))
+}
+
+/// Refuse a spot price further than the guard's tolerance from the TWAP
+fn check_deviation(env: &Env, guard: &PriceGuard, spot: i128, average: i128) {
+    if average <= 0 || deviation_bps(spot, average).or_panic(env) > guard.tolerance_bps as i128 {
+        panic_with_error!(env, ZapError::PriceDeviationTooHigh);
+    }
+}
+
+/// Fold the current spot price into the pool's accumulator
+fn record(env: &Env, pool: &Address, window: u64, spot: i128, now: u64) {
+    let key = DataKey::Observations(pool.clone());
+    let state = match load_state(env, pool) {
+        Some(mut state) => {
+            state.price_cumulative = cumulative_at(&state, now).or_panic(env);
+            state.last_price = spot;
+            state.last_timestamp = now;
+
+            // Only keep a new checkpoint once enough time passed so history spans the window
+            let granularity = (window / MAX_OBSERVATIONS as u64).max(1);
+            let last_checkpoint = state.history.last().map(|o| o.timestamp).unwrap_or(0);
+            if now >= last_checkpoint.saturating_add(granularity) {
+                state.history.push_back(Observation {
+                    timestamp: now,
+                    price_cumulative: state.price_cumulative,
+                });
+                while state.history.len() > MAX_OBSERVATIONS {
+                    state.history.pop_front();
+                }
+            }
+            state
+        }
+        None => ObservationState {
+            last_price: spot,
+            last_timestamp: now,
+            price_cumulative: 0,
+            history: vec![
+                env,
+                Observation {
+                    timestamp: now,
+                    price_cumulative: 0,
+                },
+            ],
+        },
+    };
+
+    env.storage().persistent().set(&key, &state);
+    env.storage()
+        .persistent()
+        .extend_ttl(&key, PERSISTENT_LIFETIME_THRESHOLD, PERSISTENT_BUMP_AMOUNT);
+}
+
+/// Record an observation for a guarded pair
+///
+/// Once the pool has a TWAP the spot price must be within the guard's tolerance of it,
+/// as for a guarded swap, so a pool moved and restored around this call cannot poison
+/// the average. Until then observations seed the history unchecked.
+pub fn observe(env: &Env, token_a: &Address, token_b: &Address) -> i128 {
+    let guard = get_guard(env, token_a, token_b)
+        .unwrap_or_else(|| panic_with_error!(env, ZapError::InvalidConfig));
+    let now = env.ledger().timestamp();
+    let spot = spot_price(env, &guard.pool);
+    if let Some(average) = current_twap(env, &guard, now) {
+        check_deviation(env, &guard, spot, average);
+    }
+    record(env, &guard.pool, guard.window, spot, now);
+    spot
+}
+
+/// Value of `amount` of `from` in `to` at the TWAP of the pair's guard
+///
+/// Keeper-executed zaps floor their output on this instead of a quote taken in the same
+/// transaction, which whoever submits it could sandwich. Fails with
+/// `ZapError::MissingPriceGuard` when the pair is not guarded and with
+/// `ZapError::InsufficientPriceHistory` until the guard has `min_history` of observations.
+/// Prices are token_1 per token_0 of a Soroswap pair, whose tokens are sorted by address.
+pub fn value(env: &Env, from: &Address, to: &Address, amount: i128) -> i128 {
+    let guard = get_guard(env, from, to)
+        .unwrap_or_else(|| panic_with_error!(env, ZapError::MissingPriceGuard));
+    let average = current_twap(env, &guard, env.ledger().timestamp());
+    let Some(average) = average.filter(|average| *average > 0) else {
+        panic_with_error!(env, ZapError::InsufficientPriceHistory);
+    };
+
+    if from < to {
+        math::mul_div(amount, average, PRICE_SCALE, Rounding::Down).or_panic(env)
+    } else {
+        math::mul_div(amount, PRICE_SCALE, average, Rounding::Down).or_panic(env)
+    }
+}
+
+/// Refuse the swap if any guarded hop of `path` trades away from its TWAP
+///
+/// A guarded hop without `min_history` seconds of observations fails with
+/// `ZapError::InsufficientPriceHistory`; the history has to be seeded with `observe` first.
+pub fn check_route(env: &Env, path: &Vec<Address>) {
+    let now = env.ledger().timestamp();
+
+    for i in 1..path.len() {
+        let token_a = path.get_unchecked(i - 1);
+        let token_b = path.get_unchecked(i);
+        let Some(guard) = get_guard(env, &token_a, &token_b) else {
+            continue;
+        };
+
+        let spot = spot_price(env, &guard.pool);
+        let Some(average) = current_twap(env, &guard, now) else {
+            panic_with_error!(env, ZapError::InsufficientPriceHistory);
+        };
+        check_deviation(env, &guard, spot, average);
+        record(env, &guard.pool, guard.window, spot, now);
+    }
+}
@@ -13,6 +13,334 @@ export interface ZapResult {
   amount_swapped: string;
   vault_shares: string;
   vault_address: string;
+  price_impact_bps: number;
+  protocol_fee: string;
+}
+
+export interface ZapOutResult {
+  shares_burned: string;
+  assets_withdrawn: string;
+  amount_out: string;
+  price_impact_bps: number;
+  protocol_fee: string;
+}
+
+export interface SwapRoute {
+  path: string[];
+  pools: string[];
+  distribution: number[];
+  expected_amount: string;
+  minimum_amount: string;
+  price_impact: number;
+  venue: 'Aggregator' | 'Router';
+}
+
+export interface SubRoute {
+  weight_bps: number;
+  path: string[];
+  distribution: number[];
+  venue: 'Aggregator' | 'Router';
+}
+
+export interface MinOutSuggestion {
+  slippage_bps: number;
+  min_amount_out: string;
+  min_vault_shares: string;
+}
+
+export interface ZapQuote {
+  amount_in: string;
+  protocol_fee: string;
+  expected_amount_out: string;
+  price_impact_bps: number;
+  expected_vault_shares: string;
+  route: SwapRoute;
+  min_out_suggestions: MinOutSuggestion[];
+}
+
+export interface OperatorPolicy {
+  vaults: string[];
+  tokens: string[];
+  max_amount_per_period: string;
+  max_slippage_bps: number;
+  period: number;
+  expires_at: number;
+}
+
+export interface DcaPlan {
+  id: number;
+  owner: string;
+  from_token: string;
+  to_token: string;
+  vault: string;
+  amount_per_interval: string;
+  interval: number;
+  slippage_bps: number;
+  remaining: string;
+  next_execution: number;
+}
+
+export interface LimitZap {
+  id: number;
+  owner: string;
+  from_token: string;
+  amount_in: string;
+  to_token: string;
+  vault: string;
+  min_amount_out: string;
+  expires_at: number;
+}
+
+export type ExitTrigger = 'Below' | 'Above';
+
+export interface ExitOrder {
+  id: number;
+  owner: string;
+  vault: string;
+  shares: string;
+  vault_asset: string;
+  to_token: string;
+  trigger: ExitTrigger;
+  threshold: string;
+  slippage_bps: number;
+}
+
+export interface HarvestResult {
+  compounded: string;
+  shares: string;
+  keeper_fee: string;
+}
+
+export interface Position {
+  vault: string;
+  assets_deposited: string;
+  shares_received: string;
+  assets_withdrawn: string;
+  shares_withdrawn: string;
+  updated_at: number;
+}
+
+export interface PositionReceipt {
+  vault: string;
+  asset: string;
+  shares: string;
+  entry_price: string;
+  minted_at: number;
+}
+
+export interface NftZapResult {
+  token_id: number;
+  zap: ZapResult;
+}
+
+export interface BasketVault {
+  vault: string;
+  asset: string;
+  weight_bps: number;
+}
+
+export interface RebalanceLeg {
+  vault: string;
+  shares: string;
+}
+
+export interface RebalanceResult {
+  total_value: string;
+  withdrawals: RebalanceLeg[];
+  deposits: RebalanceLeg[];
+}
+
+export interface VaultSample {
+  timestamp: number;
+  total_assets: string;
+  total_shares: string;
+}
+
+export interface VaultConstraints {
+  allowed_vaults: string[];
+  min_tvl: string;
+  max_fee_bps: number;
+  apy_window: number;
+  min_amount_out: string;
+  max_price_impact_bps?: number;
+}
+
+export interface BestVault {
+  vault: string;
+  apy_bps: string;
+  tvl: string;
+  fee_bps: number;
+}
+
+export interface BestVaultResult {
+  best: BestVault;
+  zap: ZapResult;
+}
+
+export interface SwapEvent {
+  version: number;
+  zap_id: string;
+  token_in: string;
+  token_out: string;
+  amount_in: string;
+  amount_out: string;
+  price_impact_bps: number;
+}
+
+export interface DepositEvent {
+  version: number;
+  zap_id: string;
+  asset: string;
+  amount: string;
+  receiver: string;
+  shares: string;
+}
+
+export interface WithdrawEvent {
+  version: number;
+  zap_id: string;
+  shares: string;
+  assets: string;
+}
+
+export interface ZapCompletedEvent {
+  version: number;
+  zap_id: string;
+  receiver: string;
+  from_token: string;
+  amount_in: string;
+  to_token: string;
+  amount_swapped: string;
+  vault_shares: string;
+  price_impact_bps: number;
+  protocol_fee: string;
+}
+
+export interface ZapOutCompletedEvent {
+  version: number;
+  zap_id: string;
+  vault_asset: string;
+  to_token: string;
+  shares_burned: string;
+  assets_withdrawn: string;
+  amount_out: string;
+  price_impact_bps: number;
+  protocol_fee: string;
+}
+
+export interface OperatorSetEvent {
+  version: number;
+  user: string;
+  operator: string;
+  policy: OperatorPolicy;
+}
+
+export interface OperatorRevokedEvent {
+  version: number;
+  user: string;
+  operator: string;
+}
+
+export interface DcaCreatedEvent {
+  version: number;
+  plan: DcaPlan;
+}
+
+export interface DcaExecutedEvent {
+  version: number;
+  zap_id: string;
+  plan_id: number;
+  keeper: string;
+  slice: string;
+  tip: string;
+  remaining: string;
+}
+
+export interface DcaCancelledEvent {
+  version: number;
+  plan_id: number;
+  owner: string;
+  refunded: string;
+}
+
+export interface LimitZapCreatedEvent {
+  version: number;
+  order: LimitZap;
+}
+
+export interface LimitZapFilledEvent {
+  version: number;
+  zap_id: string;
+  order_id: number;
+  owner: string;
+  amount_swapped: string;
+  vault_shares: string;
+}
+
+// Published as limit_zap_cancelled or limit_zap_refunded
+export interface LimitZapClosedEvent {
+  version: number;
+  order_id: number;
+  owner: string;
+  amount_in: string;
+}
+
+export interface ExitOrderCreatedEvent {
+  version: number;
+  order: ExitOrder;
+}
+
+export interface ExitOrderExecutedEvent {
+  version: number;
+  zap_id: string;
+  order_id: number;
+  keeper: string;
+  value: string;
+  tip: string;
+  amount_out: string;
+}
+
+export interface ExitOrderCancelledEvent {
+  version: number;
+  order_id: number;
+  owner: string;
+  shares: string;
+}
+
+export interface HarvestedEvent {
+  version: number;
+  zap_id: string;
+  vault: string;
+  keeper: string;
+  result: HarvestResult;
+}
+
+export interface MigratedEvent {
+  version: number;
+  zap_id: string;
+  user: string;
+  from_vault: string;
+  shares: string;
+  to_vault: string;
+  vault_shares: string;
+}
+
+export interface RebalancedEvent {
+  version: number;
+  zap_id: string;
+  user: string;
+  result: RebalanceResult;
+}
+
+export interface VaultSnapshotEvent {
+  version: number;
+  sample: VaultSample;
+}
+
+export interface BestVaultChosenEvent {
+  version: number;
+  zap_id: string;
+  best: BestVault;
 }
 
 export interface VaultInfo {
@@ -44,6 +372,8 @@ export interface ZapParams {
   min_amount_out: string;
   swap_path: string[];
   distribution: number[];
+  max_price_impact_bps?: number;
+  receiver?: string;
 }
 
 export interface WalletState {